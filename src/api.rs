@@ -0,0 +1,135 @@
+//! The operations behind the `dev` commands, returning data instead of printing
+//!
+//! `config_dir` is where the global config and registered projects are
+//! stored, normally [`config_dir`](crate::config::config_dir); `git` is
+//! normally [`backend()`](crate::git::backend()). Paths named `repo` may be
+//! any directory inside a repository or one of its worktrees.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{EffectiveConfig, Overrides, ProjectConfig, WorktreeEnv};
+use crate::error::{DevError, Result};
+use crate::git::{self, GitBackend, WorktreeInfo, WorktreeLayout};
+use crate::package_manager::DepsStatus;
+
+/// The worktrees of a repository, as shown by `dev --list`
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WorktreeList {
+    /// Root of the bare hub, for repositories using that layout
+    pub hub: Option<PathBuf>,
+    /// Main worktree first; a hub's bare repository is left out
+    pub worktrees: Vec<WorktreeInfo>,
+}
+
+/// List the worktrees of a repository with their status already computed
+pub fn list_worktrees(git: &dyn GitBackend, repo: &Path) -> Result<WorktreeList> {
+    let list = find_worktrees(git, repo)?;
+    git::load_statuses(git, &list.worktrees);
+    Ok(list)
+}
+
+/// [`list_worktrees`], along with the state of each worktree's dependencies
+/// (see [`EffectiveConfig::deps_status`]), checked alongside its status
+///
+/// A worktree whose dependencies can't be checked, or not in time, has
+/// None, as does one without any.
+pub fn list_worktrees_with_deps(
+    config_dir: &Path,
+    git: &dyn GitBackend,
+    repo: &Path,
+) -> Result<(WorktreeList, Vec<Option<DepsStatus>>)> {
+    let list = find_worktrees(git, repo)?;
+    let settings = EffectiveConfig::load(config_dir, git, repo, &Overrides::default())?;
+
+    let deps = git::load_statuses_with(git, &list.worktrees, move |path| {
+        settings.deps_status(path).ok().flatten()
+    });
+
+    Ok((list, deps))
+}
+
+/// The worktrees of a repository, without their status
+fn find_worktrees(git: &dyn GitBackend, repo: &Path) -> Result<WorktreeList> {
+    if !git::is_git_repository(repo) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    let mut worktrees = git.list_worktrees(repo)?;
+
+    // In a bare hub the bare repository is plumbing, not a worktree to show
+    let hub = git::find_hub_root(repo);
+    if hub.is_some() {
+        worktrees.retain(|wt| !wt.is_bare);
+    }
+
+    Ok(WorktreeList { hub, worktrees })
+}
+
+/// Find the worktree that has `branch` checked out
+pub fn find_worktree(git: &dyn GitBackend, repo: &Path, branch: &str) -> Result<WorktreeInfo> {
+    if !git::is_git_repository(repo) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    git::find_worktree_by_branch(git, repo, branch)?
+        .ok_or_else(|| DevError::WorktreeNotFound(branch.to_string()))
+}
+
+/// Create a worktree for `branch` where the configured layout puts it,
+/// returning its path
+///
+/// With `create_branch` a new branch is started from HEAD; otherwise an
+/// existing local branch, or `origin/<branch>`, is checked out.
+pub fn create_worktree(
+    config_dir: &Path,
+    git: &dyn GitBackend,
+    repo: &Path,
+    branch: &str,
+    create_branch: bool,
+) -> Result<PathBuf> {
+    if !git::is_git_repository(repo) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    let branch = branch.trim();
+    if branch.is_empty() {
+        return Err(DevError::Other("Branch name is required".to_string()));
+    }
+
+    if create_branch && git::branch_exists_locally(git, repo, branch) {
+        return Err(DevError::BranchExistsLocally(branch.to_string()));
+    }
+
+    let path = WorktreeLayout::load(config_dir, git, repo)?.path_for(branch);
+    git::create_worktree(git, repo, branch, &path, create_branch)?;
+
+    Ok(path)
+}
+
+/// Environment injected into processes run in a worktree: the one with
+/// `branch` checked out, or the one containing `repo`
+pub fn worktree_env(
+    config_dir: &Path,
+    git: &dyn GitBackend,
+    repo: &Path,
+    branch: Option<&str>,
+) -> Result<WorktreeEnv> {
+    let worktree = match branch {
+        Some(branch) => find_worktree(git, repo, branch)?.path,
+        None => git::get_repository_root(repo)?,
+    };
+
+    let settings = EffectiveConfig::load(config_dir, git, &worktree, &Overrides::default())?;
+    Ok(settings.env_for(git, &worktree))
+}
+
+/// All registered projects
+pub fn list_projects(config_dir: &Path) -> Result<Vec<ProjectConfig>> {
+    ProjectConfig::list_all(config_dir)
+}
+
+/// Look up a registered project by name
+pub fn find_project(config_dir: &Path, name: &str) -> Result<ProjectConfig> {
+    ProjectConfig::load(config_dir, name)
+}
@@ -1,6 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+use crate::config::global::Shell;
+use crate::config::Overrides;
+use crate::ui::output::OutputFormat;
+
 #[derive(Parser)]
 #[command(name = "dev")]
 #[command(about = "Git worktree and project management CLI")]
@@ -9,7 +13,6 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
-
     /// Target project or branch name
     #[arg(value_name = "TARGET")]
     pub target: Option<String>,
@@ -22,13 +25,85 @@ pub struct Cli {
     #[arg(short, long, value_name = "BRANCH")]
     pub create: Option<String>,
 
+    /// With --create, check out the remote branch of that name if there is one
+    #[arg(long, requires = "create", conflicts_with = "diverge")]
+    pub track_remote: bool,
+
+    /// With --create, start a new local branch even if the remote has one
+    #[arg(long, requires = "create")]
+    pub diverge: bool,
+
     /// Remove unused worktrees
     #[arg(long)]
     pub cleanup: bool,
 
-    /// Generate shell completion script
-    #[arg(long)]
-    pub completion: bool,
+    /// With --cleanup, also remove worktrees with no commits in this many days
+    #[arg(long, value_name = "DAYS", requires = "cleanup")]
+    pub older_than: Option<u32>,
+
+    /// With --cleanup, delete the branches of merged worktrees without asking
+    #[arg(long, requires = "cleanup", conflicts_with = "keep_branches")]
+    pub delete_branches: bool,
+
+    /// With --cleanup, keep the branches of removed worktrees
+    #[arg(long, requires = "cleanup")]
+    pub keep_branches: bool,
+
+    /// Generate shell completion script (defaults to the configured shell)
+    #[arg(long, value_name = "SHELL", num_args = 0..=1, value_enum)]
+    pub completion: Option<Option<Shell>>,
+
+    /// Editor to open worktrees with (overrides config)
+    #[arg(long, global = true, value_name = "EDITOR")]
+    pub editor: Option<String>,
+
+    /// Install dependencies (overrides config)
+    #[arg(long, global = true, overrides_with = "no_install")]
+    pub install: bool,
+
+    /// Don't install dependencies (overrides config)
+    #[arg(long, global = true, overrides_with = "install")]
+    pub no_install: bool,
+
+    /// Don't run hooks
+    #[arg(long, global = true)]
+    pub no_hooks: bool,
+
+    /// Output format for listings and errors
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Go ahead without asking, taking the default answer to other questions
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Never prompt; fail when a flag is needed to answer a question
+    /// (implied when stdin is not a terminal)
+    #[arg(long, global = true)]
+    pub no_input: bool,
+}
+
+impl Cli {
+    /// Settings given as flags, overriding project and global config
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            editor: self.editor.clone(),
+            editor_wait: None,
+            auto_install_deps: either(self.install, self.no_install),
+            no_hooks: self.no_hooks,
+        }
+    }
+}
+
+/// The answer given by a pair of opposing flags, if either was passed
+pub fn either(yes: bool, no: bool) -> Option<bool> {
+    if yes {
+        Some(true)
+    } else if no {
+        Some(false)
+    } else {
+        None
+    }
 }
 
 #[derive(Subcommand)]
@@ -42,18 +117,144 @@ pub enum Commands {
         #[arg(long)]
         clone: Option<String>,
 
+        /// With --clone, use a bare repository hub with one worktree per branch
+        #[arg(long, requires = "clone")]
+        bare_layout: bool,
+
         /// Initialize in existing directory
         #[arg(long)]
         existing: Option<PathBuf>,
 
+        /// Set up devbox without asking
+        #[arg(long, conflicts_with = "no_devbox")]
+        devbox: bool,
+
         /// Skip devbox setup
         #[arg(long)]
         no_devbox: bool,
     },
 
+    /// Remove a worktree and optionally its branch
+    Remove {
+        /// Branch whose worktree should be removed
+        branch: String,
+
+        /// Remove even with uncommitted changes or unpushed commits
+        #[arg(short, long)]
+        force: bool,
+
+        /// Delete the local branch without asking
+        #[arg(long, conflicts_with = "keep_branch")]
+        delete_branch: bool,
+
+        /// Keep the local branch without asking
+        #[arg(long)]
+        keep_branch: bool,
+
+        /// Delete the remote branch without asking
+        #[arg(long, conflicts_with = "keep_remote")]
+        delete_remote: bool,
+
+        /// Keep the remote branch without asking
+        #[arg(long)]
+        keep_remote: bool,
+    },
+
+    /// Move existing worktrees to match the configured path template
+    MigrateWorktrees {
+        /// Show what would be moved without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Install dependencies in a worktree if its lockfile changed since the last install
+    Install {
+        /// Branch whose worktree to use (defaults to the current worktree)
+        branch: Option<String>,
+
+        /// Reinstall even if the dependencies are up to date
+        #[arg(short, long)]
+        force: bool,
+
+        /// Only install monorepo packages whose path matches this glob
+        /// (repeatable); remembered for the worktree
+        #[arg(
+            short,
+            long = "package",
+            value_name = "PATTERN",
+            conflicts_with = "all_packages"
+        )]
+        packages: Vec<String>,
+
+        /// Install every package again, forgetting the worktree's --package
+        #[arg(long)]
+        all_packages: bool,
+    },
+
+    /// Open a worktree, or a file in it, in the editor
+    Open {
+        /// Branch whose worktree to open (defaults to the current worktree)
+        branch: Option<String>,
+
+        /// File to open, relative to the worktree, with an optional line
+        #[arg(long, value_name = "PATH[:LINE]")]
+        file: Option<String>,
+
+        /// Wait for the editor to be closed (overrides config)
+        #[arg(long)]
+        wait: bool,
+    },
+
+    /// Copy the project's worktree_files from the main worktree into the others
+    SyncFiles {
+        /// Only this branch's worktree (defaults to all of them)
+        branch: Option<String>,
+
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print the environment injected into processes for a worktree
+    Env {
+        /// Branch whose worktree to use (defaults to the current worktree)
+        branch: Option<String>,
+
+        /// Syntax of the printed variables (ignored with --format json)
+        #[arg(long, value_enum, default_value_t = EnvSyntax::Sh)]
+        syntax: EnvSyntax,
+    },
+
+    /// Print the shell wrapper that lets `dev` change directory
+    ShellInit {
+        /// Shell to generate the wrapper for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
     /// List registered projects
     Projects,
 
+    /// Report the external tools dev uses and check config, registered
+    /// projects and worktrees for problems
+    Doctor {
+        /// Repair the problems that can be
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Print completion candidates for the given words (used by completion scripts)
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Words typed after `dev`, the last one being completed
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+
+    /// Enter devbox in the current worktree with its environment (used by the shell wrapper)
+    #[command(name = "__devbox-shell", hide = true)]
+    DevboxShell,
+
     /// Show/edit configuration
     Config {
         /// Set a config value (format: key=value)
@@ -63,5 +264,20 @@ pub enum Commands {
         /// Get a config value
         #[arg(long)]
         get: Option<String>,
+
+        /// Show the effective settings for the current project and where each comes from
+        #[arg(long, conflicts_with_all = ["set", "get"])]
+        effective: bool,
     },
 }
+
+/// Variable syntax for `dev env`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EnvSyntax {
+    /// `export KEY='value'` lines for bash/zsh
+    Sh,
+    /// `set -gx KEY 'value'` lines for fish
+    Fish,
+    /// `KEY="value"` lines for .env files and direnv
+    Dotenv,
+}
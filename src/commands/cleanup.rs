@@ -1,105 +1,238 @@
+use std::path::Path;
+
+use chrono::Utc;
 use colored::Colorize;
 
+use crate::context::Context;
 use crate::error::{DevError, Result};
-use crate::git::{is_git_repository, list_worktrees, prune_worktrees, remove_worktree};
-use crate::ui::output::{info, success};
-use crate::ui::prompts::Prompts;
+use crate::git::{
+    branch_exists_on_remote, get_main_worktree, is_git_repository, load_statuses, GitBackend,
+    WorktreeInfo,
+};
+
+/// Why a worktree is a candidate for cleanup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CleanupReason {
+    Missing,
+    Detached,
+    Merged,
+    SquashMerged,
+    UpstreamGone,
+    Stale,
+}
+
+impl CleanupReason {
+    /// Order in which groups are reported
+    const ALL: [CleanupReason; 6] = [
+        CleanupReason::Missing,
+        CleanupReason::Detached,
+        CleanupReason::Merged,
+        CleanupReason::SquashMerged,
+        CleanupReason::UpstreamGone,
+        CleanupReason::Stale,
+    ];
+
+    fn heading(&self, default_branch: &str, older_than: Option<u32>) -> String {
+        match self {
+            CleanupReason::Missing => "Directory missing".to_string(),
+            CleanupReason::Detached => "Detached HEAD".to_string(),
+            CleanupReason::Merged => format!("Merged into {}", default_branch),
+            CleanupReason::SquashMerged => format!("Squash-merged into {}", default_branch),
+            CleanupReason::UpstreamGone => "Upstream deleted on remote".to_string(),
+            CleanupReason::Stale => {
+                format!("No commits in {} days", older_than.unwrap_or_default())
+            }
+        }
+    }
 
-pub fn run() -> Result<()> {
-    let current_dir = std::env::current_dir()?;
+    /// Whether the branch's work is known to be in the default branch
+    ///
+    /// A deleted upstream says nothing about commits that were never
+    /// pushed, so those branches are kept.
+    fn branch_is_disposable(&self) -> bool {
+        matches!(self, CleanupReason::Merged | CleanupReason::SquashMerged)
+    }
+}
 
-    if !is_git_repository(&current_dir) {
+/// Remove worktrees that are merged, gone or stale
+///
+/// `delete_branches` says whether to delete the branches of merged
+/// worktrees too; None asks.
+pub fn run(ctx: &Context, older_than: Option<u32>, delete_branches: Option<bool>) -> Result<()> {
+    let out = &ctx.out;
+    let repo = ctx.cwd.as_path();
+
+    if !is_git_repository(repo) {
         return Err(DevError::NotGitRepository);
     }
 
+    let worktrees = ctx.git.list_worktrees(repo)?;
+    let main_path = get_main_worktree(ctx.git, repo)?.map(|wt| wt.path);
+
+    let default_branch = ctx.git.default_branch(repo);
+    let target = if branch_exists_on_remote(ctx.git, repo, &default_branch) {
+        format!("origin/{}", default_branch)
+    } else {
+        default_branch.clone()
+    };
 
-    let worktrees = list_worktrees()?;
+    out.info("Checking worktrees...");
+    load_statuses(ctx.git, &worktrees);
 
-    // Find problematic worktrees
-    let problematic: Vec<_> = worktrees
+    // Find worktrees worth cleaning up
+    let candidates: Vec<(&WorktreeInfo, CleanupReason)> = worktrees
         .iter()
-        .filter(|wt| !wt.path.exists() || wt.is_detached)
+        .filter(|wt| !wt.is_bare && !wt.is_current && Some(&wt.path) != main_path.as_ref())
+        .filter(|wt| wt.branch.as_deref() != Some(default_branch.as_str()))
+        .filter_map(|wt| {
+            classify(ctx.git, repo, wt, &target, older_than).map(|reason| (wt, reason))
+        })
         .collect();
 
-    if problematic.is_empty() {
-        success("All worktrees are in good condition");
+    if candidates.is_empty() {
+        out.success("All worktrees are in good condition");
         return Ok(());
     }
 
-    println!(
-        "{}",
-        format!("Found {} problematic worktree(s):\n", problematic.len()).yellow()
-    );
+    out.println(format!("Found {} worktree(s) to clean up:\n", candidates.len()).yellow());
 
-    for wt in &problematic {
-        let reason = if !wt.path.exists() {
-            "directory missing".red()
-        } else {
-            "detached HEAD".yellow()
-        };
+    for reason in CleanupReason::ALL {
+        let group: Vec<_> = candidates.iter().filter(|(_, r)| *r == reason).collect();
+        if group.is_empty() {
+            continue;
+        }
 
-        let branch_name = wt.branch.as_deref().unwrap_or("unknown");
-        println!(
-            "  {} - {} ({})",
-            branch_name.cyan(),
-            wt.path.display(),
-            reason
-        );
-    }
+        out.println(format!(
+            "{} ({})",
+            reason.heading(&default_branch, older_than).bold(),
+            group.len()
+        ));
+
+        for (wt, _) in group {
+            let branch_name = wt.branch.as_deref().unwrap_or("unknown");
+            out.println(format!("  {} - {}", branch_name.cyan(), wt.path.display()));
+        }
 
-    println!();
+        out.println("");
+    }
 
-    let prompts = Prompts::new();
-    let should_clean = prompts.confirm("Remove these problematic worktrees?", false)?;
+    let should_clean = ctx.prompter.proceed("Remove these worktrees?")?;
 
     if !should_clean {
-        println!("{}", "Cleanup cancelled".yellow());
+        out.println("Cleanup cancelled".yellow());
         return Ok(());
     }
 
-    info("Cleaning up worktrees...");
+    let delete_branches = candidates.iter().any(|(_, r)| r.branch_is_disposable())
+        && match delete_branches {
+            Some(delete) => delete,
+            None => ctx.prompter.confirm(
+                "Also delete branches that were merged?",
+                false,
+                "--delete-branches or --keep-branches",
+            )?,
+        };
+
+    out.info("Cleaning up worktrees...");
 
     let mut cleaned = 0;
     let mut failed = 0;
 
-    for wt in &problematic {
+    for (wt, reason) in &candidates {
         let branch_name = wt.branch.as_deref().unwrap_or("unknown");
 
-        match remove_worktree(&wt.path) {
+        match ctx.git.remove_worktree(repo, &wt.path, false) {
             Ok(()) => {
-                println!(
+                out.println(format!(
                     "{} Removed: {} ({})",
                     "✓".green(),
                     branch_name,
                     wt.path.display()
-                );
+                ));
                 cleaned += 1;
             }
             Err(e) => {
-                println!(
+                out.println(format!(
                     "{} Failed to remove: {} - {}",
                     "✗".red(),
                     branch_name,
                     e
-                );
+                ));
                 failed += 1;
+                continue;
+            }
+        }
+
+        if delete_branches && reason.branch_is_disposable() {
+            if let Some(ref branch) = wt.branch {
+                match ctx.git.delete_branch(repo, branch, true) {
+                    Ok(()) => out.println(format!("{} Deleted branch: {}", "✓".green(), branch)),
+                    Err(e) => out.println(format!("{} {}", "✗".red(), e)),
+                }
             }
         }
     }
 
-    println!();
-    success(&format!("Cleanup complete: {} removed", cleaned));
+    out.println("");
+    out.success(&format!("Cleanup complete: {} removed", cleaned));
 
     if failed > 0 {
-        println!("{}", format!("✗ Failed to remove: {}", failed).red());
+        out.println(format!("✗ Failed to remove: {}", failed).red());
     }
 
     // Run git worktree prune
-    info("Running git worktree prune...");
-    match prune_worktrees() {
-        Ok(()) => success("Pruned worktree references"),
-        Err(e) => println!("{}", format!("Warning: git worktree prune failed: {}", e).yellow()),
+    out.info("Running git worktree prune...");
+    match ctx.git.prune_worktrees(repo) {
+        Ok(()) => out.success("Pruned worktree references"),
+        Err(e) => out.println(format!("Warning: git worktree prune failed: {}", e).yellow()),
     }
 
     Ok(())
 }
+
+/// Determine whether (and why) a worktree should be cleaned up
+fn classify(
+    git: &dyn GitBackend,
+    repo: &Path,
+    wt: &WorktreeInfo,
+    target: &str,
+    older_than: Option<u32>,
+) -> Option<CleanupReason> {
+    if !wt.path.exists() {
+        return Some(CleanupReason::Missing);
+    }
+
+    // Never reap work in progress (or worktrees whose state is unknown)
+    match wt.status() {
+        Some(status) if !status.has_changes() && status.operation.is_none() => {}
+        _ => return None,
+    }
+
+    let branch = match wt.branch {
+        Some(ref branch) if !wt.is_detached => branch,
+        _ => return Some(CleanupReason::Detached),
+    };
+
+    // A branch fresh from the target has nothing of its own to have merged
+    if git.has_own_commits(repo, branch, target) {
+        if git.is_branch_merged(repo, branch, target) {
+            return Some(CleanupReason::Merged);
+        }
+
+        if git.is_squash_merged(repo, branch, target) {
+            return Some(CleanupReason::SquashMerged);
+        }
+    }
+
+    if git.is_upstream_gone(repo, branch) {
+        return Some(CleanupReason::UpstreamGone);
+    }
+
+    if let (Some(days), Some(date)) = (older_than, git.last_commit_date(repo, branch)) {
+        if (Utc::now() - date).num_days() >= i64::from(days) {
+            return Some(CleanupReason::Stale);
+        }
+    }
+
+    None
+}
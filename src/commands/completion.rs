@@ -1,9 +1,30 @@
+use crate::config::global::Shell;
+use crate::config::GlobalConfig;
+use crate::context::Context;
 use crate::error::Result;
-use crate::shell::completion::generate_zsh_completion;
+use crate::shell::completion::{complete as complete_words, completion_script};
+
+/// Print the completion script for a shell (or the configured one)
+pub fn run(ctx: &Context, shell: Option<Shell>) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => GlobalConfig::load(&ctx.config_dir)?.shell,
+    };
+
+    ctx.out.println(completion_script(shell));
+    Ok(())
+}
+
+/// Print candidates for `dev __complete`, one per line as `value\tdescription`
+pub fn complete(ctx: &Context, words: &[String]) -> Result<()> {
+    for candidate in complete_words(ctx, words) {
+        if candidate.description.is_empty() {
+            ctx.out.println(&candidate.value);
+        } else {
+            ctx.out
+                .println(format!("{}\t{}", candidate.value, candidate.description));
+        }
+    }
 
-pub fn run() -> Result<()> {
-    // For now, just generate zsh completion with our custom script
-    // that includes dynamic project/branch completion
-    generate_zsh_completion();
     Ok(())
 }
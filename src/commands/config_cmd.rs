@@ -1,10 +1,26 @@
 use colored::Colorize;
 
-use crate::config::GlobalConfig;
+use crate::config::effective::Setting;
+use crate::config::{GlobalConfig, Overrides};
+use crate::context::Context;
 use crate::error::{DevError, Result};
+use crate::ui::json::{self, ConfigEntryJson, ConfigJson, EffectiveConfigJson};
+use crate::ui::output::{Output, OutputFormat};
 
-pub fn run(set: Option<String>, get: Option<String>) -> Result<()> {
-    let mut config = GlobalConfig::load()?;
+pub fn run(
+    ctx: &Context,
+    set: Option<String>,
+    get: Option<String>,
+    effective: bool,
+    overrides: &Overrides,
+    format: OutputFormat,
+) -> Result<()> {
+    if effective {
+        return show_effective(ctx, overrides, format);
+    }
+
+    let out = &ctx.out;
+    let mut config = GlobalConfig::load(&ctx.config_dir)?;
 
     if let Some(key_value) = set {
         // Set a config value
@@ -19,36 +35,177 @@ pub fn run(set: Option<String>, get: Option<String>) -> Result<()> {
         let value = parts[1].trim();
 
         config.set(key, value)?;
-        println!("{} {} = {}", "✓".green(), key, value);
+        config.save(&ctx.config_dir)?;
+
+        match format {
+            OutputFormat::Json => {
+                json::print(out, "config_entry", ConfigEntryJson::new(key, value))?
+            }
+            OutputFormat::Plain => out.println(format!("{}={}", key, value)),
+            OutputFormat::Table => out.println(format!("{} {} = {}", "✓".green(), key, value)),
+        }
     } else if let Some(key) = get {
         // Get a config value
-        match config.get(&key) {
-            Some(value) => println!("{}", value),
-            None => {
-                return Err(DevError::ConfigError(format!("Unknown config key: {}", key)));
+        let value = config
+            .get(&key)
+            .ok_or_else(|| DevError::ConfigError(format!("Unknown config key: {}", key)))?;
+
+        match format {
+            OutputFormat::Json => {
+                json::print(out, "config_entry", ConfigEntryJson::new(&key, value))?
             }
+            _ => out.println(value),
+        }
+    } else if format == OutputFormat::Json {
+        let entries = config_entries(&config)
+            .map(|(key, value)| ConfigEntryJson::new(key, value))
+            .collect();
+
+        json::print(
+            out,
+            "config",
+            ConfigJson {
+                path: GlobalConfig::config_path(&ctx.config_dir)
+                    .display()
+                    .to_string(),
+                entries,
+            },
+        )?;
+    } else if format == OutputFormat::Plain {
+        for (key, value) in config_entries(&config) {
+            out.println(format!("{}\t{}", key, value));
         }
     } else {
         // Show all config
-        println!("{}", "\n⚙️  Configuration:\n".bold());
-        println!("  {}: {}", "editor".cyan(), config.editor);
-        println!("  {}: {}", "dev_path".cyan(), config.dev_path.display());
-        println!(
+        out.println("\n⚙️  Configuration:\n".bold());
+        out.println(format!("  {}: {}", "editor".cyan(), config.editor));
+        out.println(format!(
+            "  {}: {}",
+            "editor_window".cyan(),
+            config.editor_window
+        ));
+        out.println(format!(
+            "  {}: {}",
+            "editor_wait".cyan(),
+            config.editor_wait
+        ));
+        out.println(format!(
+            "  {}: {}",
+            "dev_path".cyan(),
+            config.dev_path.display()
+        ));
+        out.println(format!(
             "  {}: {}",
             "auto_install_deps".cyan(),
             config.auto_install_deps
-        );
-        println!("  {}: {}", "auto_devbox".cyan(), config.auto_devbox);
-        println!(
-            "  {}: {:?}",
-            "shell".cyan(),
-            config.shell
-        );
-
-        println!();
-        println!("{}", "Config file:".dimmed());
-        println!("  {}", GlobalConfig::config_path().display());
+        ));
+        out.println(format!(
+            "  {}: {}",
+            "shared_deps".cyan(),
+            config.shared_deps
+        ));
+        out.println(format!(
+            "  {}: {}",
+            "auto_devbox".cyan(),
+            config.auto_devbox
+        ));
+        out.println(format!("  {}: {:?}", "shell".cyan(), config.shell));
+        out.println(format!(
+            "  {}: {}",
+            "worktree_path_template".cyan(),
+            config.worktree_path_template
+        ));
+
+        out.println("");
+        out.println("Config file:".dimmed());
+        out.println(format!(
+            "  {}",
+            GlobalConfig::config_path(&ctx.config_dir).display()
+        ));
     }
 
     Ok(())
 }
+
+/// All known keys with their current values
+fn config_entries(config: &GlobalConfig) -> impl Iterator<Item = (&'static str, String)> + '_ {
+    GlobalConfig::KEYS
+        .iter()
+        .filter_map(move |key| config.get(key).map(|value| (*key, value)))
+}
+
+/// Show the merged settings for the current directory and where each came from
+fn show_effective(ctx: &Context, overrides: &Overrides, format: OutputFormat) -> Result<()> {
+    let out = &ctx.out;
+    let settings = ctx.settings(&ctx.cwd, overrides)?;
+
+    if format != OutputFormat::Table {
+        let entries = vec![
+            ConfigEntryJson::from_setting("editor", &settings.editor),
+            ConfigEntryJson::from_setting("editor_window", &settings.editor_window),
+            ConfigEntryJson::from_setting("editor_wait", &settings.editor_wait),
+            ConfigEntryJson::from_setting("auto_install_deps", &settings.auto_install_deps),
+            ConfigEntryJson::from_setting("shared_deps", &settings.shared_deps),
+            ConfigEntryJson::from_setting("auto_devbox", &settings.auto_devbox),
+            ConfigEntryJson::from_setting(
+                "worktree_path_template",
+                &settings.worktree_path_template,
+            ),
+        ];
+
+        if format == OutputFormat::Json {
+            return json::print(
+                out,
+                "effective_config",
+                EffectiveConfigJson {
+                    project: settings.project.map(|p| p.name),
+                    entries,
+                },
+            );
+        }
+
+        // One tab-separated line per setting: key, value, source
+        for entry in entries {
+            out.println(format!(
+                "{}\t{}\t{}",
+                entry.key,
+                entry.value,
+                entry.source.unwrap_or_default()
+            ));
+        }
+        return Ok(());
+    }
+
+    match settings.project {
+        Some(ref project) => {
+            out.println(format!("\n⚙️  Effective configuration for '{}':\n", project.name).bold())
+        }
+        None => out.println("\n⚙️  Effective configuration:\n".bold()),
+    }
+
+    print_setting(out, "editor", &settings.editor);
+    print_setting(out, "editor_window", &settings.editor_window);
+    print_setting(out, "editor_wait", &settings.editor_wait);
+    print_setting(out, "auto_install_deps", &settings.auto_install_deps);
+    print_setting(out, "shared_deps", &settings.shared_deps);
+    print_setting(out, "auto_devbox", &settings.auto_devbox);
+    print_setting(
+        out,
+        "worktree_path_template",
+        &settings.worktree_path_template,
+    );
+
+    out.println("");
+    out.println("Precedence: command-line flag > project > global".dimmed());
+
+    Ok(())
+}
+
+fn print_setting<T: std::fmt::Display>(out: &Output, key: &str, setting: &Setting<T>) {
+    out.println(format!(
+        "  {}: {} {}",
+        key.cyan(),
+        setting.value,
+        format!("({})", setting.source).dimmed()
+    ));
+}
@@ -1,18 +1,29 @@
 use colored::Colorize;
 
-use crate::editor;
+use crate::api;
+use crate::config::Overrides;
+use crate::context::Context;
 use crate::error::{DevError, Result};
 use crate::git::{
-    branch_exists_locally, branch_exists_on_remote, create_worktree, is_git_repository,
+    branch_exists_locally, branch_exists_on_remote, get_main_worktree, is_git_repository,
 };
-use crate::package_manager;
-use crate::ui::output::{info, success};
-use crate::ui::prompts::{prompt_remote_branch_action, Prompts, RemoteBranchAction};
+use crate::hooks::HookEvent;
+use crate::shell::{devbox, integration};
+use crate::ui::prompts::{prompt_remote_branch_action, RemoteBranchAction};
 
-pub fn run(branch: &str) -> Result<()> {
-    let current_dir = std::env::current_dir()?;
+/// Create a worktree for a new branch
+///
+/// When the branch exists on the remote, `track_remote` says whether to
+/// check it out (true) or start a divergent local branch (false); None asks.
+pub fn run(
+    ctx: &Context,
+    branch: &str,
+    track_remote: Option<bool>,
+    overrides: &Overrides,
+) -> Result<()> {
+    let out = &ctx.out;
 
-    if !is_git_repository(&current_dir) {
+    if !is_git_repository(&ctx.cwd) {
         return Err(DevError::NotGitRepository);
     }
 
@@ -22,16 +33,21 @@ pub fn run(branch: &str) -> Result<()> {
     }
 
     // Check if branch already exists locally
-    if branch_exists_locally(branch) {
+    if branch_exists_locally(ctx.git, &ctx.cwd, branch) {
         return Err(DevError::BranchExistsLocally(branch.to_string()));
     }
 
     let mut create_new_branch = true;
 
     // Check if branch exists on remote
-    if branch_exists_on_remote(branch) {
-        let prompts = Prompts::new();
-        match prompt_remote_branch_action(&prompts, branch)? {
+    if branch_exists_on_remote(ctx.git, &ctx.cwd, branch) {
+        let action = match track_remote {
+            Some(true) => RemoteBranchAction::Checkout,
+            Some(false) => RemoteBranchAction::CreateDivergent,
+            None => prompt_remote_branch_action(ctx.prompter.as_ref(), branch)?,
+        };
+
+        match action {
             RemoteBranchAction::Checkout => {
                 create_new_branch = false;
             }
@@ -39,33 +55,64 @@ pub fn run(branch: &str) -> Result<()> {
                 create_new_branch = true;
             }
             RemoteBranchAction::Cancel => {
-                println!("{}", "Operation cancelled".yellow());
+                out.println("Operation cancelled".yellow());
                 return Ok(());
             }
         }
+    } else if track_remote == Some(true) {
+        return Err(DevError::BranchNotFound(format!("origin/{}", branch)));
     }
 
-    info(&format!("Creating worktree for branch '{}'...", branch));
+    let settings = ctx.settings(&ctx.cwd, overrides)?;
+
+    out.info(&format!("Creating worktree for branch '{}'...", branch));
+
+    // Create the worktree where the configured layout puts it
+    let worktree_path = api::create_worktree(
+        &ctx.config_dir,
+        ctx.git,
+        &ctx.cwd,
+        branch,
+        create_new_branch,
+    )?;
+
+    out.success(&format!("Worktree created at: {}", worktree_path.display()));
 
-    // Create the worktree
-    let worktree_path = create_worktree(branch, create_new_branch)?;
+    let env = settings.env_for(ctx.git, &worktree_path);
 
-    success(&format!("Worktree created at: {}", worktree_path.display()));
+    // Bring over untracked files before anything needs them
+    if let Some(main) = get_main_worktree(ctx.git, &ctx.cwd)? {
+        super::sync_worktree_files(out, &settings, &main.path, &worktree_path, &env, false)?;
+    }
 
-    // Install dependencies if needed
-    info("Checking for dependencies...");
-    if package_manager::install_dependencies(&worktree_path)? {
-        success("Dependencies installed");
+    // Install dependencies if enabled and not already brought in up to date
+    let deps = settings.deps_status(&worktree_path)?;
+    if settings.auto_install_deps.value && deps.is_some_and(|deps| deps.needs_install()) {
+        out.info("Checking for dependencies...");
+        if super::install_dependencies(out, ctx.git, &settings, &worktree_path, &env, false)? {
+            out.success("Dependencies installed");
+        }
     }
 
+    super::run_hooks(ctx, &settings, HookEvent::PostCreate, &worktree_path, &env)?;
+
+    // Move the shell into the new worktree when the wrapper is installed
+    integration::request_cd(
+        &worktree_path,
+        devbox::should_enter(&settings, &worktree_path),
+    )?;
+
     // Open in editor
-    info("Opening in editor...");
-    let config = crate::config::GlobalConfig::load()?;
-    editor::open(&worktree_path, &config)?;
+    out.info("Opening in editor...");
+    super::open_editor(ctx, &settings, &worktree_path, &env)?;
 
-    println!();
-    success(&format!("Ready to work on '{}'!", branch));
-    println!("   {}: {}", "Path".dimmed(), worktree_path.display());
+    out.println("");
+    out.success(&format!("Ready to work on '{}'!", branch));
+    out.println(format!(
+        "   {}: {}",
+        "Path".dimmed(),
+        worktree_path.display()
+    ));
 
     Ok(())
 }
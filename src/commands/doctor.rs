@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use colored::Colorize;
+
+use crate::config::{layout, EffectiveConfig, GlobalConfig, Overrides, ProjectConfig};
+use crate::context::Context;
+use crate::editor::{self, Launcher};
+use crate::error::{DevError, Result};
+use crate::executable;
+use crate::git::{self, GitBackend, MIN_GIT_VERSION};
+use crate::ui::json::{self, DoctorJson, ProblemJson, ToolJson};
+use crate::ui::output::{Output, OutputFormat};
+
+/// An external program dev runs
+struct Tool {
+    name: String,
+    /// What dev uses it for: "git", "editor", "devbox" or "package_manager"
+    kind: &'static str,
+    /// Where it was found; None when it wasn't
+    path: Option<PathBuf>,
+    version: Option<String>,
+    /// Whether dev can't do its job without it
+    required: bool,
+}
+
+/// Something wrong with the setup
+struct Problem {
+    /// What was being checked: "config", "registry", "git", "worktrees" or
+    /// "tools"
+    check: &'static str,
+    message: String,
+    fix: Option<Fix>,
+    fixed: bool,
+}
+
+impl Problem {
+    fn new(check: &'static str, message: String, fix: Option<Fix>) -> Self {
+        Self {
+            check,
+            message,
+            fix,
+            fixed: false,
+        }
+    }
+}
+
+/// A repair `--fix` makes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fix {
+    /// Delete a project's file, unregistering it
+    Unregister(PathBuf),
+    /// Set the name inside a project's file to the file's name, which is
+    /// what the project is looked up by
+    Rename(PathBuf),
+    /// Run `git worktree prune` in a repository
+    Prune(PathBuf),
+}
+
+impl Fix {
+    fn describe(&self) -> &'static str {
+        match self {
+            Fix::Unregister(_) => "unregister it",
+            Fix::Rename(_) => "rename it after its file",
+            Fix::Prune(_) => "prune it",
+        }
+    }
+
+    fn apply(&self, git: &dyn GitBackend) -> Result<()> {
+        match self {
+            Fix::Unregister(file) => Ok(std::fs::remove_file(file)?),
+            Fix::Rename(file) => {
+                let mut project: serde_json::Value =
+                    serde_json::from_str(&std::fs::read_to_string(file)?)?;
+                project["name"] = file_name(file).into();
+                Ok(std::fs::write(
+                    file,
+                    serde_json::to_string_pretty(&project)?,
+                )?)
+            }
+            Fix::Prune(repo) => git.prune_worktrees(repo),
+        }
+    }
+}
+
+/// Report the external tools dev depends on and check the config, the
+/// project registry and the repositories' worktrees for problems,
+/// repairing those that can be with `fix`
+///
+/// Fails when problems are left, so scripts can tell.
+pub fn run(ctx: &Context, fix: bool, overrides: &Overrides, format: OutputFormat) -> Result<()> {
+    let out = &ctx.out;
+    let mut problems = Vec::new();
+
+    let global = check_config(ctx, &mut problems);
+    let projects = check_registry(ctx, &mut problems)?;
+
+    // Fall back to the defaults so a broken config file still gets a report
+    let settings = ctx
+        .settings(&ctx.cwd, overrides)
+        .unwrap_or_else(|_| EffectiveConfig::resolve(&global.unwrap_or_default(), None, overrides));
+    let tools = tools(ctx, &settings)?;
+    check_tools(&tools, &mut problems);
+    check_worktrees(ctx, &projects, &mut problems);
+
+    if fix {
+        apply_fixes(out, ctx.git, &mut problems);
+    }
+
+    match format {
+        OutputFormat::Json => {
+            json::print(
+                out,
+                "doctor",
+                DoctorJson {
+                    tools: tools.iter().map(tool_json).collect(),
+                    problems: problems.iter().map(problem_json).collect(),
+                },
+            )?;
+        }
+        OutputFormat::Plain => {
+            // One tab-separated line per tool: kind, name, path, version
+            for tool in &tools {
+                out.println(format!(
+                    "{}\t{}\t{}\t{}",
+                    tool.kind,
+                    tool.name,
+                    tool.path
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    tool.version.as_deref().unwrap_or_default()
+                ));
+            }
+            // Then one per problem: "problem", check, state, message
+            for problem in &problems {
+                out.println(format!(
+                    "problem\t{}\t{}\t{}",
+                    problem.check,
+                    problem_state(problem),
+                    problem.message
+                ));
+            }
+        }
+        OutputFormat::Table => print_table(out, &tools, &problems, fix),
+    }
+
+    let left = problems.iter().filter(|p| !p.fixed).count();
+    if left > 0 {
+        return Err(DevError::Other(format!("{} problem(s) found", left)));
+    }
+
+    Ok(())
+}
+
+fn print_table(out: &Output, tools: &[Tool], problems: &[Problem], fix: bool) {
+    out.println("\n🩺 Tools:\n".bold());
+
+    let name_width = tools.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    let path_width = tools
+        .iter()
+        .map(|t| t.path.as_ref().map_or(0, |p| p.display().to_string().len()))
+        .max()
+        .unwrap_or(0);
+
+    for tool in tools {
+        let name = format!("{:width$}", tool.name, width = name_width);
+        match &tool.path {
+            Some(path) => out.println(format!(
+                "  {} {}  {:width$}  {}",
+                "✓".green(),
+                name.cyan(),
+                path.display(),
+                tool.version
+                    .as_deref()
+                    .unwrap_or("unknown version")
+                    .dimmed(),
+                width = path_width
+            )),
+            None if tool.required => out.println(format!(
+                "  {} {}  {}",
+                "✗".red(),
+                name.cyan(),
+                "not found".red()
+            )),
+            None => out.println(format!(
+                "  {} {}  {}",
+                "-".dimmed(),
+                name.cyan(),
+                "not found".dimmed()
+            )),
+        }
+    }
+
+    out.println("\n🩺 Checks:\n".bold());
+
+    if problems.is_empty() {
+        out.success("No problems found");
+        return;
+    }
+
+    for problem in problems {
+        let line = match &problem.fix {
+            Some(f) if problem.fixed => format!(
+                "  {} {} {}",
+                "✓".green(),
+                problem.message,
+                format!("(fixed: {})", f.describe()).green()
+            ),
+            Some(f) => format!(
+                "  {} {} {}",
+                "✗".red(),
+                problem.message,
+                format!("(--fix: {})", f.describe()).dimmed()
+            ),
+            None => format!("  {} {}", "✗".red(), problem.message),
+        };
+        out.println(line);
+    }
+
+    let fixable = problems
+        .iter()
+        .filter(|p| p.fix.is_some() && !p.fixed)
+        .count();
+    if !fix && fixable > 0 {
+        out.println("");
+        out.println(format!("Run 'dev doctor --fix' to repair {} of them", fixable).dimmed());
+    }
+}
+
+/// Whether the global config and project files parse, and their settings
+/// are usable; returns the global config if it does
+fn check_config(ctx: &Context, problems: &mut Vec<Problem>) -> Option<GlobalConfig> {
+    let path = GlobalConfig::config_path(&ctx.config_dir);
+
+    // A missing file is fine; the defaults are written on first use
+    let global = match std::fs::read_to_string(&path) {
+        Ok(contents) => match serde_json::from_str::<GlobalConfig>(&contents) {
+            Ok(global) => Some(global),
+            Err(e) => {
+                problems.push(Problem::new(
+                    "config",
+                    format!("{} is invalid: {}", path.display(), e),
+                    None,
+                ));
+                None
+            }
+        },
+        Err(_) => None,
+    };
+
+    if let Some(global) = &global {
+        check_settings(
+            "global config",
+            Some(&global.editor),
+            Some(&global.worktree_path_template),
+            problems,
+        );
+    }
+
+    global
+}
+
+/// Whether an editor and worktree path template from config can be used
+fn check_settings(
+    owner: &str,
+    editor: Option<&String>,
+    template: Option<&String>,
+    problems: &mut Vec<Problem>,
+) {
+    if let Some(Err(e)) = editor.map(|editor| editor::from_config(editor)) {
+        problems.push(Problem::new("config", format!("{}: {}", owner, e), None));
+    }
+
+    if let Some(Err(e)) = template.map(|template| layout::validate_template(template)) {
+        problems.push(Problem::new(
+            "config",
+            format!("{}: worktree_path_template: {}", owner, e),
+            None,
+        ));
+    }
+}
+
+/// Whether each project file parses and matches its name, and whether the
+/// projects point at distinct directories that exist; returns the projects
+/// that parse with their files
+fn check_registry(
+    ctx: &Context,
+    problems: &mut Vec<Problem>,
+) -> Result<Vec<(PathBuf, ProjectConfig)>> {
+    let mut projects = Vec::new();
+
+    for (file, config) in ProjectConfig::load_files(&ctx.config_dir)? {
+        match config {
+            Ok(project) => projects.push((file, project)),
+            Err(e) => problems.push(Problem::new(
+                "config",
+                format!("{} is invalid: {}", file.display(), e),
+                None,
+            )),
+        }
+    }
+
+    // Most recently used first, so that's the one kept of duplicates
+    projects.sort_by_key(|(_, p)| std::cmp::Reverse(p.last_accessed));
+    let mut by_path: HashMap<PathBuf, &str> = HashMap::new();
+
+    for (file, project) in &projects {
+        let name = file_name(file);
+
+        check_settings(
+            &format!("project '{}'", name),
+            project.editor.as_ref(),
+            project.worktree_path_template.as_ref(),
+            problems,
+        );
+
+        if project.name != name {
+            problems.push(Problem::new(
+                "registry",
+                format!(
+                    "Project '{}' is named '{}' inside its file",
+                    name, project.name
+                ),
+                Some(Fix::Rename(file.clone())),
+            ));
+        }
+
+        if !project.path.exists() {
+            problems.push(Problem::new(
+                "registry",
+                format!(
+                    "Project '{}' points to {}, which doesn't exist",
+                    name,
+                    project.path.display()
+                ),
+                Some(Fix::Unregister(file.clone())),
+            ));
+            continue;
+        }
+
+        let path = project
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| project.path.clone());
+        match by_path.get(&path) {
+            Some(kept) => problems.push(Problem::new(
+                "registry",
+                format!(
+                    "Project '{}' has the same path as '{}': {}",
+                    name,
+                    kept,
+                    project.path.display()
+                ),
+                Some(Fix::Unregister(file.clone())),
+            )),
+            None => {
+                by_path.insert(path, name);
+            }
+        }
+    }
+
+    Ok(projects)
+}
+
+/// Whether required tools are installed and git is new enough
+fn check_tools(tools: &[Tool], problems: &mut Vec<Problem>) {
+    for tool in tools {
+        let message = match (&tool.path, tool.kind) {
+            (Some(_), "git") => {
+                let (major, minor) = MIN_GIT_VERSION;
+                match tool.version.as_deref().and_then(parse_version) {
+                    Some(version) if version < MIN_GIT_VERSION => format!(
+                        "git {} is too old for dev's worktree commands; {}.{} or later is needed",
+                        tool.version.as_deref().unwrap_or_default(),
+                        major,
+                        minor
+                    ),
+                    _ => continue,
+                }
+            }
+            (Some(_), _) => continue,
+            (None, _) if !tool.required => continue,
+            (None, "git") => "git isn't installed".to_string(),
+            (None, "editor") => format!("Editor '{}' isn't installed", tool.name),
+            (None, "devbox") => "devbox isn't installed, but the project uses it".to_string(),
+            (None, _) => format!(
+                "{} isn't installed, but the worktree's dependencies need it",
+                tool.name
+            ),
+        };
+
+        let check = if tool.kind == "git" { "git" } else { "tools" };
+        problems.push(Problem::new(check, message, None));
+    }
+}
+
+/// Whether the repositories of the current directory and of each project
+/// have worktrees whose directories are gone
+fn check_worktrees(
+    ctx: &Context,
+    projects: &[(PathBuf, ProjectConfig)],
+    problems: &mut Vec<Problem>,
+) {
+    let mut repos: Vec<PathBuf> = git::get_repository_root(&ctx.cwd).into_iter().collect();
+    repos.extend(
+        projects
+            .iter()
+            .map(|(_, p)| p.path.clone())
+            .filter(|path| path.exists()),
+    );
+
+    // Each repository once, however many of its worktrees led to it
+    let mut seen: Vec<Vec<PathBuf>> = Vec::new();
+
+    for repo in repos {
+        let worktrees = match ctx.git.list_worktrees(&repo) {
+            Ok(worktrees) => worktrees,
+            Err(_) => continue,
+        };
+
+        let paths: Vec<PathBuf> = worktrees.iter().map(|wt| wt.path.clone()).collect();
+        if seen.contains(&paths) {
+            continue;
+        }
+        seen.push(paths);
+
+        for wt in worktrees
+            .iter()
+            .filter(|wt| !wt.is_bare && !wt.path.exists())
+        {
+            problems.push(Problem::new(
+                "worktrees",
+                format!(
+                    "Worktree for '{}' is gone from {}",
+                    wt.branch.as_deref().unwrap_or("(detached)"),
+                    wt.path.display()
+                ),
+                Some(Fix::Prune(repo.clone())),
+            ));
+        }
+    }
+}
+
+/// Repair every problem that can be, each fix made once however many
+/// problems it covers
+fn apply_fixes(out: &Output, git: &dyn GitBackend, problems: &mut [Problem]) {
+    let mut applied: Vec<Fix> = Vec::new();
+
+    for problem in problems.iter_mut() {
+        let fix = match &problem.fix {
+            Some(fix) => fix,
+            None => continue,
+        };
+
+        if applied.contains(fix) {
+            problem.fixed = true;
+            continue;
+        }
+
+        match fix.apply(git) {
+            Ok(()) => {
+                problem.fixed = true;
+                applied.push(fix.clone());
+            }
+            Err(e) => out.eprintln(format!("Couldn't {}: {}", fix.describe(), e).red()),
+        }
+    }
+}
+
+/// Every tool dev may run for the current directory: git, the configured
+/// editor, devbox and the package managers of the worktree's packages
+fn tools(ctx: &Context, settings: &EffectiveConfig) -> Result<Vec<Tool>> {
+    let mut tools = Vec::new();
+
+    let git = executable::find("git");
+    tools.push(Tool {
+        name: "git".to_string(),
+        kind: "git",
+        version: git
+            .as_ref()
+            .and_then(|path| version(command(path, &["--version"]), None)),
+        path: git,
+        required: true,
+    });
+
+    // Opening worktrees fails without it. An editor setting that doesn't
+    // parse is the same as a missing editor.
+    if let Ok(editor) = editor::from_config(&settings.editor.value) {
+        let (path, version) = match editor.launcher() {
+            Some(Launcher::Command(path)) => {
+                let version = editor
+                    .version_args()
+                    .and_then(|args| version(command(&path, args), None));
+                (Some(path), version)
+            }
+            Some(Launcher::MacApp(app)) => (Some(app), None),
+            None => (None, None),
+        };
+
+        tools.push(Tool {
+            name: editor.command().to_string(),
+            kind: "editor",
+            path,
+            version,
+            required: true,
+        });
+    }
+
+    let devbox = executable::find("devbox");
+    tools.push(Tool {
+        name: "devbox".to_string(),
+        kind: "devbox",
+        version: devbox
+            .as_ref()
+            .and_then(|path| version(command(path, &["version"]), None)),
+        path: devbox,
+        required: settings.project.as_ref().is_some_and(|p| p.uses_devbox),
+    });
+
+    if let Ok(worktree) = git::get_repository_root(&ctx.cwd) {
+        let mut seen = Vec::new();
+
+        for planned in settings.install_plan(&worktree)? {
+            let manager = planned.package.manager;
+            if seen.contains(&manager.name()) {
+                continue;
+            }
+            seen.push(manager.name());
+
+            let path = executable::find(&manager.executable());
+            tools.push(Tool {
+                name: manager.name().to_string(),
+                kind: "package_manager",
+                // Run where the package is, so per-project versions (corepack,
+                // rustup overrides) are the ones reported
+                version: path.as_ref().and_then(|_| {
+                    manager
+                        .version_command()
+                        .and_then(|cmd| version(cmd, Some(&planned.package.dir())))
+                }),
+                path,
+                required: true,
+            });
+        }
+    }
+
+    Ok(tools)
+}
+
+fn command(program: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd
+}
+
+/// The version a `--version` style command prints, if it runs
+///
+/// That's the first word of its first line that looks like a version
+/// number, or the whole line when none does.
+fn version(mut cmd: Command, dir: Option<&Path>) -> Option<String> {
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+
+    let number = line.split_whitespace().find_map(|word| {
+        let word = word.strip_prefix('v').unwrap_or(word);
+        word.starts_with(|c: char| c.is_ascii_digit())
+            .then(|| word.trim_end_matches(',').to_string())
+    });
+
+    Some(number.unwrap_or_else(|| line.to_string()))
+}
+
+fn tool_json(tool: &Tool) -> ToolJson {
+    ToolJson {
+        name: tool.name.clone(),
+        kind: tool.kind,
+        path: tool.path.as_ref().map(|p| p.display().to_string()),
+        version: tool.version.clone(),
+        required: tool.required,
+    }
+}
+
+/// `major.minor` of a version number such as `2.39.5`
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()?;
+    Some((major, minor))
+}
+
+/// A project's name as its file gives it
+fn file_name(file: &Path) -> &str {
+    file.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+}
+
+fn problem_state(problem: &Problem) -> &'static str {
+    match (&problem.fix, problem.fixed) {
+        (_, true) => "fixed",
+        (Some(_), false) => "fixable",
+        (None, false) => "unfixable",
+    }
+}
+
+fn problem_json(problem: &Problem) -> ProblemJson {
+    ProblemJson {
+        check: problem.check,
+        message: problem.message.clone(),
+        fix: problem.fix.as_ref().map(Fix::describe),
+        fixed: problem.fixed,
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api;
+use crate::cli::EnvSyntax;
+use crate::config::{EffectiveConfig, Overrides};
+use crate::context::Context;
+use crate::error::Result;
+use crate::git;
+use crate::shell::devbox;
+use crate::ui::json;
+use crate::ui::output::OutputFormat;
+
+/// Print the environment for a worktree so it can be `eval`ed or loaded by direnv
+pub fn run(
+    ctx: &Context,
+    branch: Option<&str>,
+    syntax: EnvSyntax,
+    format: OutputFormat,
+) -> Result<()> {
+    let env = api::worktree_env(&ctx.config_dir, ctx.git, &ctx.cwd, branch)?;
+
+    if format == OutputFormat::Json {
+        return json::print(&ctx.out, "env", env.vars());
+    }
+
+    for (key, value) in env.vars() {
+        let line = match syntax {
+            EnvSyntax::Sh => format!("export {}={}", key, sh_quote(value)),
+            EnvSyntax::Fish => format!("set -gx {} {}", key, fish_quote(value)),
+            EnvSyntax::Dotenv => format!("{}={}", key, dotenv_quote(value)),
+        };
+        ctx.out.println(line);
+    }
+
+    Ok(())
+}
+
+/// Enter devbox in the current worktree with the environment its processes get
+///
+/// The shell wrapper runs this after moving into a worktree, so the project's
+/// `env` and the `DEV_*` variables are set inside the devbox shell too.
+pub fn enter_devbox(ctx: &Context, overrides: &Overrides) -> Result<()> {
+    let worktree = git::get_repository_root(&ctx.cwd).unwrap_or_else(|_| ctx.cwd.clone());
+    let settings = EffectiveConfig::load(&ctx.config_dir, ctx.git, &worktree, overrides)?;
+
+    devbox::exec_devbox_shell(&worktree, &settings.env_for(ctx.git, &worktree))
+}
+
+/// Single-quote for POSIX shells (`'` becomes `'\''`)
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Single-quote for fish (only `\` and `'` are special)
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Double-quote for dotenv files
+fn dotenv_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('$', "\\$");
+    format!("\"{}\"", escaped)
+}
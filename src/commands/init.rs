@@ -1,28 +1,32 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use colored::Colorize;
 
-use crate::config::{GlobalConfig, ProjectConfig};
-use crate::editor;
+use crate::config::{EffectiveConfig, GlobalConfig, Overrides, ProjectConfig};
+use crate::context::Context;
 use crate::error::{DevError, Result};
 use crate::git;
-use crate::shell::devbox;
-use crate::ui::output::{info, success};
-use crate::ui::prompts::Prompts;
+use crate::hooks::HookEvent;
+use crate::shell::{devbox, integration};
 
 pub fn run(
+    ctx: &Context,
     name: Option<String>,
     clone_url: Option<String>,
+    bare_layout: bool,
     existing_path: Option<PathBuf>,
-    no_devbox: bool,
+    devbox: Option<bool>,
+    overrides: &Overrides,
 ) -> Result<()> {
-    let prompts = Prompts::new();
-    let config = GlobalConfig::load()?;
+    let out = &ctx.out;
+    let config = GlobalConfig::load(&ctx.config_dir)?;
 
     // Determine project name
     let project_name = match &name {
         Some(n) => n.clone(),
-        None => prompts.input("Project name", None)?,
+        None => ctx
+            .prompter
+            .input("Project name", None, "a name (dev init <name>)")?,
     };
 
     if project_name.trim().is_empty() {
@@ -30,61 +34,99 @@ pub fn run(
     }
 
     // Check if project already exists in registry
-    if ProjectConfig::exists(&project_name) {
+    if ProjectConfig::exists(&ctx.config_dir, &project_name) {
         return Err(DevError::ProjectExists(project_name));
     }
 
+    // Ask everything up front so nothing is left half set up
+    let uses_devbox = match devbox {
+        Some(devbox) => devbox,
+        None => ctx.prompter.confirm(
+            "Set up devbox for this project?",
+            true,
+            "--devbox or --no-devbox",
+        )?,
+    };
+
     // Determine the project path and how to initialize
-    let project_path = if let Some(url) = clone_url {
-        // Clone from URL
-        init_from_clone(&project_name, &url, &config)?
+    let project_path = if let Some(url) = clone_url.as_deref() {
+        if bare_layout {
+            // Clone into a bare hub with a worktree for the default branch
+            init_from_clone_hub(ctx, &project_name, url, &config)?
+        } else {
+            // Clone from URL
+            init_from_clone(ctx, &project_name, url, &config)?
+        }
     } else if let Some(path) = existing_path {
         // Initialize in existing directory
-        init_from_existing(&project_name, &path)?
+        init_from_existing(ctx, &project_name, &path)?
     } else {
         // Create new repository
-        init_new_repo(&project_name, &config)?
+        init_new_repo(ctx, &project_name, &config)?
     };
 
-    // Set up devbox if requested
-    let uses_devbox = if no_devbox {
-        false
+    // The hub itself has no checkout; work happens in the default worktree
+    let workspace_path = if bare_layout {
+        git::get_main_worktree(ctx.git, &project_path)?
+            .map(|wt| wt.path)
+            .unwrap_or_else(|| project_path.clone())
     } else {
-        let setup_devbox = prompts.confirm("Set up devbox for this project?", true)?;
-        if setup_devbox {
-            info("Creating devbox.json...");
-            devbox::init_devbox(&project_path, &[])?;
-            success("devbox.json created");
-            true
-        } else {
-            false
-        }
+        project_path.clone()
     };
 
+    // Set up devbox if requested
+    if uses_devbox {
+        out.info("Creating devbox.json...");
+        devbox::init_devbox(&workspace_path, &[])?;
+        out.success("devbox.json created");
+    }
+
     // Register the project
     let mut project = ProjectConfig::new(&project_name, project_path.clone());
+    project.remote_url = clone_url;
     project.uses_devbox = uses_devbox;
-    project.save()?;
+    project.save(&ctx.config_dir)?;
 
-    success(&format!("Project '{}' registered", project_name));
+    out.success(&format!("Project '{}' registered", project_name));
 
-    // Open in editor
-    info("Opening in editor...");
-    editor::open(&project_path, &config)?;
+    let settings = EffectiveConfig::for_project(&ctx.config_dir, project, overrides)?;
+    let env = settings.env_for(ctx.git, &workspace_path);
 
-    println!();
-    success(&format!("Project '{}' is ready!", project_name));
-    println!("  {}: {}", "Path".dimmed(), project_path.display());
+    // Install dependencies for cloned or existing code
+    if settings.auto_install_deps.value
+        && super::install_dependencies(out, ctx.git, &settings, &workspace_path, &env, false)?
+    {
+        out.success("Dependencies installed");
+    }
 
-    if uses_devbox {
-        println!();
-        devbox::print_devbox_instructions(&project_path);
+    super::run_hooks(ctx, &settings, HookEvent::PostInit, &workspace_path, &env)?;
+
+    // Move the shell into the project when the wrapper is installed
+    let enter_devbox = uses_devbox && settings.auto_devbox.value;
+    let moved_shell = integration::request_cd(&workspace_path, enter_devbox)?;
+
+    // Open in editor
+    out.info("Opening in editor...");
+    super::open_editor(ctx, &settings, &workspace_path, &env)?;
+
+    out.println("");
+    out.success(&format!("Project '{}' is ready!", project_name));
+    out.println(format!(
+        "  {}: {}",
+        "Path".dimmed(),
+        workspace_path.display()
+    ));
+
+    // Without the wrapper, explain how to enter the devbox shell
+    if !moved_shell && uses_devbox {
+        out.println("");
+        devbox::print_devbox_instructions(out, &workspace_path);
     }
 
     Ok(())
 }
 
-fn init_new_repo(name: &str, config: &GlobalConfig) -> Result<PathBuf> {
+fn init_new_repo(ctx: &Context, name: &str, config: &GlobalConfig) -> Result<PathBuf> {
     let project_path = config.dev_path.join(name);
 
     if project_path.exists() {
@@ -94,7 +136,10 @@ fn init_new_repo(name: &str, config: &GlobalConfig) -> Result<PathBuf> {
         )));
     }
 
-    info(&format!("Creating new repository at {}...", project_path.display()));
+    ctx.out.info(&format!(
+        "Creating new repository at {}...",
+        project_path.display()
+    ));
 
     // Create directory
     std::fs::create_dir_all(&project_path)?;
@@ -110,12 +155,12 @@ fn init_new_repo(name: &str, config: &GlobalConfig) -> Result<PathBuf> {
     git::run_git_command(&["add", "."], Some(&project_path))?;
     git::run_git_command(&["commit", "-m", "Initial commit"], Some(&project_path))?;
 
-    success("Repository initialized");
+    ctx.out.success("Repository initialized");
 
     Ok(project_path)
 }
 
-fn init_from_clone(name: &str, url: &str, config: &GlobalConfig) -> Result<PathBuf> {
+fn init_from_clone(ctx: &Context, name: &str, url: &str, config: &GlobalConfig) -> Result<PathBuf> {
     let project_path = config.dev_path.join(name);
 
     if project_path.exists() {
@@ -125,16 +170,48 @@ fn init_from_clone(name: &str, url: &str, config: &GlobalConfig) -> Result<PathB
         )));
     }
 
-    info(&format!("Cloning {} to {}...", url, project_path.display()));
+    ctx.out
+        .info(&format!("Cloning {} to {}...", url, project_path.display()));
 
     git::clone_repository(url, &project_path)?;
 
-    success("Repository cloned");
+    ctx.out.success("Repository cloned");
 
     Ok(project_path)
 }
 
-fn init_from_existing(_name: &str, path: &PathBuf) -> Result<PathBuf> {
+fn init_from_clone_hub(
+    ctx: &Context,
+    name: &str,
+    url: &str,
+    config: &GlobalConfig,
+) -> Result<PathBuf> {
+    let hub_path = config.dev_path.join(name);
+
+    if hub_path.exists() {
+        return Err(DevError::Other(format!(
+            "Directory already exists: {}",
+            hub_path.display()
+        )));
+    }
+
+    ctx.out.info(&format!(
+        "Cloning {} into bare hub at {}...",
+        url,
+        hub_path.display()
+    ));
+
+    let worktree_path = git::clone_hub(ctx.git, url, &hub_path)?;
+
+    ctx.out.success(&format!(
+        "Repository cloned, default branch checked out at {}",
+        worktree_path.display()
+    ));
+
+    Ok(hub_path)
+}
+
+fn init_from_existing(ctx: &Context, _name: &str, path: &Path) -> Result<PathBuf> {
     if !path.exists() {
         return Err(DevError::Other(format!(
             "Directory does not exist: {}",
@@ -142,16 +219,20 @@ fn init_from_existing(_name: &str, path: &PathBuf) -> Result<PathBuf> {
         )));
     }
 
-    let abs_path = path.canonicalize()?;
+    // Relative paths are relative to where dev was run
+    let abs_path = ctx.cwd.join(path).canonicalize()?;
 
     // Check if it's a git repository
     if !git::is_git_repository(&abs_path) {
-        info("Initializing git repository...");
+        ctx.out.info("Initializing git repository...");
         git::init_repository(&abs_path)?;
-        success("Repository initialized");
+        ctx.out.success("Repository initialized");
     }
 
-    info(&format!("Registering existing project at {}...", abs_path.display()));
+    ctx.out.info(&format!(
+        "Registering existing project at {}...",
+        abs_path.display()
+    ));
 
     Ok(abs_path)
 }
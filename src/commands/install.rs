@@ -0,0 +1,61 @@
+use colored::Colorize;
+
+use crate::api;
+use crate::config::Overrides;
+use crate::context::Context;
+use crate::error::{DevError, Result};
+use crate::git;
+use crate::package_manager::{self, DepsStatus};
+
+/// Install dependencies in a worktree unless they match its lockfiles
+///
+/// `packages` narrows the monorepo packages installed in the worktree from
+/// now on, and `all_packages` widens them back to everything.
+pub fn run(
+    ctx: &Context,
+    branch: Option<&str>,
+    force: bool,
+    packages: &[String],
+    all_packages: bool,
+    overrides: &Overrides,
+) -> Result<()> {
+    let out = &ctx.out;
+
+    let worktree = match branch {
+        Some(branch) => api::find_worktree(ctx.git, &ctx.cwd, branch)?.path,
+        None => git::get_repository_root(&ctx.cwd)?,
+    };
+
+    if !packages.is_empty() || all_packages {
+        package_manager::select_packages(&worktree, packages)?;
+    }
+
+    let settings = ctx.settings(&worktree, overrides)?;
+
+    match settings.deps_status(&worktree)? {
+        None => {
+            let message = if packages.is_empty() {
+                "No package manager detected; nothing to install"
+            } else {
+                "No packages match; nothing to install"
+            };
+            out.println(message.yellow());
+            return Ok(());
+        }
+        Some(DepsStatus::UpToDate) if !force => {
+            out.success("Dependencies are up to date (use --force to reinstall)");
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let env = settings.env_for(ctx.git, &worktree);
+
+    if !super::install_dependencies(out, ctx.git, &settings, &worktree, &env, force)? {
+        return Err(DevError::Other(
+            "Failed to install dependencies".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,24 +1,58 @@
 use colored::Colorize;
 
-use crate::error::{DevError, Result};
-use crate::git::{is_git_repository, list_worktrees};
-use crate::ui::output::{status_icon, status_label};
-
-pub fn run() -> Result<()> {
-    let current_dir = std::env::current_dir()?;
-
-    if !is_git_repository(&current_dir) {
-        return Err(DevError::NotGitRepository);
+use crate::api::{self, WorktreeList};
+use crate::context::Context;
+use crate::error::Result;
+use crate::git::WorktreeInfo;
+use crate::package_manager::DepsStatus;
+use crate::ui::json::{self, WorktreeJson, WorktreeListJson};
+use crate::ui::output::{status_icon, status_label, status_text, Output, OutputFormat};
+
+pub fn run(ctx: &Context, format: OutputFormat) -> Result<()> {
+    let out = &ctx.out;
+
+    let (
+        WorktreeList {
+            hub: hub_root,
+            worktrees,
+            ..
+        },
+        deps,
+    ) = api::list_worktrees_with_deps(&ctx.config_dir, ctx.git, &ctx.cwd)?;
+
+    match format {
+        OutputFormat::Json => {
+            return json::print(
+                out,
+                "worktrees",
+                WorktreeListJson {
+                    hub: hub_root.map(|hub| hub.display().to_string()),
+                    worktrees: worktrees
+                        .iter()
+                        .zip(&deps)
+                        .map(|(wt, deps)| WorktreeJson::new(wt, *deps))
+                        .collect(),
+                },
+            );
+        }
+        OutputFormat::Plain => {
+            print_plain(out, &worktrees, &deps);
+            return Ok(());
+        }
+        OutputFormat::Table => {}
     }
 
-    let worktrees = list_worktrees()?;
-
     if worktrees.is_empty() {
-        println!("{}", "No worktrees found".yellow());
+        out.println("No worktrees found".yellow());
         return Ok(());
     }
 
-    println!("{}", "\n📁 Git Worktrees:\n".bold());
+    match hub_root {
+        Some(ref hub) => {
+            out.println(format!("\n📁 Git Worktrees (hub: {}):\n", hub.display()).bold())
+        }
+        None => out.println("\n📁 Git Worktrees:\n".bold()),
+    }
 
     // Calculate column widths for alignment
     let max_branch_len = worktrees
@@ -33,7 +67,7 @@ pub fn run() -> Result<()> {
         .max()
         .unwrap_or(20);
 
-    for worktree in &worktrees {
+    for (worktree, deps) in worktrees.iter().zip(&deps) {
         let current_marker = if worktree.is_current {
             "* ".green()
         } else {
@@ -42,8 +76,7 @@ pub fn run() -> Result<()> {
 
         let branch_name = worktree
             .branch
-            .as_ref()
-            .map(|b| b.as_str())
+            .as_deref()
             .unwrap_or(if worktree.is_detached {
                 "detached"
             } else if worktree.is_bare {
@@ -60,15 +93,11 @@ pub fn run() -> Result<()> {
             format!("{:width$}", branch_name, width = max_branch_len).cyan()
         };
 
-        let path_display = format!(
-            "{:width$}",
-            worktree.path.display(),
-            width = max_path_len
-        )
-        .dimmed();
+        let path_display =
+            format!("{:width$}", worktree.path.display(), width = max_path_len).dimmed();
 
-        let status_icon = status_icon(&worktree.status);
-        let status_label = status_label(&worktree.status);
+        let status_icon = status_icon(worktree.status());
+        let status_label = status_label(worktree.status());
 
         let short_commit = if worktree.commit.len() >= 7 {
             &worktree.commit[..7]
@@ -77,21 +106,54 @@ pub fn run() -> Result<()> {
         }
         .dimmed();
 
-        println!(
-            "{}{} {} {} {} {}",
-            current_marker, branch_display, path_display, status_icon, status_label, short_commit
-        );
+        let deps_marker = if *deps == Some(DepsStatus::Stale) {
+            format!(" {}", "deps stale".yellow())
+        } else {
+            String::new()
+        };
+
+        out.println(format!(
+            "{}{} {} {} {} {}{}",
+            current_marker,
+            branch_display,
+            path_display,
+            status_icon,
+            status_label,
+            short_commit,
+            deps_marker
+        ));
     }
 
-    println!();
+    out.println("");
 
     // Show legend
-    println!("{}", "Legend:".dimmed());
-    println!("{}", "  * = current worktree".dimmed());
-    println!(
-        "{}",
-        "  ✓ = clean  ↑ = ahead  ↓ = behind  ⇅ = diverged  ● = modified".dimmed()
+    out.println("Legend:".dimmed());
+    out.println("  * = current worktree".dimmed());
+    out.println(
+        "  ✓ = clean  ● = changes  ! = conflicts/in progress  ↑ ahead  ↓ behind  ⇅ diverged"
+            .dimmed(),
+    );
+    out.println("  ○ = no upstream  ⊘ = upstream gone".dimmed());
+    out.println("  deps stale = lockfile changed since the last install".dimmed());
+    out.println(
+        "  !n conflicted  +n staged  ~n unstaged  ?n untracked  $n stashes  (main ↑n ↓n)".dimmed(),
     );
 
     Ok(())
 }
+
+/// One tab-separated line per worktree: branch, path, status, commit, `*` if
+/// current, dependency status
+fn print_plain(out: &Output, worktrees: &[WorktreeInfo], deps: &[Option<DepsStatus>]) {
+    for (worktree, deps) in worktrees.iter().zip(deps) {
+        out.println(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            worktree.branch.as_deref().unwrap_or(""),
+            worktree.path.display(),
+            status_text(worktree.status()),
+            worktree.commit,
+            if worktree.is_current { "*" } else { "" },
+            deps.map_or("", json::deps_key)
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use crate::context::Context;
+use crate::error::{DevError, Result};
+use crate::git::{is_git_repository, move_worktree, WorktreeLayout};
+use crate::shell::integration;
+
+/// Move existing worktrees to where the configured layout would place them
+pub fn run(ctx: &Context, dry_run: bool) -> Result<()> {
+    let out = &ctx.out;
+
+    if !is_git_repository(&ctx.cwd) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    let layout = WorktreeLayout::load(&ctx.config_dir, ctx.git, &ctx.cwd)?;
+    let worktrees = ctx.git.list_worktrees(&ctx.cwd)?;
+
+    // Work out which worktrees are not where the template says they should be
+    let mut moves: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+
+    for wt in &worktrees {
+        if wt.is_bare || wt.path == layout.repo_root || !wt.path.exists() {
+            continue;
+        }
+
+        let branch = match wt.branch {
+            Some(ref branch) => branch,
+            None => continue,
+        };
+
+        let target = layout.path_for(branch);
+        if target != wt.path {
+            moves.push((branch.clone(), wt.path.clone(), target));
+        }
+    }
+
+    if moves.is_empty() {
+        out.success("All worktrees already match the configured layout");
+        return Ok(());
+    }
+
+    // Two worktrees resolving to the same place can't both be moved
+    for (i, (branch, _, target)) in moves.iter().enumerate() {
+        if let Some((other, _, _)) = moves[..i].iter().find(|(_, _, t)| t == target) {
+            return Err(DevError::Other(format!(
+                "Branches '{}' and '{}' both map to {}; adjust worktree_path_template",
+                other,
+                branch,
+                target.display()
+            )));
+        }
+    }
+
+    out.println(format!("{} worktree(s) to move:\n", moves.len()).yellow());
+
+    for (branch, from, to) in &moves {
+        out.println(format!("  {}", branch.cyan()));
+        out.println(format!("    {} {}", "from".dimmed(), from.display()));
+        out.println(format!("    {}   {}", "to".dimmed(), to.display()));
+    }
+
+    out.println("");
+
+    if dry_run {
+        out.info("Dry run: no worktrees were moved");
+        return Ok(());
+    }
+
+    if !ctx.prompter.proceed("Move these worktrees?")? {
+        out.println("Migration cancelled".yellow());
+        return Ok(());
+    }
+
+    // git runs from the main worktree, and the worktree holding the current
+    // directory goes last so nothing runs from a directory that has moved
+    moves.sort_by_key(|(_, from, _)| ctx.cwd.starts_with(from));
+
+    let mut moved = 0;
+    let mut failed = 0;
+
+    for (branch, from, to) in &moves {
+        match move_worktree(ctx.git, &layout.repo_root, from, to) {
+            Ok(()) => {
+                out.println(format!(
+                    "{} Moved: {} -> {}",
+                    "✓".green(),
+                    branch,
+                    to.display()
+                ));
+                moved += 1;
+            }
+            Err(e) => {
+                out.println(format!("{} Failed to move: {} - {}", "✗".red(), branch, e));
+                failed += 1;
+                continue;
+            }
+        }
+
+        // Follow the shell to the same place in the moved worktree
+        if let Ok(rest) = ctx.cwd.strip_prefix(from) {
+            let dir = to.join(rest);
+            if !integration::request_cd(&dir, false)? {
+                out.warning(&format!(
+                    "Your shell is still in the old location of '{}'; cd to {}",
+                    branch,
+                    dir.display()
+                ));
+            }
+        }
+    }
+
+    out.println("");
+    out.success(&format!("Migration complete: {} moved", moved));
+
+    if failed > 0 {
+        return Err(DevError::Other(format!(
+            "Failed to move {} worktree(s)",
+            failed
+        )));
+    }
+
+    Ok(())
+}
@@ -2,7 +2,312 @@ pub mod cleanup;
 pub mod completion;
 pub mod config_cmd;
 pub mod create;
+pub mod doctor;
+pub mod env;
 pub mod init;
+pub mod install;
 pub mod list;
+pub mod migrate;
+pub mod open;
 pub mod project;
+pub mod remove;
 pub mod switch;
+pub mod sync_files;
+
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use crate::config::{CommittedHooks, EffectiveConfig, TrustedHooks, WorktreeEnv};
+use crate::context::Context;
+use crate::editor::{self, OpenOptions, Target};
+use crate::error::{DevError, Result};
+use crate::git::GitBackend;
+use crate::hooks::{self, FailurePolicy, HookEvent};
+use crate::package_manager::{self, DepsStatus};
+use crate::ui::output::Output;
+use crate::worktree_files::{self, FileMode, SyncAction};
+
+/// What became of a package in [`install_dependencies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InstallOutcome {
+    Installed,
+    Reused(PathBuf),
+    UpToDate,
+    Failed,
+}
+
+/// Install dependencies for the packages of a worktree, reporting progress
+///
+/// Packages whose dependencies are up to date are skipped unless `force`.
+/// With `shared_deps`, managers that install into the package take their
+/// dependencies from the same package in another worktree installed from
+/// the same lockfile when there is one (unless `force` asks for a real
+/// install), and the others install from their cache without asking the
+/// registry where they can. A worktree with several packages gets a summary
+/// of each at the end.
+///
+/// Returns false when there is nothing to install or an install failed.
+fn install_dependencies(
+    out: &Output,
+    git: &dyn GitBackend,
+    settings: &EffectiveConfig,
+    path: &Path,
+    env: &WorktreeEnv,
+    force: bool,
+) -> Result<bool> {
+    let plan = settings.install_plan(path)?;
+    if plan.is_empty() {
+        return Ok(false);
+    }
+
+    let shared = settings.shared_deps.value;
+
+    let siblings: Vec<PathBuf> = if shared && !force {
+        git.list_worktrees(path)?
+            .into_iter()
+            .filter(|wt| !wt.is_bare)
+            .map(|wt| wt.path)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut outcomes = Vec::new();
+
+    for planned in &plan {
+        let package = &planned.package;
+        let pm = package.manager.name();
+        let place = install_place(planned);
+
+        let outcome = if !force && package.status() == DepsStatus::UpToDate {
+            InstallOutcome::UpToDate
+        } else if let Some(source) = package_manager::reuse_dependencies(package, &siblings)? {
+            let lockfile = package.manager.lockfile(&package.dir()).unwrap_or_default();
+            out.println(
+                format!(
+                    "Reused dependencies{} from {} (same {})",
+                    place,
+                    source.display(),
+                    lockfile.file_name().unwrap_or_default().to_string_lossy()
+                )
+                .green(),
+            );
+            InstallOutcome::Reused(source)
+        } else {
+            out.println(format!("Installing dependencies{} using {}...", place, pm).blue());
+
+            if package_manager::install(planned, env, shared)? {
+                out.println(
+                    format!("Dependencies installed successfully{} using {}", place, pm).green(),
+                );
+                InstallOutcome::Installed
+            } else {
+                out.println(format!("Failed to install dependencies{} with {}", place, pm).red());
+                InstallOutcome::Failed
+            }
+        };
+
+        outcomes.push(outcome);
+    }
+
+    if plan.len() > 1 {
+        out.println("");
+        out.println("Packages:".bold());
+        for (planned, outcome) in plan.iter().zip(&outcomes) {
+            let label = format!(
+                "{} ({})",
+                planned.package.display_path(),
+                planned.package.manager.name()
+            );
+            let line = match outcome {
+                InstallOutcome::Installed => format!("  {} {}: installed", "✓".green(), label),
+                InstallOutcome::Reused(source) => format!(
+                    "  {} {}: reused from {}",
+                    "✓".green(),
+                    label,
+                    source.display()
+                ),
+                InstallOutcome::UpToDate => format!("  {} {}: up to date", "✓".green(), label),
+                InstallOutcome::Failed => format!("  {} {}: failed", "✗".red(), label),
+            };
+            out.println(line);
+        }
+    }
+
+    Ok(!outcomes.contains(&InstallOutcome::Failed))
+}
+
+/// Where an install happens, for progress messages: nothing for the
+/// worktree root, else the package or the workspace members
+fn install_place(planned: &package_manager::Planned) -> String {
+    if !planned.members.is_empty() {
+        let members: Vec<String> = planned
+            .members
+            .iter()
+            .map(|member| member.display().to_string())
+            .collect();
+        format!(" for {}", members.join(", "))
+    } else if planned.package.path.as_os_str().is_empty() {
+        String::new()
+    } else {
+        format!(" in {}", planned.package.path.display())
+    }
+}
+
+/// Run the hooks for `event` in a worktree
+///
+/// A failing hook stops the rest and is returned as an error, unless it is
+/// set to only warn.
+fn run_hooks(
+    ctx: &Context,
+    settings: &EffectiveConfig,
+    event: HookEvent,
+    worktree: &Path,
+    env: &WorktreeEnv,
+) -> Result<()> {
+    let out = &ctx.out;
+
+    let mut all = settings.hooks_for(event);
+    if let Some(committed) = settings.committed_hooks_for(event, worktree)? {
+        if approve_hooks(ctx, event, &committed)? {
+            all.extend(committed.hooks);
+        }
+    }
+
+    for hook in all {
+        out.info(&format!("Running {} hook: {}", event, hook.run));
+
+        if let Err(e) = hooks::run(event, &hook, worktree, env) {
+            match hook.on_failure {
+                FailurePolicy::Abort => return Err(e),
+                FailurePolicy::Warn => out.warning(&e.to_string()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether hooks from a `.dev.json` may run, asking the first time a file
+/// (or a new version of it) is seen
+///
+/// Only someone at a terminal can approve them: `--yes` takes the default
+/// of not running them, and without a terminal they are skipped.
+fn approve_hooks(ctx: &Context, event: HookEvent, committed: &CommittedHooks) -> Result<bool> {
+    let out = &ctx.out;
+
+    let mut trusted = TrustedHooks::load(&ctx.config_dir)?;
+    if trusted.is_trusted(&committed.hash) {
+        return Ok(true);
+    }
+
+    out.warning(&format!(
+        "{} has {} hooks that haven't been approved:",
+        committed.path.display(),
+        event
+    ));
+    for hook in &committed.hooks {
+        out.println(format!("  {}", hook.run));
+    }
+
+    let approved = match ctx.prompter.confirm(
+        "Run the hooks in this file, now and whenever it is unchanged?",
+        false,
+        "a terminal to approve them",
+    ) {
+        Ok(approved) => approved,
+        Err(DevError::InputRequired(..)) => false,
+        Err(e) => return Err(e),
+    };
+
+    if approved {
+        trusted.trust(&committed.hash, &committed.path);
+        trusted.save(&ctx.config_dir)?;
+    } else {
+        out.warning(&format!(
+            "Skipping {} hooks from {}; run dev from a terminal to approve them",
+            event,
+            committed.path.display()
+        ));
+    }
+
+    Ok(approved)
+}
+
+/// Open a worktree in the configured editor, after its pre_open hooks
+fn open_editor(
+    ctx: &Context,
+    settings: &EffectiveConfig,
+    worktree: &Path,
+    env: &WorktreeEnv,
+) -> Result<()> {
+    open_in_editor(ctx, settings, &Target::worktree(worktree), env)
+}
+
+/// Open a worktree, or a file in it, in the configured editor, after the
+/// worktree's pre_open hooks
+fn open_in_editor(
+    ctx: &Context,
+    settings: &EffectiveConfig,
+    target: &Target,
+    env: &WorktreeEnv,
+) -> Result<()> {
+    run_hooks(ctx, settings, HookEvent::PreOpen, target.worktree, env)?;
+
+    let options = OpenOptions {
+        window: settings.editor_window.value,
+        wait: settings.editor_wait.value,
+    };
+    editor::open(target, &settings.editor.value, options, env)
+}
+
+/// Bring the project's `worktree_files` from `source` into `worktree`,
+/// reporting each path that changes
+///
+/// Returns how many paths changed (or would, with `dry_run`).
+fn sync_worktree_files(
+    out: &Output,
+    settings: &EffectiveConfig,
+    source: &Path,
+    worktree: &Path,
+    env: &WorktreeEnv,
+    dry_run: bool,
+) -> Result<usize> {
+    let rules = match &settings.project {
+        Some(project) if !project.worktree_files.is_empty() => &project.worktree_files,
+        _ => return Ok(0),
+    };
+
+    let mut changed = 0;
+
+    for file in worktree_files::sync(rules, source, worktree, env, dry_run)? {
+        let path = file.path.display();
+        let verb = match file.mode {
+            FileMode::Copy => "Copied",
+            FileMode::Symlink => "Linked",
+            FileMode::Template => "Rendered",
+        };
+
+        match file.action {
+            SyncAction::Created | SyncAction::Updated if dry_run => {
+                out.println(format!("  {} {}", "would update".yellow(), path));
+            }
+            SyncAction::Created | SyncAction::Updated => {
+                out.success(&format!("{} {}", verb, path));
+            }
+            SyncAction::Unchanged => continue,
+            SyncAction::Tracked => {
+                out.println(format!("  Skipped {} (tracked by git)", path).dimmed());
+                continue;
+            }
+        }
+
+        changed += 1;
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use crate::api;
+use crate::config::Overrides;
+use crate::context::Context;
+use crate::editor::Target;
+use crate::error::{DevError, Result};
+use crate::git;
+
+/// Open a worktree in the editor, or a file in it at `PATH[:LINE]`
+///
+/// `wait` blocks until the editor is closed whatever `editor_wait` says.
+pub fn run(
+    ctx: &Context,
+    branch: Option<&str>,
+    file: Option<&str>,
+    wait: bool,
+    overrides: &Overrides,
+) -> Result<()> {
+    let out = &ctx.out;
+
+    let worktree = match branch {
+        Some(branch) => api::find_worktree(ctx.git, &ctx.cwd, branch)?.path,
+        None => git::get_repository_root(&ctx.cwd)?,
+    };
+
+    if !worktree.exists() {
+        return Err(DevError::WorktreePathMissing(
+            worktree.display().to_string(),
+        ));
+    }
+
+    let (file, line) = match file {
+        Some(spec) => {
+            let (path, line) = parse_file_spec(spec);
+            let path = worktree.join(path);
+            if !path.exists() {
+                return Err(DevError::Other(format!(
+                    "No such file in the worktree: {}",
+                    spec
+                )));
+            }
+            (Some(path), line)
+        }
+        None => (None, None),
+    };
+
+    let overrides = Overrides {
+        editor_wait: if wait {
+            Some(true)
+        } else {
+            overrides.editor_wait
+        },
+        ..overrides.clone()
+    };
+    let settings = ctx.settings(&worktree, &overrides)?;
+    let env = settings.env_for(ctx.git, &worktree);
+
+    let target = Target {
+        worktree: &worktree,
+        file: file.as_deref(),
+        line,
+    };
+
+    out.info("Opening in editor...");
+    super::open_in_editor(ctx, &settings, &target, &env)
+}
+
+/// Split `PATH[:LINE]` into the path and the line, if one is given
+fn parse_file_spec(spec: &str) -> (&Path, Option<u32>) {
+    match spec.rsplit_once(':') {
+        Some((path, line)) if !path.is_empty() => match line.parse() {
+            Ok(line) => (Path::new(path), Some(line)),
+            Err(_) => (Path::new(spec), None),
+        },
+        _ => (Path::new(spec), None),
+    }
+}
@@ -1,39 +1,38 @@
 use colored::Colorize;
 
-use crate::config::{GlobalConfig, ProjectConfig};
-use crate::editor;
+use crate::api;
+use crate::config::{EffectiveConfig, Overrides, ProjectConfig};
+use crate::context::Context;
 use crate::error::{DevError, Result};
-use crate::git::list_worktrees;
-use crate::shell::devbox;
-use crate::ui::output::{info, success};
+use crate::shell::{devbox, integration};
+use crate::ui::json::{self, ProjectJson};
+use crate::ui::output::OutputFormat;
 
 /// Open a project by name
-pub fn run(project_name: &str) -> Result<()> {
-    let config = GlobalConfig::load()?;
+pub fn run(ctx: &Context, project_name: &str, overrides: &Overrides) -> Result<()> {
+    let out = &ctx.out;
 
     // Look up project in registry
-    let mut project = match ProjectConfig::load(project_name) {
+    let mut project = match api::find_project(&ctx.config_dir, project_name) {
         Ok(p) => p,
         Err(DevError::ProjectNotFound(_)) => {
             // Project not found - show available projects
-            eprintln!(
-                "{}",
-                format!("Project '{}' not found", project_name).red()
-            );
-            println!();
-            println!("{}", "Registered projects:".yellow());
-
-            let projects = ProjectConfig::list_all()?;
+            out.eprintln(format!("Project '{}' not found", project_name).red());
+            out.println("");
+            out.println("Registered projects:".yellow());
+
+            let projects = api::list_projects(&ctx.config_dir)?;
             if projects.is_empty() {
-                println!("  {}", "(no projects registered)".dimmed());
-                println!();
-                println!(
-                    "{}",
-                    "Use 'dev init <name>' to create a new project".dimmed()
-                );
+                out.println(format!("  {}", "(no projects registered)".dimmed()));
+                out.println("");
+                out.println("Use 'dev init <name>' to create a new project".dimmed());
             } else {
                 for p in &projects {
-                    println!("  {} - {}", p.name.cyan(), p.path.display().to_string().dimmed());
+                    out.println(format!(
+                        "  {} - {}",
+                        p.name.cyan(),
+                        p.path.display().to_string().dimmed()
+                    ));
                 }
             }
 
@@ -43,7 +42,7 @@ pub fn run(project_name: &str) -> Result<()> {
     };
 
     // Update last accessed
-    project.touch_accessed()?;
+    project.touch_accessed(&ctx.config_dir)?;
 
     // Check if project path exists
     if !project.path.exists() {
@@ -53,43 +52,61 @@ pub fn run(project_name: &str) -> Result<()> {
         )));
     }
 
-    info(&format!("Opening project '{}'...", project_name));
+    out.info(&format!("Opening project '{}'...", project_name));
 
     // Find the best worktree to open (main/master/default, or project root)
-    let target_path = find_main_worktree(&project)?;
+    let target_path = find_main_worktree(ctx, &project)?;
+
+    let settings = EffectiveConfig::for_project(&ctx.config_dir, project.clone(), overrides)?;
+
+    // Move the shell there when the wrapper is installed
+    let enter_devbox = devbox::should_enter(&settings, &target_path);
+    let moved_shell = integration::request_cd(&target_path, enter_devbox)?;
 
     // Open in editor
-    editor::open(&target_path, &config)?;
+    let env = settings.env_for(ctx.git, &target_path);
+    super::open_editor(ctx, &settings, &target_path, &env)?;
 
-    success(&format!("Opened project '{}'", project_name));
-    println!("  {}: {}", "Path".dimmed(), target_path.display());
+    out.success(&format!("Opened project '{}'", project_name));
+    out.println(format!("  {}: {}", "Path".dimmed(), target_path.display()));
 
-    // Handle devbox shell
-    if project.uses_devbox && config.auto_devbox {
-        println!();
-        if devbox::has_devbox_config(&target_path) {
-            devbox::print_devbox_instructions(&target_path);
-        }
+    // Without the wrapper, explain how to enter the devbox shell
+    if !moved_shell && enter_devbox {
+        out.println("");
+        devbox::print_devbox_instructions(out, &target_path);
     }
 
     Ok(())
 }
 
 /// List all registered projects
-pub fn list_projects() -> Result<()> {
-    let projects = ProjectConfig::list_all()?;
+pub fn list_projects(ctx: &Context, format: OutputFormat) -> Result<()> {
+    let out = &ctx.out;
+    let projects = api::list_projects(&ctx.config_dir)?;
+
+    match format {
+        OutputFormat::Json => {
+            let projects: Vec<ProjectJson> = projects.iter().map(ProjectJson::from).collect();
+            return json::print(out, "projects", projects);
+        }
+        OutputFormat::Plain => {
+            // One tab-separated line per project: name, path
+            for project in &projects {
+                out.println(format!("{}\t{}", project.name, project.path.display()));
+            }
+            return Ok(());
+        }
+        OutputFormat::Table => {}
+    }
 
     if projects.is_empty() {
-        println!("{}", "No projects registered".yellow());
-        println!();
-        println!(
-            "{}",
-            "Use 'dev init <name>' to create a new project".dimmed()
-        );
+        out.println("No projects registered".yellow());
+        out.println("");
+        out.println("Use 'dev init <name>' to create a new project".dimmed());
         return Ok(());
     }
 
-    println!("{}", "\n📁 Registered Projects:\n".bold());
+    out.println("\n📁 Registered Projects:\n".bold());
 
     for project in &projects {
         let name = project.name.cyan();
@@ -100,25 +117,28 @@ pub fn list_projects() -> Result<()> {
             String::new()
         };
 
-        println!("  {}{}", name, devbox_indicator);
-        println!("    {}", path);
+        out.println(format!("  {}{}", name, devbox_indicator));
+        out.println(format!("    {}", path));
     }
 
-    println!();
-    println!("{}", "Legend: 📦 = uses devbox".dimmed());
+    out.println("");
+    out.println("Legend: 📦 = uses devbox".dimmed());
 
     Ok(())
 }
 
-/// Find the main worktree to open (main, master, or project root)
-fn find_main_worktree(project: &ProjectConfig) -> Result<std::path::PathBuf> {
-    // Try to get worktrees if this is a git repo
-    if let Ok(worktrees) = list_worktrees() {
-        // Prefer main, then master, then first non-bare worktree
-        for preferred in &["main", "master"] {
-            if let Some(wt) = worktrees.iter().find(|wt| {
-                wt.branch.as_deref() == Some(*preferred)
-            }) {
+/// Find the main worktree to open (default branch, main, master, or project root)
+fn find_main_worktree(ctx: &Context, project: &ProjectConfig) -> Result<std::path::PathBuf> {
+    // Try to get worktrees if this is a git repo (or a bare hub)
+    if let Ok(worktrees) = ctx.git.list_worktrees(&project.path) {
+        let default_branch = ctx.git.default_branch(&project.path);
+
+        // Prefer the default branch, then main, then master, then first non-bare worktree
+        for preferred in [default_branch.as_str(), "main", "master"] {
+            if let Some(wt) = worktrees
+                .iter()
+                .find(|wt| wt.branch.as_deref() == Some(preferred))
+            {
                 if wt.path.exists() {
                     return Ok(wt.path.clone());
                 }
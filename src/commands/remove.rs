@@ -0,0 +1,141 @@
+use colored::Colorize;
+
+use crate::config::Overrides;
+use crate::context::Context;
+use crate::error::{DevError, Result};
+use crate::git::{
+    branch_exists_locally, branch_exists_on_remote, delete_remote_branch, find_worktree_by_branch,
+    get_main_worktree, is_git_repository,
+};
+use crate::hooks::HookEvent;
+use crate::shell::integration;
+
+/// Remove the worktree for a branch
+///
+/// `delete_local` and `delete_remote` say what to do with the local and
+/// remote branch; None asks.
+pub fn run(
+    ctx: &Context,
+    branch: &str,
+    force: bool,
+    delete_local: Option<bool>,
+    delete_remote: Option<bool>,
+    overrides: &Overrides,
+) -> Result<()> {
+    let out = &ctx.out;
+
+    if !is_git_repository(&ctx.cwd) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    let branch = branch.trim();
+    if branch.is_empty() {
+        return Err(DevError::Other("Branch name is required".to_string()));
+    }
+
+    let worktree = find_worktree_by_branch(ctx.git, &ctx.cwd, branch)?
+        .ok_or_else(|| DevError::WorktreeNotFound(branch.to_string()))?;
+
+    let main_worktree = get_main_worktree(ctx.git, &ctx.cwd)?
+        .ok_or_else(|| DevError::GitError("Cannot determine main worktree".to_string()))?;
+
+    // The current directory may be the worktree being removed, so run git
+    // from the main worktree
+    let repo = main_worktree.path.as_path();
+
+    if worktree.path == main_worktree.path {
+        return Err(DevError::Other(format!(
+            "Cannot remove the main worktree ({})",
+            main_worktree.path.display()
+        )));
+    }
+
+    // Refuse to throw away work unless forced, including work that can't
+    // be checked for; a directory that is already gone has none left
+    if !force && worktree.path.exists() {
+        let status = worktree
+            .load_status(ctx.git)
+            .ok_or_else(|| DevError::StatusUnknown(branch.to_string()))?;
+        if status.has_changes() || status.operation.is_some() {
+            return Err(DevError::UncommittedChanges(branch.to_string()));
+        }
+        if status.unpushed() > 0 {
+            return Err(DevError::UnpushedCommits(
+                branch.to_string(),
+                status.unpushed(),
+            ));
+        }
+    }
+
+    // Settle what happens to the branches before changing anything, so a
+    // missing answer can't leave the removal half done
+    let delete_local = branch_exists_locally(ctx.git, repo, branch)
+        && match delete_local {
+            Some(delete) => delete,
+            None => ctx.prompter.confirm(
+                &format!("Delete local branch '{}'?", branch),
+                false,
+                "--delete-branch or --keep-branch",
+            )?,
+        };
+
+    let delete_remote = branch_exists_on_remote(ctx.git, repo, branch)
+        && match delete_remote {
+            Some(delete) => delete,
+            None => ctx.prompter.confirm(
+                &format!("Delete remote branch 'origin/{}'?", branch),
+                false,
+                "--delete-remote or --keep-remote",
+            )?,
+        };
+
+    let settings = ctx.settings(repo, overrides)?;
+
+    if worktree.path.exists() {
+        let env = settings.env_for(ctx.git, &worktree.path);
+        super::run_hooks(ctx, &settings, HookEvent::PreRemove, &worktree.path, &env)?;
+    }
+
+    // Step out of the worktree before deleting it
+    if worktree.is_current {
+        out.info(&format!(
+            "Moving to main worktree at {}...",
+            main_worktree.path.display()
+        ));
+
+        let env = settings.env_for(ctx.git, repo);
+        if let Err(e) = super::open_editor(ctx, &settings, repo, &env) {
+            out.warning(&e.to_string());
+        }
+    }
+
+    out.info(&format!("Removing worktree for branch '{}'...", branch));
+
+    // Installed dependencies are ignored files in the worktree and install
+    // records in its git dir, so they go with it, and stay if it can't be
+    // removed
+    ctx.git.remove_worktree(repo, &worktree.path, force)?;
+
+    out.success(&format!("Worktree removed: {}", worktree.path.display()));
+
+    if delete_local {
+        match ctx.git.delete_branch(repo, branch, force) {
+            Ok(()) => out.success(&format!("Deleted local branch '{}'", branch)),
+            Err(e) => out.warning(&format!("{} (use --force to delete anyway)", e)),
+        }
+    }
+
+    if delete_remote {
+        out.info(&format!("Deleting remote branch 'origin/{}'...", branch));
+        delete_remote_branch(repo, branch)?;
+        out.success(&format!("Deleted remote branch 'origin/{}'", branch));
+    }
+
+    if worktree.is_current && !integration::request_cd(repo, false)? {
+        out.println("");
+        out.println("Your shell is still in the removed directory:".yellow());
+        out.println(format!("  cd {}", repo.display()));
+    }
+
+    Ok(())
+}
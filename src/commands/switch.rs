@@ -1,17 +1,16 @@
 use colored::Colorize;
 
-use crate::editor;
+use crate::api;
+use crate::config::Overrides;
+use crate::context::Context;
 use crate::error::{DevError, Result};
-use crate::git::{find_worktree_by_branch, is_git_repository, list_worktrees};
-use crate::package_manager;
-use crate::ui::output::{info, status_label, success};
+use crate::hooks::HookEvent;
+use crate::package_manager::DepsStatus;
+use crate::shell::{devbox, integration};
+use crate::ui::output::status_label;
 
-pub fn run(branch: &str) -> Result<()> {
-    let current_dir = std::env::current_dir()?;
-
-    if !is_git_repository(&current_dir) {
-        return Err(DevError::NotGitRepository);
-    }
+pub fn run(ctx: &Context, branch: &str, overrides: &Overrides) -> Result<()> {
+    let out = &ctx.out;
 
     let branch = branch.trim();
     if branch.is_empty() {
@@ -19,15 +18,13 @@ pub fn run(branch: &str) -> Result<()> {
     }
 
     // Find the worktree for the specified branch
-    let worktree = find_worktree_by_branch(branch)?;
-
-    let worktree = match worktree {
-        Some(wt) => wt,
-        None => {
-            eprintln!("{}", format!("No worktree found for branch '{}'", branch).red());
-            println!("{}", "Available branches:".yellow());
+    let worktree = match api::find_worktree(ctx.git, &ctx.cwd, branch) {
+        Ok(wt) => wt,
+        Err(DevError::WorktreeNotFound(_)) => {
+            out.eprintln(format!("No worktree found for branch '{}'", branch).red());
+            out.println("Available branches:".yellow());
 
-            let worktrees = list_worktrees()?;
+            let worktrees = ctx.git.list_worktrees(&ctx.cwd)?;
             for wt in &worktrees {
                 if let Some(ref b) = wt.branch {
                     if !wt.is_bare && !wt.is_detached {
@@ -36,24 +33,22 @@ pub fn run(branch: &str) -> Result<()> {
                         } else {
                             "  ".normal()
                         };
-                        println!("{}{}", current, b);
+                        out.println(format!("{}{}", current, b));
                     }
                 }
             }
 
-            println!();
-            println!(
-                "{}",
-                format!("To create a new worktree: dev --create {}", branch).dimmed()
-            );
+            out.println("");
+            out.println(format!("To create a new worktree: dev --create {}", branch).dimmed());
 
             return Err(DevError::WorktreeNotFound(branch.to_string()));
         }
+        Err(e) => return Err(e),
     };
 
     // Check if already on this branch
     if worktree.is_current {
-        println!("{}", format!("Already on branch '{}'", branch).yellow());
+        out.println(format!("Already on branch '{}'", branch).yellow());
         return Ok(());
     }
 
@@ -64,31 +59,55 @@ pub fn run(branch: &str) -> Result<()> {
         ));
     }
 
-    info(&format!("Switching to branch '{}'...", branch));
+    let settings = ctx.settings(&ctx.cwd, overrides)?;
+    let env = settings.env_for(ctx.git, &worktree.path);
+
+    out.info(&format!("Switching to branch '{}'...", branch));
+
+    // Install when nothing is installed or the lockfile changed since
+    if settings.auto_install_deps.value {
+        let deps = settings.deps_status(&worktree.path)?;
+        let message = match deps {
+            Some(DepsStatus::Missing) => Some("Dependencies not found, installing..."),
+            Some(DepsStatus::Stale) => Some("Dependencies are stale, reinstalling..."),
+            _ => None,
+        };
 
-    // Check if dependencies need to be installed
-    if !package_manager::has_node_modules(&worktree.path) {
-        info("Dependencies not found, installing...");
-        package_manager::install_dependencies(&worktree.path)?;
+        if let Some(message) = message {
+            out.info(message);
+            super::install_dependencies(out, ctx.git, &settings, &worktree.path, &env, false)?;
+        }
     }
 
+    super::run_hooks(ctx, &settings, HookEvent::PostSwitch, &worktree.path, &env)?;
+
+    // Move the shell into the worktree when the wrapper is installed
+    integration::request_cd(
+        &worktree.path,
+        devbox::should_enter(&settings, &worktree.path),
+    )?;
+
     // Open in editor
-    info("Opening in editor...");
-    let config = crate::config::GlobalConfig::load()?;
-    editor::open(&worktree.path, &config)?;
-
-    println!();
-    success(&format!("Switched to '{}'", branch));
-    println!("  {}: {}", "Path".dimmed(), worktree.path.display());
-    println!(
+    out.info("Opening in editor...");
+    super::open_editor(ctx, &settings, &worktree.path, &env)?;
+
+    out.println("");
+    out.success(&format!("Switched to '{}'", branch));
+    out.println(format!(
+        "  {}: {}",
+        "Path".dimmed(),
+        worktree.path.display()
+    ));
+    out.println(format!(
         "  {}: {}",
         "Commit".dimmed(),
         &worktree.commit[..7.min(worktree.commit.len())]
-    );
+    ));
 
     // Show status if not clean
-    if worktree.status != crate::git::WorktreeStatus::Clean {
-        println!("  {}: {}", "Status".dimmed(), status_label(&worktree.status));
+    let status = worktree.load_status(ctx.git);
+    if !status.is_some_and(|s| s.is_clean()) {
+        out.println(format!("  {}: {}", "Status".dimmed(), status_label(status)));
     }
 
     Ok(())
@@ -0,0 +1,81 @@
+use colored::Colorize;
+
+use crate::config::Overrides;
+use crate::context::Context;
+use crate::error::{DevError, Result};
+use crate::git::{find_worktree_by_branch, get_main_worktree, is_git_repository};
+
+/// Copy the project's `worktree_files` from the main worktree into one
+/// worktree, or all of them
+pub fn run(
+    ctx: &Context,
+    branch: Option<&str>,
+    dry_run: bool,
+    overrides: &Overrides,
+) -> Result<()> {
+    let out = &ctx.out;
+
+    if !is_git_repository(&ctx.cwd) {
+        return Err(DevError::NotGitRepository);
+    }
+
+    let main = get_main_worktree(ctx.git, &ctx.cwd)?
+        .ok_or_else(|| DevError::GitError("Cannot determine main worktree".to_string()))?;
+
+    let settings = ctx.settings(&main.path, overrides)?;
+
+    match &settings.project {
+        Some(project) if !project.worktree_files.is_empty() => {}
+        Some(project) => {
+            out.println(
+                format!(
+                    "Project '{}' has no worktree_files configured",
+                    project.name
+                )
+                .yellow(),
+            );
+            return Ok(());
+        }
+        None => {
+            out.println(
+                "worktree_files are set per project; this repository isn't registered".yellow(),
+            );
+            return Ok(());
+        }
+    }
+
+    let worktrees = match branch {
+        Some(branch) => vec![find_worktree_by_branch(ctx.git, &ctx.cwd, branch)?
+            .ok_or_else(|| DevError::WorktreeNotFound(branch.to_string()))?],
+        None => ctx.git.list_worktrees(&ctx.cwd)?,
+    };
+
+    let mut changed = 0;
+
+    for wt in &worktrees {
+        if wt.is_bare || wt.path == main.path || !wt.path.exists() {
+            continue;
+        }
+
+        let name = wt.branch.as_deref().unwrap_or("(detached)");
+        out.println(format!(
+            "{} {}",
+            name.cyan(),
+            wt.path.display().to_string().dimmed()
+        ));
+
+        let env = settings.env_for(ctx.git, &wt.path);
+        changed += super::sync_worktree_files(out, &settings, &main.path, &wt.path, &env, dry_run)?;
+    }
+
+    out.println("");
+    if dry_run {
+        out.println(format!("{} file(s) would be updated (dry run)", changed).yellow());
+    } else if changed == 0 {
+        out.success("All worktree files are up to date");
+    } else {
+        out.success(&format!("{} file(s) updated", changed));
+    }
+
+    Ok(())
+}
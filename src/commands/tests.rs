@@ -0,0 +1,696 @@
+//! Commands run against throwaway repositories, with scripted prompts and
+//! captured output
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use tempfile::TempDir;
+
+use crate::config::Overrides;
+use crate::context::Context;
+use crate::error::{DevError, Result};
+use crate::git::{backend, GitBackend, WorktreeInfo, WorktreeStatus};
+use crate::ui::output::Output;
+use crate::ui::prompts::{NoPrompts, Prompter};
+
+use super::{cleanup, create, remove, switch};
+
+/// An answer the scripted prompter gives, in order
+#[derive(Debug)]
+enum Answer {
+    Confirm(bool),
+    Select(usize),
+}
+
+/// Answers prompts from a script, failing the test on an unexpected prompt
+struct ScriptedPrompter {
+    answers: RefCell<VecDeque<Answer>>,
+}
+
+impl ScriptedPrompter {
+    fn new(answers: Vec<Answer>) -> Self {
+        Self {
+            answers: RefCell::new(answers.into()),
+        }
+    }
+
+    fn next(&self, message: &str) -> Answer {
+        self.answers
+            .borrow_mut()
+            .pop_front()
+            .unwrap_or_else(|| panic!("unexpected prompt: {}", message))
+    }
+}
+
+impl Drop for ScriptedPrompter {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            let answers = self.answers.borrow();
+            assert!(answers.is_empty(), "unused answers: {:?}", answers);
+        }
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn confirm(&self, message: &str, _default: bool, _flags: &str) -> Result<bool> {
+        match self.next(message) {
+            Answer::Confirm(answer) => Ok(answer),
+            answer => panic!("expected {:?} for confirm: {}", answer, message),
+        }
+    }
+
+    fn proceed(&self, message: &str) -> Result<bool> {
+        self.confirm(message, false, "--yes")
+    }
+
+    fn input(&self, message: &str, _default: Option<&str>, _flags: &str) -> Result<String> {
+        panic!("unexpected input prompt: {}", message)
+    }
+
+    fn select(&self, message: &str, items: &[String], _flags: &str) -> Result<usize> {
+        match self.next(message) {
+            Answer::Select(index) if index < items.len() => Ok(index),
+            answer => panic!("expected {:?} for select: {}", answer, message),
+        }
+    }
+}
+
+/// Output written to memory, readable after the command has run
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The real backend, except that status is never available
+struct NoStatus;
+
+impl GitBackend for NoStatus {
+    fn resolve_ref(&self, repo: &Path, refname: &str) -> Result<Option<String>> {
+        backend().resolve_ref(repo, refname)
+    }
+
+    fn current_branch(&self, worktree: &Path) -> Result<Option<String>> {
+        backend().current_branch(worktree)
+    }
+
+    fn default_branch(&self, repo: &Path) -> String {
+        backend().default_branch(repo)
+    }
+
+    fn list_branches(&self, repo: &Path, include_remote: bool) -> Result<Vec<String>> {
+        backend().list_branches(repo, include_remote)
+    }
+
+    fn delete_branch(&self, repo: &Path, name: &str, force: bool) -> Result<()> {
+        backend().delete_branch(repo, name, force)
+    }
+
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>> {
+        backend().list_worktrees(repo)
+    }
+
+    fn add_worktree(
+        &self,
+        repo: &Path,
+        branch: &str,
+        path: &Path,
+        create_branch: bool,
+    ) -> Result<()> {
+        backend().add_worktree(repo, branch, path, create_branch)
+    }
+
+    fn remove_worktree(&self, repo: &Path, path: &Path, force: bool) -> Result<()> {
+        backend().remove_worktree(repo, path, force)
+    }
+
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+        backend().move_worktree(repo, from, to)
+    }
+
+    fn prune_worktrees(&self, repo: &Path) -> Result<()> {
+        backend().prune_worktrees(repo)
+    }
+
+    fn is_branch_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        backend().is_branch_merged(repo, branch, target)
+    }
+
+    fn is_squash_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        backend().is_squash_merged(repo, branch, target)
+    }
+
+    fn has_own_commits(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        backend().has_own_commits(repo, branch, target)
+    }
+
+    fn is_upstream_gone(&self, repo: &Path, branch: &str) -> bool {
+        backend().is_upstream_gone(repo, branch)
+    }
+
+    fn last_commit_date(&self, repo: &Path, branch: &str) -> Option<DateTime<Utc>> {
+        backend().last_commit_date(repo, branch)
+    }
+
+    fn status(
+        &self,
+        _worktree: &Path,
+        _branch: Option<&str>,
+        _deadline: Instant,
+    ) -> Option<WorktreeStatus> {
+        None
+    }
+}
+
+static NO_STATUS: NoStatus = NoStatus;
+
+/// A temporary directory holding a config dir and a repository on `main`
+struct Sandbox {
+    _tmp: TempDir,
+    root: PathBuf,
+    repo: PathBuf,
+    config_dir: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let tmp = TempDir::new().unwrap();
+        // Resolve symlinks (e.g. /tmp on macOS) so paths compare equal to git's
+        let root = tmp.path().canonicalize().unwrap();
+
+        let config_dir = root.join("config");
+        std::fs::create_dir(&config_dir).unwrap();
+        let config = serde_json::json!({
+            "editor": "true",
+            "dev_path": root.join("dev"),
+            "auto_install_deps": false,
+            "auto_devbox": false,
+        });
+        std::fs::write(config_dir.join("config.json"), config.to_string()).unwrap();
+
+        let repo = root.join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        commit(&repo, "README", "hello\n");
+
+        Self {
+            _tmp: tmp,
+            root,
+            repo,
+            config_dir,
+        }
+    }
+
+    /// Replace the repository with a clone of it that has a `remote-only` branch
+    fn with_clone(mut self) -> Self {
+        git(&self.repo, &["branch", "remote-only"]);
+        git(&self.root, &["clone", "-q", "repo", "clone"]);
+        self.repo = self.root.join("clone");
+        self
+    }
+
+    fn add_worktree(&self, branch: &str) -> PathBuf {
+        let path = self.root.join(branch);
+        git(
+            &self.repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                branch,
+                path.to_str().unwrap(),
+            ],
+        );
+        path
+    }
+
+    fn run(
+        &self,
+        answers: Vec<Answer>,
+        command: impl FnOnce(&Context) -> Result<()>,
+    ) -> (Result<()>, String, String) {
+        self.run_with(backend(), Box::new(ScriptedPrompter::new(answers)), command)
+    }
+
+    fn run_with(
+        &self,
+        git: &'static dyn GitBackend,
+        prompter: Box<dyn Prompter>,
+        command: impl FnOnce(&Context) -> Result<()>,
+    ) -> (Result<()>, String, String) {
+        let stdout = Captured::default();
+        let stderr = Captured::default();
+
+        let ctx = Context {
+            cwd: self.repo.clone(),
+            config_dir: self.config_dir.clone(),
+            git,
+            prompter,
+            out: Output::new(stdout.clone(), stderr.clone()),
+        };
+
+        let result = command(&ctx);
+        (result, stdout.text(), stderr.text())
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .output()
+        .expect("failed to run git");
+
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn commit(dir: &Path, file: &str, contents: &str) {
+    std::fs::write(dir.join(file), contents).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", file]);
+}
+
+// create
+
+#[test]
+fn create_adds_worktree_for_new_branch() {
+    let sandbox = Sandbox::new();
+
+    let (result, stdout, _) = sandbox.run(vec![], |ctx| {
+        create::run(ctx, "feature", None, &Overrides::default())
+    });
+
+    result.unwrap();
+    let path = sandbox.root.join("feature");
+    assert!(path.join("README").exists());
+    assert_eq!(git(&path, &["branch", "--show-current"]), "feature");
+    assert!(stdout.contains("Ready to work on 'feature'"));
+}
+
+#[test]
+fn create_refuses_existing_local_branch() {
+    let sandbox = Sandbox::new();
+    git(&sandbox.repo, &["branch", "topic"]);
+
+    let (result, _, _) = sandbox.run(vec![], |ctx| {
+        create::run(ctx, "topic", None, &Overrides::default())
+    });
+
+    assert!(matches!(result, Err(DevError::BranchExistsLocally(ref b)) if b == "topic"));
+    assert!(!sandbox.root.join("topic").exists());
+}
+
+#[test]
+fn create_checks_out_remote_branch() {
+    let sandbox = Sandbox::new().with_clone();
+
+    let (result, _, _) = sandbox.run(vec![Answer::Select(0)], |ctx| {
+        create::run(ctx, "remote-only", None, &Overrides::default())
+    });
+
+    result.unwrap();
+    let path = sandbox.root.join("remote-only");
+    assert_eq!(
+        git(&path, &["rev-parse", "--abbrev-ref", "@{upstream}"]),
+        "origin/remote-only"
+    );
+}
+
+#[test]
+fn create_cancelled_at_remote_branch_prompt() {
+    let sandbox = Sandbox::new().with_clone();
+
+    let (result, stdout, _) = sandbox.run(vec![Answer::Select(2)], |ctx| {
+        create::run(ctx, "remote-only", None, &Overrides::default())
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Operation cancelled"));
+    assert!(!sandbox.root.join("remote-only").exists());
+}
+
+#[test]
+fn create_without_input_names_the_flags() {
+    let sandbox = Sandbox::new().with_clone();
+
+    let (result, _, _) = sandbox.run_with(
+        backend(),
+        Box::new(NoPrompts { assume_yes: false }),
+        |ctx| create::run(ctx, "remote-only", None, &Overrides::default()),
+    );
+
+    match result {
+        Err(DevError::InputRequired(_, flags)) => {
+            assert_eq!(flags, "--track-remote or --diverge")
+        }
+        other => panic!("expected InputRequired, got {:?}", other),
+    }
+    assert!(!sandbox.root.join("remote-only").exists());
+}
+
+#[test]
+fn create_diverges_from_remote_when_told() {
+    let sandbox = Sandbox::new().with_clone();
+
+    let (result, _, _) = sandbox.run(vec![], |ctx| {
+        create::run(ctx, "remote-only", Some(false), &Overrides::default())
+    });
+
+    result.unwrap();
+    let path = sandbox.root.join("remote-only");
+    assert_eq!(git(&path, &["branch", "--show-current"]), "remote-only");
+    // A new branch, not one tracking origin/remote-only
+    assert!(!git(&path, &["config", "--list"]).contains("branch.remote-only.remote"));
+}
+
+#[test]
+fn create_track_remote_needs_remote_branch() {
+    let sandbox = Sandbox::new();
+
+    let (result, _, _) = sandbox.run(vec![], |ctx| {
+        create::run(ctx, "feature", Some(true), &Overrides::default())
+    });
+
+    assert!(matches!(result, Err(DevError::BranchNotFound(ref b)) if b == "origin/feature"));
+}
+
+// switch
+
+#[test]
+fn switch_to_existing_worktree() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    let (result, stdout, _) = sandbox.run(vec![], |ctx| {
+        switch::run(ctx, "feature", &Overrides::default())
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Switched to 'feature'"));
+}
+
+#[test]
+fn switch_to_unknown_branch_lists_available() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    let (result, stdout, stderr) = sandbox.run(vec![], |ctx| {
+        switch::run(ctx, "missing", &Overrides::default())
+    });
+
+    assert!(matches!(result, Err(DevError::WorktreeNotFound(ref b)) if b == "missing"));
+    assert!(stderr.contains("No worktree found for branch 'missing'"));
+    assert!(stdout.contains("feature"));
+}
+
+#[test]
+fn switch_to_current_branch_is_a_no_op() {
+    let sandbox = Sandbox::new();
+
+    let (result, stdout, _) = sandbox.run(vec![], |ctx| {
+        switch::run(ctx, "main", &Overrides::default())
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Already on branch 'main'"));
+}
+
+// remove
+
+#[test]
+fn remove_refuses_when_status_is_unknown() {
+    let sandbox = Sandbox::new();
+    let path = sandbox.add_worktree("feature");
+
+    let remove = |ctx: &Context, force| {
+        remove::run(
+            ctx,
+            "feature",
+            force,
+            Some(false),
+            Some(false),
+            &Overrides::default(),
+        )
+    };
+
+    let (result, _, _) =
+        sandbox.run_with(&NO_STATUS, Box::new(ScriptedPrompter::new(vec![])), |ctx| {
+            remove(ctx, false)
+        });
+    assert!(matches!(result, Err(DevError::StatusUnknown(ref b)) if b == "feature"));
+    assert!(path.exists());
+
+    let (result, _, _) =
+        sandbox.run_with(&NO_STATUS, Box::new(ScriptedPrompter::new(vec![])), |ctx| {
+            remove(ctx, true)
+        });
+    result.unwrap();
+    assert!(!path.exists());
+}
+
+// cleanup
+
+/// A worktree for `branch` with a commit that has been merged into `main`
+fn merged_worktree(sandbox: &Sandbox, branch: &str) -> PathBuf {
+    let path = sandbox.add_worktree(branch);
+    commit(&path, &format!("{}.txt", branch), "merged\n");
+    git(&sandbox.repo, &["merge", "-q", "--ff-only", branch]);
+    path
+}
+
+#[test]
+fn cleanup_removes_merged_worktree_and_branch() {
+    let sandbox = Sandbox::new();
+    let path = merged_worktree(&sandbox, "done");
+
+    let (result, stdout, _) = sandbox
+        .run(vec![Answer::Confirm(true), Answer::Confirm(true)], |ctx| {
+            cleanup::run(ctx, None, None)
+        });
+
+    result.unwrap();
+    assert!(stdout.contains("Cleanup complete: 1 removed"));
+    assert!(!path.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "done"]), "");
+}
+
+#[test]
+fn cleanup_keeps_everything_when_declined() {
+    let sandbox = Sandbox::new();
+    let path = merged_worktree(&sandbox, "done");
+
+    let (result, stdout, _) = sandbox.run(vec![Answer::Confirm(false)], |ctx| {
+        cleanup::run(ctx, None, None)
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Cleanup cancelled"));
+    assert!(path.exists());
+}
+
+#[test]
+fn cleanup_skips_worktrees_with_unknown_status() {
+    let sandbox = Sandbox::new();
+    let path = merged_worktree(&sandbox, "done");
+
+    let (result, stdout, _) =
+        sandbox.run_with(&NO_STATUS, Box::new(ScriptedPrompter::new(vec![])), |ctx| {
+            cleanup::run(ctx, None, None)
+        });
+
+    result.unwrap();
+    assert!(stdout.contains("All worktrees are in good condition"));
+    assert!(path.exists());
+}
+
+#[test]
+fn cleanup_keeps_unmerged_work() {
+    let sandbox = Sandbox::new();
+    let path = sandbox.add_worktree("wip");
+    commit(&path, "wip.txt", "unmerged\n");
+
+    let (result, stdout, _) = sandbox.run(vec![], |ctx| cleanup::run(ctx, None, None));
+
+    result.unwrap();
+    assert!(stdout.contains("All worktrees are in good condition"));
+    assert!(path.exists());
+}
+
+#[test]
+fn cleanup_with_yes_removes_worktree_but_keeps_branch() {
+    let sandbox = Sandbox::new();
+    let path = merged_worktree(&sandbox, "done");
+
+    let (result, _, _) =
+        sandbox.run_with(backend(), Box::new(NoPrompts { assume_yes: true }), |ctx| {
+            cleanup::run(ctx, None, None)
+        });
+
+    result.unwrap();
+    assert!(!path.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "done"]), "done");
+}
+
+#[test]
+fn cleanup_keeps_branches_when_told() {
+    let sandbox = Sandbox::new();
+    let path = merged_worktree(&sandbox, "done");
+
+    let (result, _, _) = sandbox.run(vec![Answer::Confirm(true)], |ctx| {
+        cleanup::run(ctx, None, Some(false))
+    });
+
+    result.unwrap();
+    assert!(!path.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "done"]), "done");
+}
+
+#[test]
+fn cleanup_leaves_worktrees_without_commits() {
+    let sandbox = Sandbox::new();
+    let path = sandbox.add_worktree("fresh");
+
+    let (result, stdout, _) = sandbox.run(vec![], |ctx| cleanup::run(ctx, None, None));
+
+    result.unwrap();
+    assert!(stdout.contains("All worktrees are in good condition"));
+    assert!(path.exists());
+}
+
+#[test]
+fn cleanup_finds_branches_merged_with_a_merge_commit() {
+    let sandbox = Sandbox::new();
+    let path = sandbox.add_worktree("done");
+    commit(&path, "done.txt", "merged\n");
+    git(
+        &sandbox.repo,
+        &["merge", "-q", "--no-ff", "--no-edit", "done"],
+    );
+
+    let (result, stdout, _) = sandbox.run(vec![Answer::Confirm(true)], |ctx| {
+        cleanup::run(ctx, None, Some(true))
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Merged into main (1)"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn cleanup_keeps_gone_branch_with_unpushed_commits() {
+    let sandbox = Sandbox::new().with_clone();
+    let path = sandbox.add_worktree("gone");
+    commit(&path, "pushed.txt", "pushed\n");
+    git(&path, &["push", "-q", "-u", "origin", "gone"]);
+    commit(&path, "unpushed.txt", "unpushed\n");
+    let tip = git(&path, &["rev-parse", "HEAD"]);
+
+    let origin = sandbox.root.join("repo");
+    git(&origin, &["branch", "-D", "gone"]);
+    git(&sandbox.repo, &["fetch", "-q", "--prune"]);
+
+    let (result, stdout, _) =
+        sandbox.run_with(backend(), Box::new(NoPrompts { assume_yes: true }), |ctx| {
+            cleanup::run(ctx, None, None)
+        });
+
+    result.unwrap();
+    assert!(stdout.contains("Upstream deleted on remote (1)"));
+    assert!(!path.exists());
+    assert_eq!(git(&sandbox.repo, &["rev-parse", "gone"]), tip);
+}
+
+// hooks
+
+/// A worktree for `feature` whose `.dev.json` has a post_switch hook
+fn worktree_with_committed_hook(sandbox: &Sandbox) -> PathBuf {
+    let path = sandbox.add_worktree("feature");
+    let hooks = serde_json::json!({ "hooks": { "post_switch": ["echo ran >> ran"] } });
+    commit(&path, ".dev.json", &hooks.to_string());
+    path
+}
+
+#[test]
+fn committed_hooks_run_once_approved() {
+    let sandbox = Sandbox::new();
+    let path = worktree_with_committed_hook(&sandbox);
+
+    let (result, stdout, _) = sandbox.run(vec![Answer::Confirm(true)], |ctx| {
+        switch::run(ctx, "feature", &Overrides::default())
+    });
+    result.unwrap();
+    assert!(stdout.contains("echo ran >> ran"));
+
+    // Approved for as long as the file is unchanged
+    let (result, _, _) = sandbox.run(vec![], |ctx| {
+        switch::run(ctx, "feature", &Overrides::default())
+    });
+    result.unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(path.join("ran")).unwrap(),
+        "ran\nran\n"
+    );
+}
+
+#[test]
+fn committed_hooks_are_skipped_when_declined() {
+    let sandbox = Sandbox::new();
+    let path = worktree_with_committed_hook(&sandbox);
+
+    let (result, stdout, _) = sandbox.run(vec![Answer::Confirm(false)], |ctx| {
+        switch::run(ctx, "feature", &Overrides::default())
+    });
+
+    result.unwrap();
+    assert!(stdout.contains("Skipping post_switch hooks"));
+    assert!(!path.join("ran").exists());
+}
+
+#[test]
+fn yes_does_not_approve_committed_hooks() {
+    let sandbox = Sandbox::new();
+    let path = worktree_with_committed_hook(&sandbox);
+
+    let (result, _, _) =
+        sandbox.run_with(backend(), Box::new(NoPrompts { assume_yes: true }), |ctx| {
+            switch::run(ctx, "feature", &Overrides::default())
+        });
+
+    result.unwrap();
+    assert!(!path.join("ran").exists());
+    assert!(!sandbox.config_dir.join("trusted_hooks.json").exists());
+}
@@ -0,0 +1,267 @@
+use std::fmt;
+use std::path::Path;
+
+use super::global::EditorWindow;
+use super::{CommittedHooks, GlobalConfig, ProjectConfig, RepoConfig, WorktreeEnv};
+use crate::error::Result;
+use crate::git::GitBackend;
+use crate::hooks::{Hook, HookEvent, Hooks};
+use crate::package_manager::{self, CustomPackageManager, DepsStatus, Planned};
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Global config file
+    Global,
+    /// Per-project config
+    Project(String),
+    /// Command-line flag
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Global => write!(f, "global config"),
+            Source::Project(name) => write!(f, "project '{}'", name),
+            Source::Flag => write!(f, "command-line flag"),
+        }
+    }
+}
+
+/// A resolved setting value together with its origin
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// Settings given on the command line, which take precedence over config
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub editor: Option<String>,
+    pub editor_wait: Option<bool>,
+    pub auto_install_deps: Option<bool>,
+    /// Skip all hooks
+    pub no_hooks: bool,
+}
+
+/// Settings after merging global config, project config and command-line
+/// flags (flag > project > global)
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    pub editor: Setting<String>,
+    pub editor_window: Setting<EditorWindow>,
+    pub editor_wait: Setting<bool>,
+    pub auto_install_deps: Setting<bool>,
+    pub shared_deps: Setting<bool>,
+    pub auto_devbox: Setting<bool>,
+    pub worktree_path_template: Setting<String>,
+    /// Global then project hooks; None when hooks are turned off
+    pub hooks: Option<Hooks>,
+    /// Project then global package managers, checked before the built-in ones
+    pub package_managers: Vec<CustomPackageManager>,
+    /// The project the settings were resolved for, if any
+    pub project: Option<ProjectConfig>,
+}
+
+impl EffectiveConfig {
+    /// Resolve settings for the repository containing `path`
+    pub fn load(
+        config_dir: &Path,
+        git: &dyn GitBackend,
+        path: &Path,
+        overrides: &Overrides,
+    ) -> Result<Self> {
+        let global = GlobalConfig::load(config_dir)?;
+        let project = ProjectConfig::find_for_repo(config_dir, git, path)?;
+        Ok(Self::resolve(&global, project, overrides))
+    }
+
+    /// Resolve settings for a known project
+    pub fn for_project(
+        config_dir: &Path,
+        project: ProjectConfig,
+        overrides: &Overrides,
+    ) -> Result<Self> {
+        let global = GlobalConfig::load(config_dir)?;
+        Ok(Self::resolve(&global, Some(project), overrides))
+    }
+
+    /// Merge the layers with flag > project > global precedence
+    pub fn resolve(
+        global: &GlobalConfig,
+        project: Option<ProjectConfig>,
+        overrides: &Overrides,
+    ) -> Self {
+        let project_source = |p: &ProjectConfig| Source::Project(p.name.clone());
+
+        let editor = pick(
+            overrides.editor.clone(),
+            project
+                .as_ref()
+                .and_then(|p| p.editor.clone().map(|v| (v, project_source(p)))),
+            global.editor.clone(),
+        );
+
+        let editor_window = pick(
+            None,
+            project
+                .as_ref()
+                .and_then(|p| p.editor_window.map(|v| (v, project_source(p)))),
+            global.editor_window,
+        );
+
+        let editor_wait = pick(
+            overrides.editor_wait,
+            project
+                .as_ref()
+                .and_then(|p| p.editor_wait.map(|v| (v, project_source(p)))),
+            global.editor_wait,
+        );
+
+        let auto_install_deps = pick(
+            overrides.auto_install_deps,
+            project
+                .as_ref()
+                .and_then(|p| p.auto_install_deps.map(|v| (v, project_source(p)))),
+            global.auto_install_deps,
+        );
+
+        let shared_deps = pick(
+            None,
+            project
+                .as_ref()
+                .and_then(|p| p.shared_deps.map(|v| (v, project_source(p)))),
+            global.shared_deps,
+        );
+
+        let auto_devbox = pick(None, None, global.auto_devbox);
+
+        let worktree_path_template = pick(
+            None,
+            project.as_ref().and_then(|p| {
+                p.worktree_path_template
+                    .clone()
+                    .map(|v| (v, project_source(p)))
+            }),
+            global.worktree_path_template.clone(),
+        );
+
+        // Hooks add up rather than override
+        let hooks = (!overrides.no_hooks).then(|| {
+            let mut hooks = global.hooks.clone();
+            if let Some(project) = &project {
+                hooks.extend(&project.hooks);
+            }
+            hooks
+        });
+
+        let mut package_managers = project
+            .as_ref()
+            .map(|p| p.package_managers.clone())
+            .unwrap_or_default();
+        package_managers.extend_from_slice(&global.package_managers);
+
+        Self {
+            editor,
+            editor_window,
+            editor_wait,
+            auto_install_deps,
+            shared_deps,
+            auto_devbox,
+            worktree_path_template,
+            hooks,
+            package_managers,
+            project,
+        }
+    }
+
+    /// Global and project hooks to run for `event`
+    pub fn hooks_for(&self, event: HookEvent) -> Vec<Hook> {
+        self.hooks
+            .as_ref()
+            .map(|hooks| hooks.get(event).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Hooks for `event` in `worktree`'s `.dev.json`, which run after the
+    /// others once the user has approved them (see [`TrustedHooks`])
+    ///
+    /// [`TrustedHooks`]: super::TrustedHooks
+    pub fn committed_hooks_for(
+        &self,
+        event: HookEvent,
+        worktree: &Path,
+    ) -> Result<Option<CommittedHooks>> {
+        if self.hooks.is_none() {
+            return Ok(None);
+        }
+
+        let (config, hash) = match RepoConfig::load_hashed(worktree)? {
+            Some(loaded) => loaded,
+            None => return Ok(None),
+        };
+
+        let hooks = config.hooks.get(event).to_vec();
+        Ok((!hooks.is_empty()).then(|| CommittedHooks {
+            path: RepoConfig::config_path(worktree),
+            hash,
+            hooks,
+        }))
+    }
+
+    /// Patterns of the packages to install in `worktree`: those chosen for
+    /// it with `dev install --package`, else the project's `packages`
+    pub fn packages_for(&self, worktree: &Path) -> Vec<String> {
+        package_manager::selected_packages(worktree).unwrap_or_else(|| {
+            self.project
+                .as_ref()
+                .map(|p| p.packages.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    /// The packages to install in `worktree`
+    pub fn install_plan(&self, worktree: &Path) -> Result<Vec<Planned<'_>>> {
+        package_manager::plan(
+            worktree,
+            &self.package_managers,
+            &self.packages_for(worktree),
+        )
+    }
+
+    /// Compare the dependencies of the packages to install in `worktree`
+    /// with their lockfiles, or None if there are none
+    pub fn deps_status(&self, worktree: &Path) -> Result<Option<DepsStatus>> {
+        package_manager::deps_status(
+            worktree,
+            &self.package_managers,
+            &self.packages_for(worktree),
+        )
+    }
+
+    /// Environment for processes spawned in a worktree of this project
+    pub fn env_for(&self, git: &dyn GitBackend, worktree: &Path) -> WorktreeEnv {
+        WorktreeEnv::new(git, self.project.as_ref(), worktree)
+    }
+}
+
+/// Take the first value present, in precedence order
+fn pick<T>(flag: Option<T>, project: Option<(T, Source)>, global: T) -> Setting<T> {
+    if let Some(value) = flag {
+        return Setting {
+            value,
+            source: Source::Flag,
+        };
+    }
+
+    if let Some((value, source)) = project {
+        return Setting { value, source };
+    }
+
+    Setting {
+        value: global,
+        source: Source::Global,
+    }
+}
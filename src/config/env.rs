@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use super::ProjectConfig;
+use crate::git::GitBackend;
+
+/// Environment variables injected into every process spawned for a worktree
+///
+/// Combines the project's `env` map with per-worktree variables:
+/// `DEV_PROJECT`, `DEV_PROJECT_PATH`, `DEV_WORKTREE` and `DEV_BRANCH`.
+/// The `DEV_*` variables always win over same-named project entries.
+#[derive(Debug, Clone, Default)]
+pub struct WorktreeEnv {
+    vars: BTreeMap<String, String>,
+}
+
+impl WorktreeEnv {
+    /// Build the environment for a worktree of an (optional) project
+    pub fn new(git: &dyn GitBackend, project: Option<&ProjectConfig>, worktree: &Path) -> Self {
+        let mut vars: BTreeMap<String, String> = project
+            .map(|p| p.env.clone().into_iter().collect())
+            .unwrap_or_default();
+
+        if let Some(project) = project {
+            vars.insert("DEV_PROJECT".to_string(), project.name.clone());
+            vars.insert(
+                "DEV_PROJECT_PATH".to_string(),
+                project.path.display().to_string(),
+            );
+        }
+
+        vars.insert("DEV_WORKTREE".to_string(), worktree.display().to_string());
+
+        if let Ok(Some(branch)) = git.current_branch(worktree) {
+            vars.insert("DEV_BRANCH".to_string(), branch);
+        }
+
+        Self { vars }
+    }
+
+    /// All variables, sorted by name
+    pub fn vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    /// Add the variables to a command about to be spawned
+    pub fn apply<'a>(&self, cmd: &'a mut Command) -> &'a mut Command {
+        cmd.envs(&self.vars)
+    }
+}
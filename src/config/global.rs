@@ -1,7 +1,11 @@
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 use crate::error::{DevError, Result};
+use crate::hooks::Hooks;
+use crate::package_manager::CustomPackageManager;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GlobalConfig {
@@ -9,6 +13,14 @@ pub struct GlobalConfig {
     #[serde(default = "default_editor")]
     pub editor: String,
 
+    /// Whether the editor opens worktrees in a new window or reuses one
+    #[serde(default)]
+    pub editor_window: EditorWindow,
+
+    /// Whether opening a worktree waits until the editor is closed
+    #[serde(default)]
+    pub editor_wait: bool,
+
     /// Default development directory
     #[serde(default = "default_dev_path")]
     pub dev_path: PathBuf,
@@ -17,6 +29,10 @@ pub struct GlobalConfig {
     #[serde(default = "default_true")]
     pub auto_install_deps: bool,
 
+    /// Whether to reuse dependencies from a worktree with the same lockfile
+    #[serde(default)]
+    pub shared_deps: bool,
+
     /// Whether to launch devbox shell automatically
     #[serde(default = "default_true")]
     pub auto_devbox: bool,
@@ -24,15 +40,64 @@ pub struct GlobalConfig {
     /// Shell to use for completions
     #[serde(default)]
     pub shell: Shell,
+
+    /// Where new worktrees are placed (template or preset: "sibling", "worktrees")
+    #[serde(default = "default_worktree_path_template")]
+    pub worktree_path_template: String,
+
+    /// Hooks run for every project
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+
+    /// Package managers to detect besides the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub package_managers: Vec<CustomPackageManager>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum Shell {
     #[default]
     Zsh,
     Bash,
     Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+}
+
+/// Where the editor opens a worktree
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorWindow {
+    /// A window of its own (for terminal editors inside tmux, a tmux window)
+    #[default]
+    New,
+    /// The editor's current window (for terminal editors, this terminal)
+    Reuse,
+}
+
+impl fmt::Display for EditorWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditorWindow::New => write!(f, "new"),
+            EditorWindow::Reuse => write!(f, "reuse"),
+        }
+    }
+}
+
+impl std::str::FromStr for EditorWindow {
+    type Err = DevError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "new" => Ok(EditorWindow::New),
+            "reuse" => Ok(EditorWindow::Reuse),
+            _ => Err(DevError::ConfigError(format!(
+                "Unknown editor window: {}. Valid options: new, reuse",
+                value
+            ))),
+        }
+    }
 }
 
 fn default_editor() -> String {
@@ -45,6 +110,10 @@ fn default_dev_path() -> PathBuf {
         .join("Development")
 }
 
+fn default_worktree_path_template() -> String {
+    "sibling".to_string()
+}
+
 fn default_true() -> bool {
     true
 }
@@ -53,27 +122,46 @@ impl Default for GlobalConfig {
     fn default() -> Self {
         Self {
             editor: default_editor(),
+            editor_window: EditorWindow::default(),
+            editor_wait: false,
             dev_path: default_dev_path(),
             auto_install_deps: true,
+            shared_deps: false,
             auto_devbox: true,
             shell: Shell::default(),
+            worktree_path_template: default_worktree_path_template(),
+            hooks: Hooks::default(),
+            package_managers: Vec::new(),
         }
     }
 }
 
 impl GlobalConfig {
+    /// Keys accepted by `get` and `set`
+    pub const KEYS: [&'static str; 9] = [
+        "editor",
+        "editor_window",
+        "editor_wait",
+        "dev_path",
+        "auto_install_deps",
+        "shared_deps",
+        "auto_devbox",
+        "shell",
+        "worktree_path_template",
+    ];
+
     /// Get the path to the global config file
-    pub fn config_path() -> PathBuf {
-        super::config_dir().join("config.json")
+    pub fn config_path(config_dir: &Path) -> PathBuf {
+        config_dir.join("config.json")
     }
 
     /// Load the global config, creating defaults if it doesn't exist
-    pub fn load() -> Result<Self> {
-        let path = Self::config_path();
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = Self::config_path(config_dir);
 
         if !path.exists() {
             let config = Self::default();
-            config.save()?;
+            config.save(config_dir)?;
             return Ok(config);
         }
 
@@ -83,9 +171,9 @@ impl GlobalConfig {
     }
 
     /// Save the global config
-    pub fn save(&self) -> Result<()> {
-        super::ensure_config_dirs()?;
-        let path = Self::config_path();
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        super::ensure_config_dirs(config_dir)?;
+        let path = Self::config_path(config_dir);
         let contents = serde_json::to_string_pretty(self)?;
         std::fs::write(&path, contents)?;
         Ok(())
@@ -95,24 +183,39 @@ impl GlobalConfig {
     pub fn get(&self, key: &str) -> Option<String> {
         match key {
             "editor" => Some(self.editor.clone()),
+            "editor_window" => Some(self.editor_window.to_string()),
+            "editor_wait" => Some(self.editor_wait.to_string()),
             "dev_path" => Some(self.dev_path.display().to_string()),
             "auto_install_deps" => Some(self.auto_install_deps.to_string()),
+            "shared_deps" => Some(self.shared_deps.to_string()),
             "auto_devbox" => Some(self.auto_devbox.to_string()),
             "shell" => Some(format!("{:?}", self.shell).to_lowercase()),
+            "worktree_path_template" => Some(self.worktree_path_template.clone()),
             _ => None,
         }
     }
 
-    /// Set a config value by key
+    /// Set a config value by key (call `save` to persist it)
     pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
         match key {
             "editor" => self.editor = value.to_string(),
+            "editor_window" => self.editor_window = value.parse()?,
+            "editor_wait" => {
+                self.editor_wait = value
+                    .parse()
+                    .map_err(|_| DevError::ConfigError("Invalid boolean value".to_string()))?
+            }
             "dev_path" => self.dev_path = PathBuf::from(value),
             "auto_install_deps" => {
                 self.auto_install_deps = value
                     .parse()
                     .map_err(|_| DevError::ConfigError("Invalid boolean value".to_string()))?
             }
+            "shared_deps" => {
+                self.shared_deps = value
+                    .parse()
+                    .map_err(|_| DevError::ConfigError("Invalid boolean value".to_string()))?
+            }
             "auto_devbox" => {
                 self.auto_devbox = value
                     .parse()
@@ -123,19 +226,26 @@ impl GlobalConfig {
                     "zsh" => Shell::Zsh,
                     "bash" => Shell::Bash,
                     "fish" => Shell::Fish,
+                    "powershell" | "pwsh" => Shell::PowerShell,
                     _ => {
                         return Err(DevError::ConfigError(format!(
-                            "Unknown shell: {}. Valid options: zsh, bash, fish",
+                            "Unknown shell: {}. Valid options: zsh, bash, fish, powershell",
                             value
                         )))
                     }
                 }
             }
+            "worktree_path_template" => {
+                super::layout::validate_template(value).map_err(DevError::ConfigError)?;
+                self.worktree_path_template = value.to_string();
+            }
             _ => {
-                return Err(DevError::ConfigError(format!("Unknown config key: {}", key)));
+                return Err(DevError::ConfigError(format!(
+                    "Unknown config key: {}",
+                    key
+                )));
             }
         }
-        self.save()?;
         Ok(())
     }
 }
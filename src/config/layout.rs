@@ -0,0 +1,77 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Default layout: worktrees live next to the repository
+pub const SIBLING_TEMPLATE: &str = "{repo_root}/../{branch_slug}";
+
+/// Worktrees grouped per project under a shared `.worktrees/` directory
+pub const WORKTREES_DIR_TEMPLATE: &str = "{repo_root}/../.worktrees/{project}/{branch_slug}";
+
+/// Resolve a layout preset name ("sibling", "worktrees") to its template
+pub fn preset(name: &str) -> Option<&'static str> {
+    match name {
+        "sibling" => Some(SIBLING_TEMPLATE),
+        "worktrees" | ".worktrees" => Some(WORKTREES_DIR_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Check that a template (or preset) gives each branch its own directory
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let template = preset(template).unwrap_or(template);
+
+    if !template.contains("{branch}") && !template.contains("{branch_slug}") {
+        return Err(format!(
+            "Worktree path template must contain {{branch}} or {{branch_slug}}: {}",
+            template
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sanitize a branch name for use as a directory name
+pub fn branch_slug(branch: &str) -> String {
+    branch.replace('/', "-")
+}
+
+/// Expand a worktree path template (or preset) into an absolute path
+pub fn expand_template(template: &str, project: &str, branch: &str, repo_root: &Path) -> PathBuf {
+    let template = preset(template).unwrap_or(template);
+
+    let expanded = template
+        .replace("{project}", project)
+        .replace("{branch_slug}", &branch_slug(branch))
+        .replace("{branch}", branch)
+        .replace("{repo_root}", &repo_root.display().to_string());
+
+    let path = match expanded.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(&expanded),
+    };
+
+    // Relative templates are placed relative to the repository root
+    let path = if path.is_relative() {
+        repo_root.join(path)
+    } else {
+        path
+    };
+
+    normalize(&path)
+}
+
+/// Lexically resolve `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    normalized
+}
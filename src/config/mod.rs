@@ -1,22 +1,35 @@
+pub mod effective;
+pub mod env;
 pub mod global;
+pub mod layout;
 pub mod project;
+pub mod repo;
 
+pub use effective::{EffectiveConfig, Overrides};
+pub use env::WorktreeEnv;
 pub use global::GlobalConfig;
 pub use project::ProjectConfig;
+pub use repo::{CommittedHooks, RepoConfig, TrustedHooks};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Get the config directory path (~/.config/dev/)
+/// Environment variable overriding the config directory
+pub const CONFIG_DIR_VAR: &str = "DEV_CONFIG_DIR";
+
+/// Get the config directory path (`$DEV_CONFIG_DIR`, or ~/.config/dev/)
 pub fn config_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_VAR) {
+        return PathBuf::from(dir);
+    }
+
     dirs::config_dir()
         .unwrap_or_else(|| dirs::home_dir().unwrap().join(".config"))
         .join("dev")
 }
 
 /// Ensure the config directory structure exists
-pub fn ensure_config_dirs() -> std::io::Result<()> {
-    let config = config_dir();
-    std::fs::create_dir_all(&config)?;
-    std::fs::create_dir_all(config.join("projects"))?;
+pub fn ensure_config_dirs(config_dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(config_dir)?;
+    std::fs::create_dir_all(config_dir.join("projects"))?;
     Ok(())
 }
@@ -1,11 +1,20 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use super::global::EditorWindow;
 use crate::error::{DevError, Result};
-
+use crate::git::{get_main_worktree, GitBackend};
+use crate::hooks::Hooks;
+use crate::package_manager::CustomPackageManager;
+use crate::worktree_files::WorktreeFile;
+
+/// A registered project, stored as `projects/<name>.json` in the config dir
+///
+/// Semver-stable (the file format is not).
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[non_exhaustive]
 pub struct ProjectConfig {
     /// Project name (used as identifier)
     pub name: String,
@@ -21,10 +30,26 @@ pub struct ProjectConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub editor: Option<String>,
 
+    /// Override the editor window for this project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor_window: Option<EditorWindow>,
+
+    /// Override waiting for the editor for this project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor_wait: Option<bool>,
+
     /// Override auto-install for this project
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_install_deps: Option<bool>,
 
+    /// Override shared dependencies for this project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared_deps: Option<bool>,
+
+    /// Override worktree path template for this project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree_path_template: Option<String>,
+
     /// Whether this project uses devbox
     #[serde(default)]
     pub uses_devbox: bool,
@@ -33,6 +58,23 @@ pub struct ProjectConfig {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub env: HashMap<String, String>,
 
+    /// Hooks run after the global ones
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+
+    /// Untracked files brought from the main worktree into new ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worktree_files: Vec<WorktreeFile>,
+
+    /// Package managers to detect, checked before the global ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub package_managers: Vec<CustomPackageManager>,
+
+    /// Glob patterns of the packages to install in a monorepo's worktrees
+    /// (all of them when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+
     /// Project creation timestamp
     pub created_at: DateTime<Utc>,
 
@@ -49,27 +91,35 @@ impl ProjectConfig {
             path,
             remote_url: None,
             editor: None,
+            editor_window: None,
+            editor_wait: None,
             auto_install_deps: None,
+            shared_deps: None,
+            worktree_path_template: None,
             uses_devbox: false,
             env: HashMap::new(),
+            hooks: Hooks::default(),
+            worktree_files: Vec::new(),
+            package_managers: Vec::new(),
+            packages: Vec::new(),
             created_at: now,
             last_accessed: now,
         }
     }
 
     /// Get the path to a project's config file
-    pub fn config_path(name: &str) -> PathBuf {
-        super::config_dir().join("projects").join(format!("{}.json", name))
+    pub fn config_path(config_dir: &Path, name: &str) -> PathBuf {
+        config_dir.join("projects").join(format!("{}.json", name))
     }
 
     /// Check if a project exists
-    pub fn exists(name: &str) -> bool {
-        Self::config_path(name).exists()
+    pub fn exists(config_dir: &Path, name: &str) -> bool {
+        Self::config_path(config_dir, name).exists()
     }
 
     /// Load a project config by name
-    pub fn load(name: &str) -> Result<Self> {
-        let path = Self::config_path(name);
+    pub fn load(config_dir: &Path, name: &str) -> Result<Self> {
+        let path = Self::config_path(config_dir, name);
 
         if !path.exists() {
             return Err(DevError::ProjectNotFound(name.to_string()));
@@ -81,17 +131,17 @@ impl ProjectConfig {
     }
 
     /// Save the project config
-    pub fn save(&self) -> Result<()> {
-        super::ensure_config_dirs()?;
-        let path = Self::config_path(&self.name);
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        super::ensure_config_dirs(config_dir)?;
+        let path = Self::config_path(config_dir, &self.name);
         let contents = serde_json::to_string_pretty(self)?;
         std::fs::write(&path, contents)?;
         Ok(())
     }
 
     /// Delete a project config
-    pub fn delete(name: &str) -> Result<()> {
-        let path = Self::config_path(name);
+    pub fn delete(config_dir: &Path, name: &str) -> Result<()> {
+        let path = Self::config_path(config_dir, name);
         if path.exists() {
             std::fs::remove_file(&path)?;
         }
@@ -99,43 +149,56 @@ impl ProjectConfig {
     }
 
     /// List all registered projects
-    pub fn list_all() -> Result<Vec<ProjectConfig>> {
-        let projects_dir = super::config_dir().join("projects");
+    ///
+    /// Files that can't be read or parsed are left out; see
+    /// [`load_files`](Self::load_files).
+    pub fn list_all(config_dir: &Path) -> Result<Vec<ProjectConfig>> {
+        let mut projects: Vec<ProjectConfig> = Self::load_files(config_dir)?
+            .into_iter()
+            .filter_map(|(_, config)| config.ok())
+            .collect();
+
+        // Sort by last accessed (most recent first)
+        projects.sort_by_key(|p| std::cmp::Reverse(p.last_accessed));
+
+        Ok(projects)
+    }
+
+    /// Every file in the projects directory, sorted by path, with the
+    /// project it holds or why it couldn't be loaded
+    pub fn load_files(config_dir: &Path) -> Result<Vec<(PathBuf, Result<ProjectConfig>)>> {
+        let projects_dir = config_dir.join("projects");
 
         if !projects_dir.exists() {
             return Ok(Vec::new());
         }
 
-        let mut projects = Vec::new();
+        let mut files = Vec::new();
 
         for entry in std::fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.extension().map_or(false, |ext| ext == "json") {
-                if let Ok(contents) = std::fs::read_to_string(&path) {
-                    if let Ok(config) = serde_json::from_str::<ProjectConfig>(&contents) {
-                        projects.push(config);
-                    }
-                }
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let config = std::fs::read_to_string(&path)
+                    .map_err(DevError::from)
+                    .and_then(|contents| Ok(serde_json::from_str(&contents)?));
+                files.push((path, config));
             }
         }
 
-        // Sort by last accessed (most recent first)
-        projects.sort_by(|a, b| b.last_accessed.cmp(&a.last_accessed));
-
-        Ok(projects)
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
     }
 
     /// Update the last accessed timestamp and save
-    pub fn touch_accessed(&mut self) -> Result<()> {
+    pub fn touch_accessed(&mut self, config_dir: &Path) -> Result<()> {
         self.last_accessed = Utc::now();
-        self.save()
+        self.save(config_dir)
     }
 
     /// Find a project by path (checks if the given path is within any project)
-    pub fn find_by_path(path: &PathBuf) -> Result<Option<ProjectConfig>> {
-        let projects = Self::list_all()?;
+    pub fn find_by_path(config_dir: &Path, path: &Path) -> Result<Option<ProjectConfig>> {
+        let projects = Self::list_all(config_dir)?;
 
         for project in projects {
             if path.starts_with(&project.path) {
@@ -145,4 +208,22 @@ impl ProjectConfig {
 
         Ok(None)
     }
+
+    /// Find the project a repository belongs to
+    ///
+    /// Worktrees usually live outside the project directory, so the
+    /// repository's main worktree is matched first, then `path` itself.
+    pub fn find_for_repo(
+        config_dir: &Path,
+        git: &dyn GitBackend,
+        path: &Path,
+    ) -> Result<Option<ProjectConfig>> {
+        if let Ok(Some(main)) = get_main_worktree(git, path) {
+            if let Some(project) = Self::find_by_path(config_dir, &main.path)? {
+                return Ok(Some(project));
+            }
+        }
+
+        Self::find_by_path(config_dir, path)
+    }
 }
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{DevError, Result};
+use crate::hooks::{Hook, Hooks};
+
+/// Settings a repository carries for everyone working on it, committed as
+/// `.dev.json` at its root
+///
+/// Read from the worktree being acted on, so each branch sees its own copy.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct RepoConfig {
+    /// Hooks run after the global and project ones, once approved
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+impl RepoConfig {
+    /// Name of the file at the worktree root
+    pub const FILE_NAME: &'static str = ".dev.json";
+
+    /// Get the path to the repo config file in a worktree
+    pub fn config_path(worktree: &Path) -> PathBuf {
+        worktree.join(Self::FILE_NAME)
+    }
+
+    /// Load the repo config from a worktree, or defaults if it has none
+    pub fn load(worktree: &Path) -> Result<Self> {
+        Ok(Self::load_hashed(worktree)?
+            .map(|(config, _)| config)
+            .unwrap_or_default())
+    }
+
+    /// Load the repo config from a worktree together with the hash of the
+    /// file, or None if it has none
+    pub fn load_hashed(worktree: &Path) -> Result<Option<(Self, String)>> {
+        let path = Self::config_path(worktree);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = std::fs::read(&path)?;
+        let config = serde_json::from_slice(&contents)
+            .map_err(|e| DevError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        Ok(Some((config, format!("{:x}", Sha256::digest(&contents)))))
+    }
+}
+
+/// Hooks for one event from a worktree's `.dev.json`
+#[derive(Debug, Clone)]
+pub struct CommittedHooks {
+    /// The `.dev.json` they came from
+    pub path: PathBuf,
+    /// Hash of that file's contents
+    pub hash: String,
+    pub hooks: Vec<Hook>,
+}
+
+/// The `.dev.json` files whose hooks the user has approved
+///
+/// Anyone who can push to a repository can commit hooks, so they only run
+/// once the user has approved the exact file: each approval is recorded by
+/// the hash of its contents, and a changed file has to be approved again.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct TrustedHooks {
+    /// Where each approved file was, by hash
+    #[serde(default)]
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl TrustedHooks {
+    /// Name of the file in the config directory
+    pub const FILE_NAME: &'static str = "trusted_hooks.json";
+
+    /// Load the approvals, or none if nothing has been approved yet
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(Self::FILE_NAME);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| DevError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Save the approvals to the config directory
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        super::ensure_config_dirs(config_dir)?;
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(config_dir.join(Self::FILE_NAME), contents)?;
+        Ok(())
+    }
+
+    /// Whether the hooks in the file with this hash have been approved
+    pub fn is_trusted(&self, hash: &str) -> bool {
+        self.files.contains_key(hash)
+    }
+
+    /// Record approval of the hooks in a file
+    pub fn trust(&mut self, hash: &str, path: &Path) {
+        self.files.insert(hash.to_string(), path.to_path_buf());
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, EffectiveConfig, Overrides};
+use crate::error::Result;
+use crate::git::{backend, GitBackend};
+use crate::ui::output::Output;
+use crate::ui::prompts::Prompter;
+
+/// Everything a command needs from its surroundings
+///
+/// Commands take their directory, config location, git implementation,
+/// prompts and output from here rather than from the process, so tests
+/// can run them against temporary repositories with scripted answers.
+pub struct Context {
+    /// Directory the command acts on
+    pub cwd: PathBuf,
+    /// Where the global config and registered projects are stored
+    pub config_dir: PathBuf,
+    pub git: &'static dyn GitBackend,
+    pub prompter: Box<dyn Prompter>,
+    pub out: Output,
+}
+
+impl Context {
+    /// The real environment: current directory, the user's config
+    /// directory, the git backend chosen at build time and stdout/stderr
+    pub fn from_env(prompter: Box<dyn Prompter>) -> Result<Self> {
+        Ok(Self {
+            cwd: std::env::current_dir()?,
+            config_dir: config::config_dir(),
+            git: backend(),
+            prompter,
+            out: Output::stdio(),
+        })
+    }
+
+    /// Settings for the repository containing `path`
+    pub fn settings(&self, path: &Path, overrides: &Overrides) -> Result<EffectiveConfig> {
+        EffectiveConfig::load(&self.config_dir, self.git, path, overrides)
+    }
+}
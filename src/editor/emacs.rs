@@ -0,0 +1,43 @@
+use super::{Editor, LineSyntax};
+use crate::config::global::EditorWindow;
+
+/// Emacs through `emacsclient`, starting the daemon if it isn't running
+pub struct EmacsClient;
+
+impl Editor for EmacsClient {
+    fn name(&self) -> &str {
+        "Emacs"
+    }
+
+    fn command(&self) -> &str {
+        "emacsclient"
+    }
+
+    fn window_args(&self, window: EditorWindow) -> &[&str] {
+        match window {
+            EditorWindow::New => &["--create-frame", "--alternate-editor="],
+            EditorWindow::Reuse => &["--alternate-editor="],
+        }
+    }
+
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        if wait {
+            &[]
+        } else {
+            &["--no-wait"]
+        }
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::Plus
+    }
+
+    fn version_args(&self) -> Option<&[&str]> {
+        Some(&["--version"])
+    }
+
+    /// A directory opens in dired, which a file would just cover up
+    fn opens_folders(&self) -> bool {
+        false
+    }
+}
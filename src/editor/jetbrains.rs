@@ -0,0 +1,83 @@
+use super::{Editor, LineSyntax};
+
+/// A JetBrains IDE, started through the launcher Toolbox or the IDE's own
+/// installer puts on PATH
+///
+/// The IDEs decide for themselves whether a project gets a window of its
+/// own (the "Open project in" setting), so no window flags are passed.
+pub struct JetBrainsEditor {
+    name: &'static str,
+    /// Launcher names, Toolbox's first, then the installer's and snap's
+    launchers: &'static [&'static str],
+    mac_apps: &'static [&'static str],
+}
+
+pub const IDEA: JetBrainsEditor = JetBrainsEditor {
+    name: "IntelliJ IDEA",
+    launchers: &[
+        "idea",
+        "idea.sh",
+        "intellij-idea-ultimate",
+        "intellij-idea-community",
+    ],
+    mac_apps: &["IntelliJ IDEA", "IntelliJ IDEA CE"],
+};
+
+pub const WEBSTORM: JetBrainsEditor = JetBrainsEditor {
+    name: "WebStorm",
+    launchers: &["webstorm", "webstorm.sh"],
+    mac_apps: &["WebStorm"],
+};
+
+pub const RUSTROVER: JetBrainsEditor = JetBrainsEditor {
+    name: "RustRover",
+    launchers: &["rustrover", "rustrover.sh"],
+    mac_apps: &["RustRover"],
+};
+
+pub const PYCHARM: JetBrainsEditor = JetBrainsEditor {
+    name: "PyCharm",
+    launchers: &[
+        "pycharm",
+        "pycharm.sh",
+        "pycharm-professional",
+        "pycharm-community",
+    ],
+    mac_apps: &["PyCharm", "PyCharm CE"],
+};
+
+pub const GOLAND: JetBrainsEditor = JetBrainsEditor {
+    name: "GoLand",
+    launchers: &["goland", "goland.sh"],
+    mac_apps: &["GoLand"],
+};
+
+impl Editor for JetBrainsEditor {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn command(&self) -> &str {
+        self.launchers[0]
+    }
+
+    fn alternative_commands(&self) -> &[&str] {
+        &self.launchers[1..]
+    }
+
+    fn mac_apps(&self) -> &[&str] {
+        self.mac_apps
+    }
+
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        if wait {
+            &["--wait"]
+        } else {
+            &[]
+        }
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::LineFlag
+    }
+}
@@ -1,11 +1,94 @@
+mod emacs;
+mod jetbrains;
+mod sublime;
+mod template;
+mod terminal;
 mod vscode;
 mod zed;
 
-use std::path::Path;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::config::GlobalConfig;
+use crate::config::global::EditorWindow;
+use crate::config::WorktreeEnv;
 use crate::error::{DevError, Result};
+use crate::executable;
+
+/// How an installed editor is started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Launcher {
+    /// An executable, found on PATH unless configured as a path
+    Command(PathBuf),
+    /// A macOS application bundle, started through `open`
+    MacApp(PathBuf),
+}
+
+/// A worktree to open, and optionally a file in it
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    pub worktree: &'a Path,
+    /// Absolute path of a file to open as well
+    pub file: Option<&'a Path>,
+    /// Line of `file` to go to
+    pub line: Option<u32>,
+}
+
+impl<'a> Target<'a> {
+    /// Just the worktree
+    pub fn worktree(worktree: &'a Path) -> Self {
+        Self {
+            worktree,
+            file: None,
+            line: None,
+        }
+    }
+}
+
+/// How a target is opened
+#[derive(Debug, Clone, Copy)]
+pub struct OpenOptions {
+    pub window: EditorWindow,
+    /// Block until the editor is closed instead of leaving it in the
+    /// background
+    pub wait: bool,
+}
+
+/// How an editor is told which line of a file to go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineSyntax {
+    /// `file:line`
+    Suffix,
+    /// `+line file`
+    Plus,
+    /// `--goto file:line`
+    Goto,
+    /// `--line line file`
+    LineFlag,
+    /// It can't be; the file opens at the top
+    Unsupported,
+}
+
+impl LineSyntax {
+    /// Arguments opening `file`, at `line` if there is one
+    pub fn args(self, file: &Path, line: Option<u32>) -> Vec<OsString> {
+        let line = match line {
+            Some(line) if self != LineSyntax::Unsupported => line,
+            _ => return vec![file.into()],
+        };
+
+        let mut with_suffix = OsString::from(file);
+        with_suffix.push(format!(":{}", line));
+
+        match self {
+            LineSyntax::Suffix => vec![with_suffix],
+            LineSyntax::Plus => vec![format!("+{}", line).into(), file.into()],
+            LineSyntax::Goto => vec!["--goto".into(), with_suffix],
+            LineSyntax::LineFlag => vec!["--line".into(), line.to_string().into(), file.into()],
+            LineSyntax::Unsupported => unreachable!(),
+        }
+    }
+}
 
 /// Trait for editor implementations
 pub trait Editor: Send + Sync {
@@ -15,43 +98,167 @@ pub trait Editor: Send + Sync {
     /// Command to invoke the editor
     fn command(&self) -> &str;
 
-    /// Check if editor is installed
-    fn is_installed(&self) -> bool {
-        Command::new("which")
-            .arg(self.command())
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+    /// Other commands the editor may be installed as, tried after `command`
+    fn alternative_commands(&self) -> &[&str] {
+        &[]
+    }
+
+    /// macOS applications to start when none of the commands is on PATH
+    fn mac_apps(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Arguments before the path that open it in a new window or reuse the
+    /// current one
+    fn window_args(&self, window: EditorWindow) -> &[&str] {
+        let _ = window;
+        &[]
+    }
+
+    /// Arguments that make the command block until the editor is closed,
+    /// or return straight away
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        let _ = wait;
+        &[]
+    }
+
+    /// How to go to a line of a file
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::Unsupported
+    }
+
+    /// Arguments printing the editor's version, for editors known to print
+    /// it and exit rather than open a window
+    fn version_args(&self) -> Option<&[&str]> {
+        None
+    }
+
+    /// Whether the worktree is passed along with a file, to open it as the
+    /// editor's folder or project
+    fn opens_folders(&self) -> bool {
+        !self.is_terminal()
+    }
+
+    /// Whether the editor runs inside the terminal rather than in a window
+    /// of its own
+    fn is_terminal(&self) -> bool {
+        false
     }
 
-    /// Open a path in the editor
-    fn open(&self, path: &Path) -> Result<()> {
-        if !self.is_installed() {
-            return Err(DevError::EditorNotFound(self.name().to_string()));
+    /// How to start the editor, or None if it isn't installed
+    fn launcher(&self) -> Option<Launcher> {
+        std::iter::once(self.command())
+            .chain(self.alternative_commands().iter().copied())
+            .find_map(executable::find)
+            .map(Launcher::Command)
+            .or_else(|| {
+                self.mac_apps()
+                    .iter()
+                    .find_map(|app| find_mac_app(app))
+                    .map(Launcher::MacApp)
+            })
+    }
+
+    /// Arguments opening `target`
+    fn args(&self, target: &Target, options: OpenOptions) -> Vec<OsString> {
+        let mut args: Vec<OsString> = self
+            .window_args(options.window)
+            .iter()
+            .chain(self.wait_args(options.wait))
+            .map(OsString::from)
+            .collect();
+
+        if target.file.is_none() || self.opens_folders() {
+            args.push(target.worktree.into());
+        }
+        if let Some(file) = target.file {
+            args.extend(self.line_syntax().args(file, target.line));
         }
 
-        Command::new(self.command())
-            .arg(path)
-            .spawn()
-            .map_err(|e| DevError::Other(format!("Failed to open editor: {}", e)))?;
+        args
+    }
+
+    /// Open a target in the editor
+    ///
+    /// Graphical editors are left running in the background unless told to
+    /// wait; terminal ones take over the terminal until they exit, or get a
+    /// tmux window of their own.
+    fn open(&self, target: &Target, env: &WorktreeEnv, options: OpenOptions) -> Result<()> {
+        let launcher = self
+            .launcher()
+            .ok_or_else(|| DevError::EditorNotFound(self.name().to_string()))?;
 
-        Ok(())
+        let mut cmd = match launcher {
+            Launcher::Command(command) => Command::new(command),
+            Launcher::MacApp(app) => {
+                let mut cmd = Command::new("open");
+                cmd.arg("-na").arg(app);
+                if options.wait {
+                    cmd.arg("-W");
+                }
+                cmd.arg("--args");
+                cmd
+            }
+        };
+        cmd.args(self.args(target, options));
+        env.apply(&mut cmd);
+
+        launch(cmd, target.worktree, env, options, self.is_terminal())
     }
 }
 
+/// Editor names with built-in support
+pub const BUILTIN_EDITORS: [&str; 14] = [
+    "zed",
+    "code",
+    "cursor",
+    "windsurf",
+    "idea",
+    "webstorm",
+    "rustrover",
+    "pycharm",
+    "goland",
+    "subl",
+    "nvim",
+    "vim",
+    "hx",
+    "emacsclient",
+];
+
 /// Get an editor by name
 pub fn get_editor(name: &str) -> Box<dyn Editor> {
     match name.to_lowercase().as_str() {
         "zed" => Box::new(zed::ZedEditor),
-        "code" | "vscode" => Box::new(vscode::VSCodeEditor),
+        "code" | "vscode" => Box::new(vscode::VSCODE),
+        "cursor" => Box::new(vscode::CURSOR),
+        "windsurf" => Box::new(vscode::WINDSURF),
+        "idea" | "intellij" => Box::new(jetbrains::IDEA),
+        "webstorm" => Box::new(jetbrains::WEBSTORM),
+        "rustrover" => Box::new(jetbrains::RUSTROVER),
+        "pycharm" => Box::new(jetbrains::PYCHARM),
+        "goland" => Box::new(jetbrains::GOLAND),
+        "subl" | "sublime" => Box::new(sublime::SublimeEditor),
+        "nvim" | "neovim" => Box::new(terminal::NEOVIM),
+        "vim" => Box::new(terminal::VIM),
+        "hx" | "helix" => Box::new(terminal::HELIX),
+        "emacsclient" => Box::new(emacs::EmacsClient),
         _ => Box::new(GenericEditor(name.to_string())),
     }
 }
 
-/// Open a path with the configured editor
-pub fn open(path: &Path, config: &GlobalConfig) -> Result<()> {
-    let editor = get_editor(&config.editor);
-    editor.open(path)
+/// The editor for an `editor` setting: a name, or a command line with
+/// arguments and placeholders (see [`template::TemplateEditor`])
+pub fn from_config(editor: &str) -> Result<Box<dyn Editor>> {
+    if template::is_template(editor) {
+        Ok(Box::new(template::TemplateEditor::parse(editor)?))
+    } else {
+        Ok(get_editor(editor))
+    }
+}
+
+/// Open a target with the editor from an `editor` setting
+pub fn open(target: &Target, editor: &str, options: OpenOptions, env: &WorktreeEnv) -> Result<()> {
+    from_config(editor)?.open(target, env, options)
 }
 
 /// Generic editor that just runs the command
@@ -66,3 +273,73 @@ impl Editor for GenericEditor {
         &self.0
     }
 }
+
+/// Where a macOS application is installed, if it is
+fn find_mac_app(name: &str) -> Option<PathBuf> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+
+    let bundle = format!("{}.app", name);
+    let mut dirs = vec![PathBuf::from("/Applications")];
+    dirs.extend(dirs::home_dir().map(|home| home.join("Applications")));
+
+    dirs.into_iter()
+        .map(|dir| dir.join(&bundle))
+        .find(|app| app.exists())
+}
+
+/// Run an editor's command: terminal editors through [`run_in_terminal`],
+/// others in the foreground when waiting and in the background otherwise
+fn launch(
+    mut cmd: Command,
+    worktree: &Path,
+    env: &WorktreeEnv,
+    options: OpenOptions,
+    terminal: bool,
+) -> Result<()> {
+    let failed = |e: std::io::Error| DevError::Other(format!("Failed to open editor: {}", e));
+
+    if terminal {
+        return run_in_terminal(cmd, worktree, env, options);
+    }
+
+    if options.wait {
+        cmd.current_dir(worktree).status().map_err(failed)?;
+    } else {
+        cmd.spawn().map_err(failed)?;
+    }
+
+    Ok(())
+}
+
+/// Run a terminal editor: in a new tmux window when a new window is wanted,
+/// dev runs inside tmux and isn't to wait, otherwise in the foreground until
+/// it exits
+fn run_in_terminal(
+    mut cmd: Command,
+    path: &Path,
+    env: &WorktreeEnv,
+    options: OpenOptions,
+) -> Result<()> {
+    let failed = |e: std::io::Error| DevError::Other(format!("Failed to open editor: {}", e));
+
+    if options.window == EditorWindow::New && !options.wait && std::env::var_os("TMUX").is_some() {
+        let mut tmux = Command::new("tmux");
+        tmux.args(["new-window", "-c"]).arg(path);
+        for (key, value) in env.vars() {
+            tmux.arg("-e").arg(format!("{}={}", key, value));
+        }
+        tmux.arg(cmd.get_program()).args(cmd.get_args());
+
+        if !tmux.status().map_err(failed)?.success() {
+            return Err(DevError::Other(
+                "Failed to open editor: tmux couldn't create a window".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+
+    cmd.current_dir(path).status().map_err(failed)?;
+    Ok(())
+}
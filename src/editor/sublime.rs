@@ -0,0 +1,46 @@
+use super::{Editor, LineSyntax};
+use crate::config::global::EditorWindow;
+
+pub struct SublimeEditor;
+
+impl Editor for SublimeEditor {
+    fn name(&self) -> &str {
+        "Sublime Text"
+    }
+
+    fn command(&self) -> &str {
+        "subl"
+    }
+
+    fn alternative_commands(&self) -> &[&str] {
+        &["sublime_text"]
+    }
+
+    fn mac_apps(&self) -> &[&str] {
+        &["Sublime Text"]
+    }
+
+    fn window_args(&self, window: EditorWindow) -> &[&str] {
+        match window {
+            EditorWindow::New => &["--new-window"],
+            // Adds the worktree to the current window's folders
+            EditorWindow::Reuse => &["--add"],
+        }
+    }
+
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        if wait {
+            &["--wait"]
+        } else {
+            &[]
+        }
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::Suffix
+    }
+
+    fn version_args(&self) -> Option<&[&str]> {
+        Some(&["--version"])
+    }
+}
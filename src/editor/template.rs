@@ -0,0 +1,141 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::{get_editor, Editor, OpenOptions, Target};
+use crate::error::{DevError, Result};
+use crate::executable;
+
+/// Placeholders an editor command template can use
+const PLACEHOLDERS: [&str; 4] = ["{path}", "{file}", "{line}", "{workspace_file}"];
+
+/// Whether an `editor` setting is a command line rather than an editor name
+///
+/// A setting that names an executable as a whole, such as an unquoted path
+/// with spaces in it, is taken as it is.
+pub fn is_template(editor: &str) -> bool {
+    if PLACEHOLDERS.iter().any(|p| editor.contains(p)) {
+        return true;
+    }
+
+    let editor = editor.trim();
+    editor.contains(char::is_whitespace) && executable::find(editor).is_none()
+}
+
+/// An editor given as a command line, such as `code --new-window {path}`
+///
+/// The command line is split like a shell would, without running one.
+/// Placeholders in the arguments are replaced with:
+///
+/// - `{path}`: the worktree
+/// - `{file}`: the file being opened
+/// - `{line}`: the line to go to in it, 1 if none was given
+/// - `{workspace_file}`: the worktree's `.code-workspace` file, or the
+///   worktree if it has none
+///
+/// Arguments with `{file}` or `{line}` are left out when no file is being
+/// opened, together with a flag just before them, as in `--goto {file}`.
+/// When none of `{path}`, `{file}` and `{workspace_file}` is left, the
+/// worktree and then the file are appended. Window settings don't apply;
+/// the command line says how to open things.
+#[derive(Debug)]
+pub struct TemplateEditor {
+    program: String,
+    args: Vec<String>,
+}
+
+impl TemplateEditor {
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            DevError::ConfigError(format!("Invalid editor '{}': {}", template, reason))
+        };
+
+        let mut words = shell_words::split(template).map_err(|e| invalid(e.to_string()))?;
+        if words.is_empty() {
+            return Err(invalid("no command".to_string()));
+        }
+
+        let program = words.remove(0);
+        Ok(Self {
+            program,
+            args: words,
+        })
+    }
+}
+
+impl Editor for TemplateEditor {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn command(&self) -> &str {
+        &self.program
+    }
+
+    /// Terminal editors still need the terminal when given arguments
+    fn is_terminal(&self) -> bool {
+        get_editor(&self.program).is_terminal()
+    }
+
+    fn args(&self, target: &Target, _options: OpenOptions) -> Vec<OsString> {
+        let worktree = target.worktree.to_string_lossy();
+        let workspace_file = workspace_file(target.worktree)
+            .map(|file| file.to_string_lossy().into_owned())
+            .unwrap_or_else(|| worktree.to_string());
+        let line = target.line.unwrap_or(1).to_string();
+
+        let mut args: Vec<OsString> = Vec::new();
+        let mut mentions_path = false;
+        let mut kept_previous = false;
+
+        for (i, arg) in self.args.iter().enumerate() {
+            let arg = match target.file {
+                Some(file) => arg
+                    .replace("{file}", &file.to_string_lossy())
+                    .replace("{line}", &line),
+                None if arg.contains("{file}") || arg.contains("{line}") => {
+                    // A flag taking the file has nothing left to take
+                    if kept_previous && is_flag(&self.args[i - 1]) {
+                        args.pop();
+                    }
+                    kept_previous = false;
+                    continue;
+                }
+                None => arg.clone(),
+            };
+
+            mentions_path |= ["{path}", "{file}", "{workspace_file}"]
+                .iter()
+                .any(|p| self.args[i].contains(p));
+            kept_previous = true;
+            args.push(
+                arg.replace("{path}", &worktree)
+                    .replace("{workspace_file}", &workspace_file)
+                    .into(),
+            );
+        }
+
+        if !mentions_path {
+            args.push(target.worktree.into());
+            args.extend(target.file.map(OsString::from));
+        }
+
+        args
+    }
+}
+
+/// Whether a template argument is a flag waiting for a value, like `--goto`
+fn is_flag(arg: &str) -> bool {
+    arg.starts_with('-') && !arg.contains('=') && !PLACEHOLDERS.iter().any(|p| arg.contains(p))
+}
+
+/// The VS Code workspace file at the root of a worktree, if there is one
+fn workspace_file(worktree: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(worktree)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "code-workspace"))
+        .collect();
+    files.sort();
+    files.into_iter().next()
+}
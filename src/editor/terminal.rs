@@ -0,0 +1,60 @@
+use super::{Editor, LineSyntax};
+
+/// An editor that runs inside the terminal
+///
+/// It can't be left running in the background like a graphical editor, so
+/// it takes over the terminal, or gets a tmux window for a new window.
+pub struct TerminalEditor {
+    name: &'static str,
+    command: &'static str,
+    alternatives: &'static [&'static str],
+    line_syntax: LineSyntax,
+}
+
+pub const NEOVIM: TerminalEditor = TerminalEditor {
+    name: "Neovim",
+    command: "nvim",
+    alternatives: &[],
+    line_syntax: LineSyntax::Plus,
+};
+
+pub const VIM: TerminalEditor = TerminalEditor {
+    name: "Vim",
+    command: "vim",
+    alternatives: &[],
+    line_syntax: LineSyntax::Plus,
+};
+
+pub const HELIX: TerminalEditor = TerminalEditor {
+    name: "Helix",
+    command: "hx",
+    // What Arch and Fedora call it
+    alternatives: &["helix"],
+    line_syntax: LineSyntax::Suffix,
+};
+
+impl Editor for TerminalEditor {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn command(&self) -> &str {
+        self.command
+    }
+
+    fn alternative_commands(&self) -> &[&str] {
+        self.alternatives
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        self.line_syntax
+    }
+
+    fn version_args(&self) -> Option<&[&str]> {
+        Some(&["--version"])
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+}
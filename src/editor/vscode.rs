@@ -1,13 +1,64 @@
-use super::Editor;
+use super::{Editor, LineSyntax};
+use crate::config::global::EditorWindow;
 
-pub struct VSCodeEditor;
+/// VS Code, or one of the editors built on it, which share its command line
+pub struct VSCodeEditor {
+    name: &'static str,
+    command: &'static str,
+    mac_app: &'static str,
+}
+
+pub const VSCODE: VSCodeEditor = VSCodeEditor {
+    name: "VSCode",
+    command: "code",
+    mac_app: "Visual Studio Code",
+};
+
+pub const CURSOR: VSCodeEditor = VSCodeEditor {
+    name: "Cursor",
+    command: "cursor",
+    mac_app: "Cursor",
+};
+
+pub const WINDSURF: VSCodeEditor = VSCodeEditor {
+    name: "Windsurf",
+    command: "windsurf",
+    mac_app: "Windsurf",
+};
 
 impl Editor for VSCodeEditor {
     fn name(&self) -> &str {
-        "VSCode"
+        self.name
     }
 
     fn command(&self) -> &str {
-        "code"
+        self.command
+    }
+
+    fn mac_apps(&self) -> &[&str] {
+        std::slice::from_ref(&self.mac_app)
+    }
+
+    fn window_args(&self, window: EditorWindow) -> &[&str] {
+        match window {
+            EditorWindow::New => &["--new-window"],
+            EditorWindow::Reuse => &["--reuse-window"],
+        }
+    }
+
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        if wait {
+            &["--wait"]
+        } else {
+            &[]
+        }
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::Goto
+    }
+
+    fn version_args(&self) -> Option<&[&str]> {
+        Some(&["--version"])
     }
 }
@@ -1,4 +1,5 @@
-use super::Editor;
+use super::{Editor, LineSyntax};
+use crate::config::global::EditorWindow;
 
 pub struct ZedEditor;
 
@@ -10,4 +11,36 @@ impl Editor for ZedEditor {
     fn command(&self) -> &str {
         "zed"
     }
+
+    /// Linux distributions package the CLI under other names
+    fn alternative_commands(&self) -> &[&str] {
+        &["zeditor", "zedit"]
+    }
+
+    fn mac_apps(&self) -> &[&str] {
+        &["Zed"]
+    }
+
+    fn window_args(&self, window: EditorWindow) -> &[&str] {
+        match window {
+            EditorWindow::New => &["--new"],
+            EditorWindow::Reuse => &["--reuse"],
+        }
+    }
+
+    fn wait_args(&self, wait: bool) -> &[&str] {
+        if wait {
+            &["--wait"]
+        } else {
+            &[]
+        }
+    }
+
+    fn line_syntax(&self) -> LineSyntax {
+        LineSyntax::Suffix
+    }
+
+    fn version_args(&self) -> Option<&[&str]> {
+        Some(&["--version"])
+    }
 }
@@ -1,6 +1,10 @@
 use thiserror::Error;
 
+/// Everything that can go wrong in dev-cli
+///
+/// Semver-stable, as are the strings returned by [`DevError::code`].
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum DevError {
     #[error("Not in a git repository")]
     NotGitRepository,
@@ -20,9 +24,21 @@ pub enum DevError {
     #[error("Worktree directory already exists: {0}")]
     WorktreeDirectoryExists(String),
 
+    #[error("Worktree path {0} is already used by branch '{1}'")]
+    WorktreePathCollision(String, String),
+
     #[error("Worktree path does not exist: {0}")]
     WorktreePathMissing(String),
 
+    #[error("Worktree for '{0}' has uncommitted changes (use --force to remove anyway)")]
+    UncommittedChanges(String),
+
+    #[error("Branch '{0}' has {1} unpushed commit(s) (use --force to remove anyway)")]
+    UnpushedCommits(String, usize),
+
+    #[error("Can't tell whether '{0}' has unsaved work (use --force to remove anyway)")]
+    StatusUnknown(String),
+
     #[error("Project '{0}' not found")]
     ProjectNotFound(String),
 
@@ -44,11 +60,47 @@ pub enum DevError {
     #[error("Devbox error: {0}")]
     DevboxError(String),
 
+    #[error("{0} hook '{1}' failed: {2}")]
+    HookFailed(String, String, String),
+
     #[error("Operation cancelled")]
     UserCancelled,
 
+    #[error("Input required: pass {1} to answer \"{0}\"")]
+    InputRequired(String, String),
+
     #[error("{0}")]
     Other(String),
 }
 
+impl DevError {
+    /// Stable machine-readable code for this error, used in JSON output
+    pub fn code(&self) -> &'static str {
+        match self {
+            DevError::NotGitRepository => "not_git_repository",
+            DevError::GitError(_) => "git_error",
+            DevError::BranchExistsLocally(_) => "branch_exists_locally",
+            DevError::BranchNotFound(_) => "branch_not_found",
+            DevError::WorktreeNotFound(_) => "worktree_not_found",
+            DevError::WorktreeDirectoryExists(_) => "worktree_directory_exists",
+            DevError::WorktreePathCollision(_, _) => "worktree_path_collision",
+            DevError::WorktreePathMissing(_) => "worktree_path_missing",
+            DevError::UncommittedChanges(_) => "uncommitted_changes",
+            DevError::UnpushedCommits(_, _) => "unpushed_commits",
+            DevError::StatusUnknown(_) => "status_unknown",
+            DevError::ProjectNotFound(_) => "project_not_found",
+            DevError::ProjectExists(_) => "project_exists",
+            DevError::ConfigError(_) => "config_error",
+            DevError::IoError(_) => "io_error",
+            DevError::JsonError(_) => "json_error",
+            DevError::EditorNotFound(_) => "editor_not_found",
+            DevError::DevboxError(_) => "devbox_error",
+            DevError::HookFailed(_, _, _) => "hook_failed",
+            DevError::UserCancelled => "cancelled",
+            DevError::InputRequired(_, _) => "input_required",
+            DevError::Other(_) => "other",
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, DevError>;
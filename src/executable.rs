@@ -0,0 +1,141 @@
+//! Finding executables the way a shell would, without running `which`
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Extensions tried on Windows when `PATHEXT` isn't set
+#[cfg(windows)]
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Where the executable `name` is, if it's installed
+///
+/// A bare name is looked for in each `PATH` directory in turn; one with a
+/// path separator, as an absolute path in config would have, is checked
+/// where it points, with a leading `~/` meaning the home directory. On
+/// Windows the `PATHEXT` extensions are tried when the name has none of
+/// them; elsewhere only files with an execute bit count.
+///
+/// Results are remembered for the rest of the process.
+pub fn find(name: &str) -> Option<PathBuf> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Option<PathBuf>>>> = OnceLock::new();
+
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    cache
+        .entry(name.to_string())
+        .or_insert_with(|| find_in(name, std::env::var_os("PATH").as_deref()))
+        .clone()
+}
+
+/// [`find`] against the given `PATH`, without the cache
+pub fn find_in(name: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    if name.is_empty() {
+        return None;
+    }
+
+    if name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) {
+        let path = match (name.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(name),
+        };
+        return candidates(&path).into_iter().find(|c| is_executable(c));
+    }
+
+    // Empty entries would mean the current directory; a shell only looks
+    // there when told to, so neither does this
+    std::env::split_paths(path?)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .flat_map(|dir| candidates(&dir.join(name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Files that running `path` could mean
+#[cfg(windows)]
+fn candidates(path: &Path) -> Vec<PathBuf> {
+    use std::ffi::OsString;
+
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+    let extensions: Vec<&str> = pathext.split(';').filter(|e| !e.is_empty()).collect();
+
+    let has_extension = path.extension().is_some_and(|ext| {
+        extensions.iter().any(|e| {
+            e.trim_start_matches('.')
+                .eq_ignore_ascii_case(&ext.to_string_lossy())
+        })
+    });
+    if has_extension {
+        return vec![path.to_path_buf()];
+    }
+
+    extensions
+        .iter()
+        .map(|ext| {
+            let mut candidate = OsString::from(path);
+            candidate.push(ext);
+            PathBuf::from(candidate)
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn candidates(path: &Path) -> Vec<PathBuf> {
+    vec![path.to_path_buf()]
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn file(path: &Path, mode: u32) {
+        std::fs::write(path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn finds_first_executable_on_path() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (first, second) = (tmp.path().join("a"), tmp.path().join("b"));
+        std::fs::create_dir(&first).unwrap();
+        std::fs::create_dir(&second).unwrap();
+        // Not executable, so skipped like a shell would
+        file(&first.join("tool"), 0o644);
+        file(&second.join("tool"), 0o755);
+
+        let path = std::env::join_paths([&first, &second]).unwrap();
+        assert_eq!(find_in("tool", Some(&path)), Some(second.join("tool")));
+        assert_eq!(find_in("missing", Some(&path)), None);
+        assert_eq!(find_in("tool", None), None);
+    }
+
+    #[test]
+    fn paths_are_checked_as_given() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let tool = tmp.path().join("tool");
+        file(&tool, 0o755);
+
+        let name = tool.to_str().unwrap();
+        assert_eq!(find_in(name, None), Some(tool.clone()));
+
+        let dir = tmp.path().join("dir");
+        std::fs::create_dir(&dir).unwrap();
+        assert_eq!(find_in(dir.to_str().unwrap(), None), None);
+    }
+}
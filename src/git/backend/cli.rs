@@ -0,0 +1,589 @@
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::error::{DevError, Result};
+use crate::git::status::{Divergence, Operation, Upstream, WorktreeStatus};
+use crate::git::worktree::WorktreeInfo;
+
+use super::{clean_path, GitBackend};
+use crate::git::git_dir;
+
+/// Backend that spawns the `git` executable and parses its output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn resolve_ref(&self, repo: &Path, refname: &str) -> Result<Option<String>> {
+        let output = git(
+            repo,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{}^{{commit}}", refname),
+            ],
+        )?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    }
+
+    fn current_branch(&self, worktree: &Path) -> Result<Option<String>> {
+        let output = git(worktree, &["symbolic-ref", "--short", "HEAD"])?;
+
+        if output.status.success() {
+            let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            Ok(Some(branch))
+        } else {
+            // Might be in detached HEAD state
+            Ok(None)
+        }
+    }
+
+    fn default_branch(&self, repo: &Path) -> String {
+        if let Ok(output) = git(
+            repo,
+            &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+        ) {
+            if output.status.success() {
+                let head = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if let Some(branch) = head.strip_prefix("origin/") {
+                    return branch.to_string();
+                }
+            }
+        }
+
+        let has_local = |name: &str| {
+            git(
+                repo,
+                &[
+                    "show-ref",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{}", name),
+                ],
+            )
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+        };
+
+        if !has_local("main") && has_local("master") {
+            return "master".to_string();
+        }
+
+        "main".to_string()
+    }
+
+    fn list_branches(&self, repo: &Path, include_remote: bool) -> Result<Vec<String>> {
+        let mut args = vec!["for-each-ref", "--format=%(refname)", "refs/heads"];
+        if include_remote {
+            args.push("refs/remotes");
+        }
+
+        let output = git(repo, &args)?;
+
+        if !output.status.success() {
+            return Err(DevError::NotGitRepository);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let refnames: Vec<&str> = stdout.lines().map(str::trim).collect();
+
+        Ok(super::branch_names(refnames))
+    }
+
+    fn delete_branch(&self, repo: &Path, name: &str, force: bool) -> Result<()> {
+        let flag = if force { "-D" } else { "-d" };
+
+        let output = git(repo, &["branch", flag, name])?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::GitError(format!(
+                "Failed to delete branch: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>> {
+        let output = git(repo, &["worktree", "list", "--porcelain"])?;
+
+        if !output.status.success() {
+            return Err(DevError::NotGitRepository);
+        }
+
+        let current_dir = repo.canonicalize().ok();
+
+        Ok(parse_worktree_list(&output.stdout, current_dir.as_deref()))
+    }
+
+    fn add_worktree(
+        &self,
+        repo: &Path,
+        branch: &str,
+        path: &Path,
+        create_branch: bool,
+    ) -> Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.args(["worktree", "add"]).current_dir(repo);
+
+        if create_branch {
+            cmd.args(["-b", branch]).arg(path);
+        } else {
+            cmd.arg(path).arg(branch);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::GitError(format!(
+                "Failed to create worktree: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn remove_worktree(&self, repo: &Path, path: &Path, force: bool) -> Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.args(["worktree", "remove"]).current_dir(repo);
+
+        if force {
+            cmd.arg("--force");
+        }
+
+        let output = cmd.arg(path).output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::GitError(format!(
+                "Failed to remove worktree: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .args(["worktree", "move"])
+            .arg(from)
+            .arg(to)
+            .current_dir(repo)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::GitError(format!(
+                "Failed to move worktree: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn prune_worktrees(&self, repo: &Path) -> Result<()> {
+        let output = git(repo, &["worktree", "prune"])?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::GitError(format!(
+                "Failed to prune worktrees: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn is_branch_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        git(repo, &["merge-base", "--is-ancestor", branch, target])
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Squashes the branch onto its merge base with the target and asks
+    /// `git cherry` whether an equivalent patch (same patch-id) already
+    /// exists on the target
+    fn is_squash_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        let merge_base = match git_stdout(repo, &["merge-base", target, branch]) {
+            Some(base) => base,
+            None => return false,
+        };
+
+        let tree = match git_stdout(repo, &["rev-parse", &format!("{}^{{tree}}", branch)]) {
+            Some(tree) => tree,
+            None => return false,
+        };
+
+        // Nothing left to merge if the branch tree is identical to the target's
+        let target_tree = git_stdout(repo, &["rev-parse", &format!("{}^{{tree}}", target)]);
+        if target_tree.as_deref() == Some(&tree) {
+            return true;
+        }
+
+        let squashed = match git_stdout(
+            repo,
+            &["commit-tree", &tree, "-p", &merge_base, "-m", "squash"],
+        ) {
+            Some(commit) => commit,
+            None => return false,
+        };
+
+        git_stdout(repo, &["cherry", target, &squashed])
+            .map(|out| out.starts_with('-'))
+            .unwrap_or(false)
+    }
+
+    fn has_own_commits(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        let tip = match git_stdout(repo, &["rev-parse", &format!("{}^{{commit}}", branch)]) {
+            Some(tip) => tip,
+            None => return false,
+        };
+
+        if !self.is_branch_merged(repo, branch, target) {
+            return true;
+        }
+
+        // Following first parents back from the target stops just after the
+        // tip when the target has been at it
+        let on_first_parents = match git_stdout(
+            repo,
+            &[
+                "rev-list",
+                "--first-parent",
+                "--reverse",
+                &format!("{}..{}", tip, target),
+            ],
+        ) {
+            Some(revs) => match revs.lines().next() {
+                Some(oldest) => {
+                    git_stdout(repo, &["rev-parse", &format!("{}^", oldest)]).as_deref()
+                        == Some(tip.as_str())
+                }
+                None => true,
+            },
+            None => return false,
+        };
+        if !on_first_parents {
+            return true;
+        }
+
+        git_stdout(
+            repo,
+            &[
+                "reflog",
+                "show",
+                "--format=%H",
+                &format!("refs/heads/{}", branch),
+            ],
+        )
+        .map(|reflog| reflog.lines().count() > 1)
+        .unwrap_or(false)
+    }
+
+    fn is_upstream_gone(&self, repo: &Path, branch: &str) -> bool {
+        git_stdout(
+            repo,
+            &[
+                "for-each-ref",
+                "--format=%(upstream:track)",
+                &format!("refs/heads/{}", branch),
+            ],
+        )
+        .map(|track| track == "[gone]")
+        .unwrap_or(false)
+    }
+
+    fn last_commit_date(&self, repo: &Path, branch: &str) -> Option<DateTime<Utc>> {
+        let timestamp: i64 = git_stdout(repo, &["log", "-1", "--format=%ct", branch, "--"])?
+            .parse()
+            .ok()?;
+
+        Utc.timestamp_opt(timestamp, 0).single()
+    }
+
+    fn status(
+        &self,
+        worktree: &Path,
+        branch: Option<&str>,
+        deadline: Instant,
+    ) -> Option<WorktreeStatus> {
+        let (exit, stdout) = git_with_deadline(
+            worktree,
+            &["status", "--porcelain=v2", "--branch"],
+            deadline,
+        )?;
+
+        if !exit.success() {
+            return None;
+        }
+
+        let mut status = parse_porcelain_v2(&stdout);
+
+        status.operation = git_dir(worktree).and_then(|dir| operation_in_progress(&dir));
+
+        if let Some(branch) = branch {
+            status.stashes = count_stashes(worktree, branch, deadline)?;
+        }
+
+        let default = self.default_branch(worktree);
+        if branch != Some(default.as_str()) {
+            status.default_branch = divergence_from(worktree, &default, deadline)?
+                .map(|divergence| (default, divergence));
+        }
+
+        Some(status)
+    }
+}
+
+/// Run git with string arguments in `cwd`
+fn git(cwd: &Path, args: &[&str]) -> Result<Output> {
+    Ok(Command::new("git").args(args).current_dir(cwd).output()?)
+}
+
+/// Run git in `cwd` and return its trimmed stdout, or None on failure
+fn git_stdout(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = git(cwd, args).ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parse the porcelain output of `git worktree list`
+///
+/// Works on raw bytes so paths that aren't valid UTF-8 survive intact.
+fn parse_worktree_list(output: &[u8], current_dir: Option<&Path>) -> Vec<WorktreeInfo> {
+    let mut worktrees = Vec::new();
+    let mut current: Option<WorktreeInfoBuilder> = None;
+
+    for line in output.split(|&b| b == b'\n') {
+        if let Some(path) = line.strip_prefix(b"worktree ") {
+            // Save previous worktree if exists
+            if let Some(builder) = current.take() {
+                worktrees.push(builder.build(current_dir));
+            }
+            current = Some(WorktreeInfoBuilder {
+                path: path_from_bytes(path),
+                ..Default::default()
+            });
+        } else if let Some(ref mut builder) = current {
+            let line = String::from_utf8_lossy(line);
+            if let Some(commit) = line.strip_prefix("HEAD ") {
+                builder.commit = commit.to_string();
+            } else if let Some(branch) = line.strip_prefix("branch ") {
+                let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+                builder.branch = Some(branch.to_string());
+            } else if line == "bare" {
+                builder.is_bare = true;
+            } else if line == "detached" {
+                builder.is_detached = true;
+            }
+        }
+    }
+
+    // Don't forget the last worktree
+    if let Some(builder) = current {
+        worktrees.push(builder.build(current_dir));
+    }
+
+    worktrees
+}
+
+#[derive(Default)]
+struct WorktreeInfoBuilder {
+    path: PathBuf,
+    branch: Option<String>,
+    commit: String,
+    is_bare: bool,
+    is_detached: bool,
+}
+
+impl WorktreeInfoBuilder {
+    fn build(self, current_dir: Option<&Path>) -> WorktreeInfo {
+        WorktreeInfo::new(
+            clean_path(&self.path),
+            self.branch,
+            self.commit,
+            self.is_bare,
+            self.is_detached,
+            current_dir,
+        )
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).as_ref())
+}
+
+/// Parse `git status --porcelain=v2 --branch`
+fn parse_porcelain_v2(output: &str) -> WorktreeStatus {
+    let mut status = WorktreeStatus::default();
+    let mut has_upstream = false;
+    let mut divergence = None;
+
+    for line in output.lines() {
+        if line.starts_with("# branch.upstream ") {
+            has_upstream = true;
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            // "+<ahead> -<behind>"
+            let mut counts = ab
+                .split_whitespace()
+                .map(|n| n.trim_start_matches(['+', '-']).parse().unwrap_or(0));
+            divergence = Some(Divergence {
+                ahead: counts.next().unwrap_or(0),
+                behind: counts.next().unwrap_or(0),
+            });
+        } else if line.starts_with("1 ") || line.starts_with("2 ") {
+            // "1 XY ..." where X is the index and Y the working tree state
+            let xy = line.as_bytes();
+            if xy.get(2).is_some_and(|&x| x != b'.') {
+                status.staged += 1;
+            }
+            if xy.get(3).is_some_and(|&y| y != b'.') {
+                status.unstaged += 1;
+            }
+        } else if line.starts_with("u ") {
+            status.conflicted += 1;
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        }
+    }
+
+    // An upstream without ahead/behind counts no longer exists on the remote
+    status.upstream = match (has_upstream, divergence) {
+        (true, Some(divergence)) => Upstream::Tracking(divergence),
+        (true, None) => Upstream::Gone,
+        (false, _) => Upstream::NotSet,
+    };
+
+    status
+}
+
+/// Detect an operation waiting for the user from the marker files git leaves
+fn operation_in_progress(git_dir: &Path) -> Option<Operation> {
+    let markers = [
+        ("rebase-merge", Operation::Rebase),
+        ("rebase-apply", Operation::Rebase),
+        ("MERGE_HEAD", Operation::Merge),
+        ("CHERRY_PICK_HEAD", Operation::CherryPick),
+        ("REVERT_HEAD", Operation::Revert),
+        ("BISECT_LOG", Operation::Bisect),
+    ];
+
+    markers
+        .into_iter()
+        .find(|(marker, _)| git_dir.join(marker).exists())
+        .map(|(_, operation)| operation)
+}
+
+/// Count stash entries made on `branch` ("WIP on <branch>:" / "On <branch>:")
+fn count_stashes(worktree_path: &Path, branch: &str, deadline: Instant) -> Option<usize> {
+    let (_, stdout) =
+        git_with_deadline(worktree_path, &["stash", "list", "--format=%gs"], deadline)?;
+
+    let wip = format!("WIP on {}:", branch);
+    let on = format!("On {}:", branch);
+
+    Some(
+        stdout
+            .lines()
+            .filter(|line| line.starts_with(&wip) || line.starts_with(&on))
+            .count(),
+    )
+}
+
+/// Divergence of HEAD from a branch, preferring its remote-tracking ref
+///
+/// The outer None means timeout; the inner one that the branch doesn't exist.
+fn divergence_from(
+    worktree_path: &Path,
+    branch: &str,
+    deadline: Instant,
+) -> Option<Option<Divergence>> {
+    for base in [format!("origin/{}", branch), branch.to_string()] {
+        let range = format!("{}...HEAD", base);
+        let (exit, stdout) = git_with_deadline(
+            worktree_path,
+            &["rev-list", "--left-right", "--count", &range],
+            deadline,
+        )?;
+
+        if !exit.success() {
+            continue;
+        }
+
+        // "<behind>\t<ahead>"
+        let mut counts = stdout.split_whitespace().map(|n| n.parse().unwrap_or(0));
+        let behind = counts.next().unwrap_or(0);
+        let ahead = counts.next().unwrap_or(0);
+        return Some(Some(Divergence { ahead, behind }));
+    }
+
+    Some(None)
+}
+
+/// Run git in `cwd`, killing it if it hasn't finished by `deadline`
+///
+/// Returns the exit status and stdout, or None if git couldn't be started
+/// or timed out.
+fn git_with_deadline(cwd: &Path, args: &[&str], deadline: Instant) -> Option<(ExitStatus, String)> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Read stdout on a separate thread; it reaches EOF when git exits
+    let mut stdout = child.stdout.take()?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = tx.send(stdout.read_to_string(&mut buf).map(|_| buf));
+    });
+
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(stdout)) => {
+            let status = child.wait().ok()?;
+            Some((status, stdout))
+        }
+        _ => {
+            let _ = child.kill();
+            let _ = child.wait();
+            None
+        }
+    }
+}
@@ -0,0 +1,639 @@
+//! Behaviour every [`GitBackend`] must share
+//!
+//! Each check runs against the CLI backend and, with the `native-git`
+//! feature, the native one. Fixtures are set up with the git CLI.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use tempfile::TempDir;
+
+use crate::git::status::{Divergence, Operation, Upstream};
+
+use super::GitBackend;
+
+macro_rules! conformance_tests {
+    ($($check:ident),* $(,)?) => {
+        mod cli {
+            $(
+                #[test]
+                fn $check() {
+                    super::$check(&super::super::cli::CliBackend);
+                }
+            )*
+        }
+
+        #[cfg(feature = "native-git")]
+        mod native {
+            $(
+                #[test]
+                fn $check() {
+                    super::$check(&super::super::native::NativeBackend);
+                }
+            )*
+        }
+    };
+}
+
+conformance_tests!(
+    current_branch,
+    current_branch_detached,
+    list_branches,
+    resolve_ref,
+    default_branch_from_origin_head,
+    default_branch_fallback,
+    list_worktrees,
+    list_worktrees_bare_hub,
+    add_worktree_new_branch,
+    add_worktree_existing_branch,
+    add_worktree_remote_branch,
+    add_worktree_rejects_checked_out_branch,
+    add_worktree_rejects_unknown_branch,
+    remove_worktree,
+    remove_worktree_with_changes,
+    move_worktree,
+    prune_worktrees,
+    delete_branch,
+    status_clean,
+    status_changes,
+    status_upstream,
+    status_merge_conflict,
+    status_stashes,
+    status_default_branch,
+    branch_merged,
+    squash_merged,
+    own_commits,
+    upstream_gone,
+    last_commit_date,
+    non_utf8_path,
+);
+
+// Fixtures
+
+/// Run git in `dir` with a fixed identity, panicking on failure
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .output()
+        .expect("failed to run git");
+
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn temp_dir() -> (TempDir, PathBuf) {
+    let dir = TempDir::new().unwrap();
+    // Resolve symlinks (e.g. /tmp on macOS) so paths compare equal to git's
+    let path = dir.path().canonicalize().unwrap();
+    (dir, path)
+}
+
+fn commit(dir: &Path, file: &str, contents: &str) {
+    std::fs::write(dir.join(file), contents).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", file]);
+}
+
+/// A repository on `main` with one commit
+fn repo() -> (TempDir, PathBuf) {
+    let (tmp, root) = temp_dir();
+    let repo = root.join("repo");
+    std::fs::create_dir(&repo).unwrap();
+    git(&repo, &["init", "-q", "-b", "main"]);
+    commit(&repo, "README", "hello\n");
+    (tmp, repo)
+}
+
+/// A clone of a remote with `main` and `remote-only` branches
+fn clone() -> (TempDir, PathBuf) {
+    let (tmp, origin) = repo();
+    git(&origin, &["branch", "remote-only"]);
+
+    let clone = origin.with_file_name("clone");
+    git(origin.parent().unwrap(), &["clone", "-q", "repo", "clone"]);
+    (tmp, clone)
+}
+
+fn far_future() -> Instant {
+    Instant::now() + Duration::from_secs(30)
+}
+
+// Refs and branches
+
+fn current_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "-b", "feature"]);
+
+    assert_eq!(
+        backend.current_branch(&repo).unwrap(),
+        Some("feature".to_string())
+    );
+}
+
+fn current_branch_detached(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "--detach"]);
+
+    assert_eq!(backend.current_branch(&repo).unwrap(), None);
+}
+
+fn list_branches(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    git(&clone, &["branch", "local-only"]);
+
+    assert_eq!(
+        backend.list_branches(&clone, false).unwrap(),
+        vec!["local-only", "main"]
+    );
+    assert_eq!(
+        backend.list_branches(&clone, true).unwrap(),
+        vec!["local-only", "main", "remote-only"]
+    );
+}
+
+fn resolve_ref(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    let head = git(&clone, &["rev-parse", "HEAD"]);
+
+    assert_eq!(
+        backend.resolve_ref(&clone, "refs/heads/main").unwrap(),
+        Some(head.clone())
+    );
+    assert_eq!(
+        backend
+            .resolve_ref(&clone, "refs/remotes/origin/remote-only")
+            .unwrap(),
+        Some(head)
+    );
+    assert_eq!(
+        backend.resolve_ref(&clone, "refs/heads/missing").unwrap(),
+        None
+    );
+}
+
+fn default_branch_from_origin_head(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    git(&clone, &["remote", "set-head", "origin", "remote-only"]);
+
+    assert_eq!(backend.default_branch(&clone), "remote-only");
+}
+
+fn default_branch_fallback(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    assert_eq!(backend.default_branch(&repo), "main");
+
+    git(&repo, &["branch", "-m", "main", "master"]);
+    assert_eq!(backend.default_branch(&repo), "master");
+}
+
+fn delete_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["branch", "merged"]);
+    git(&repo, &["checkout", "-q", "-b", "unmerged"]);
+    commit(&repo, "file", "change\n");
+    git(&repo, &["checkout", "-q", "main"]);
+
+    backend.delete_branch(&repo, "merged", false).unwrap();
+    assert!(backend.delete_branch(&repo, "unmerged", false).is_err());
+    backend.delete_branch(&repo, "unmerged", true).unwrap();
+
+    assert_eq!(backend.list_branches(&repo, false).unwrap(), vec!["main"]);
+}
+
+// Worktrees
+
+fn list_worktrees(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let head = git(&repo, &["rev-parse", "HEAD"]);
+    let feature = repo.with_file_name("feature");
+    let detached = repo.with_file_name("detached");
+    git(
+        &repo,
+        &["worktree", "add", "-q", "-b", "feature", "../feature"],
+    );
+    git(&repo, &["worktree", "add", "-q", "--detach", "../detached"]);
+
+    let worktrees = backend.list_worktrees(&feature).unwrap();
+    let paths: Vec<&Path> = worktrees.iter().map(|wt| wt.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        vec![repo.as_path(), detached.as_path(), feature.as_path()]
+    );
+
+    assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+    assert_eq!(worktrees[1].branch, None);
+    assert!(worktrees[1].is_detached);
+    assert_eq!(worktrees[2].branch.as_deref(), Some("feature"));
+    assert!(worktrees[2].is_current);
+    assert!(!worktrees[0].is_current && !worktrees[1].is_current);
+    assert!(worktrees.iter().all(|wt| wt.commit == head && !wt.is_bare));
+}
+
+fn list_worktrees_bare_hub(backend: &dyn GitBackend) {
+    let (_tmp, origin) = repo();
+    let hub = origin.with_file_name("hub");
+    std::fs::create_dir(&hub).unwrap();
+    git(&hub, &["clone", "-q", "--bare", "../repo", ".bare"]);
+    std::fs::write(hub.join(".git"), "gitdir: ./.bare\n").unwrap();
+    git(&hub, &["worktree", "add", "-q", "main", "main"]);
+
+    let worktrees = backend.list_worktrees(&hub.join("main")).unwrap();
+    assert_eq!(worktrees.len(), 2);
+    assert!(worktrees[0].is_bare);
+    assert_eq!(worktrees[0].path, hub.join(".bare"));
+    assert_eq!(worktrees[1].path, hub.join("main"));
+    assert_eq!(worktrees[1].branch.as_deref(), Some("main"));
+}
+
+fn add_worktree_new_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("new");
+
+    backend.add_worktree(&repo, "new", &path, true).unwrap();
+
+    assert!(path.join("README").is_file());
+    assert_eq!(git(&path, &["symbolic-ref", "--short", "HEAD"]), "new");
+    assert_eq!(
+        git(&path, &["rev-parse", "HEAD"]),
+        git(&repo, &["rev-parse", "HEAD"])
+    );
+}
+
+fn add_worktree_existing_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["branch", "existing"]);
+    let path = repo.with_file_name("existing");
+
+    backend
+        .add_worktree(&repo, "existing", &path, false)
+        .unwrap();
+
+    assert_eq!(git(&path, &["symbolic-ref", "--short", "HEAD"]), "existing");
+}
+
+fn add_worktree_remote_branch(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    let path = clone.with_file_name("remote-only");
+
+    backend
+        .add_worktree(&clone, "remote-only", &path, false)
+        .unwrap();
+
+    assert_eq!(
+        git(&path, &["symbolic-ref", "--short", "HEAD"]),
+        "remote-only"
+    );
+    assert_eq!(
+        git(&path, &["rev-parse", "--abbrev-ref", "@{upstream}"]),
+        "origin/remote-only"
+    );
+}
+
+fn add_worktree_rejects_checked_out_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("main");
+
+    assert!(backend.add_worktree(&repo, "main", &path, false).is_err());
+    assert!(backend.add_worktree(&repo, "main", &path, true).is_err());
+}
+
+fn add_worktree_rejects_unknown_branch(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("missing");
+
+    assert!(backend
+        .add_worktree(&repo, "missing", &path, false)
+        .is_err());
+    assert!(!path.exists());
+}
+
+fn remove_worktree(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("feature");
+    git(
+        &repo,
+        &["worktree", "add", "-q", "-b", "feature", "../feature"],
+    );
+
+    backend.remove_worktree(&repo, &path, false).unwrap();
+
+    assert!(!path.exists());
+    assert_eq!(backend.list_worktrees(&repo).unwrap().len(), 1);
+    // The branch is kept
+    assert!(backend
+        .resolve_ref(&repo, "refs/heads/feature")
+        .unwrap()
+        .is_some());
+}
+
+fn remove_worktree_with_changes(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("feature");
+    git(
+        &repo,
+        &["worktree", "add", "-q", "-b", "feature", "../feature"],
+    );
+    std::fs::write(path.join("untracked"), "new\n").unwrap();
+
+    assert!(backend.remove_worktree(&repo, &path, false).is_err());
+    assert!(path.join("untracked").is_file());
+
+    backend.remove_worktree(&repo, &path, true).unwrap();
+    assert!(!path.exists());
+}
+
+fn move_worktree(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name("feature");
+    let moved = repo.with_file_name("moved");
+    git(
+        &repo,
+        &["worktree", "add", "-q", "-b", "feature", "../feature"],
+    );
+
+    backend.move_worktree(&repo, &path, &moved).unwrap();
+
+    assert!(!path.exists());
+    assert!(moved.join("README").is_file());
+    let worktrees = backend.list_worktrees(&repo).unwrap();
+    assert_eq!(worktrees[1].path, moved);
+    assert_eq!(worktrees[1].branch.as_deref(), Some("feature"));
+}
+
+fn prune_worktrees(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["worktree", "add", "-q", "-b", "gone", "../gone"]);
+    git(
+        &repo,
+        &["worktree", "add", "-q", "-b", "locked", "../locked"],
+    );
+    git(&repo, &["worktree", "add", "-q", "-b", "kept", "../kept"]);
+    git(&repo, &["worktree", "lock", "../locked"]);
+    std::fs::remove_dir_all(repo.with_file_name("gone")).unwrap();
+    std::fs::remove_dir_all(repo.with_file_name("locked")).unwrap();
+
+    backend.prune_worktrees(&repo).unwrap();
+
+    let worktrees = backend.list_worktrees(&repo).unwrap();
+    let branches: Vec<_> = worktrees.iter().map(|wt| wt.branch.as_deref()).collect();
+    assert_eq!(branches, vec![Some("main"), Some("kept"), Some("locked")]);
+}
+
+// Status
+
+fn status_clean(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+
+    let status = backend.status(&clone, Some("main"), far_future()).unwrap();
+
+    assert!(status.is_clean());
+    assert_eq!(status.default_branch, None);
+}
+
+fn status_changes(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    commit(&repo, "tracked", "one\n");
+    std::fs::write(repo.join("README"), "changed\n").unwrap();
+    std::fs::write(repo.join("staged"), "new\n").unwrap();
+    git(&repo, &["add", "staged"]);
+    std::fs::write(repo.join("tracked"), "staged\n").unwrap();
+    git(&repo, &["add", "tracked"]);
+    std::fs::write(repo.join("tracked"), "staged and changed\n").unwrap();
+    std::fs::write(repo.join("untracked"), "new\n").unwrap();
+    std::fs::create_dir(repo.join("dir")).unwrap();
+    std::fs::write(repo.join("dir/a"), "new\n").unwrap();
+    std::fs::write(repo.join("dir/b"), "new\n").unwrap();
+
+    let status = backend.status(&repo, Some("main"), far_future()).unwrap();
+
+    assert_eq!(status.staged, 2);
+    assert_eq!(status.unstaged, 2);
+    // An untracked directory counts once
+    assert_eq!(status.untracked, 2);
+    assert_eq!(status.conflicted, 0);
+    assert_eq!(status.upstream, Upstream::NotSet);
+}
+
+fn status_upstream(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    let origin = clone.with_file_name("repo");
+    commit(&origin, "upstream", "1\n");
+    git(&clone, &["fetch", "-q"]);
+    commit(&clone, "local1", "1\n");
+    commit(&clone, "local2", "2\n");
+
+    let status = backend.status(&clone, Some("main"), far_future()).unwrap();
+    assert_eq!(
+        status.upstream,
+        Upstream::Tracking(Divergence {
+            ahead: 2,
+            behind: 1
+        })
+    );
+    assert_eq!(status.unpushed(), 2);
+
+    git(
+        &clone,
+        &[
+            "checkout",
+            "-q",
+            "-b",
+            "gone",
+            "--track",
+            "origin/remote-only",
+        ],
+    );
+    git(&origin, &["branch", "-D", "remote-only"]);
+    git(&clone, &["fetch", "-q", "--prune"]);
+
+    let status = backend.status(&clone, Some("gone"), far_future()).unwrap();
+    assert_eq!(status.upstream, Upstream::Gone);
+    assert_eq!(status.unpushed(), 0);
+}
+
+fn status_merge_conflict(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "-b", "other"]);
+    commit(&repo, "README", "other\n");
+    git(&repo, &["checkout", "-q", "main"]);
+    commit(&repo, "README", "main\n");
+
+    let output = Command::new("git")
+        .args(["merge", "other"])
+        .current_dir(&repo)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let status = backend.status(&repo, Some("main"), far_future()).unwrap();
+    assert_eq!(status.conflicted, 1);
+    assert_eq!(status.operation, Some(Operation::Merge));
+    assert!(!status.is_clean());
+}
+
+fn status_stashes(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    std::fs::write(repo.join("README"), "one\n").unwrap();
+    git(&repo, &["stash", "-q"]);
+    std::fs::write(repo.join("README"), "two\n").unwrap();
+    git(&repo, &["stash", "push", "-q", "-m", "named"]);
+    git(&repo, &["checkout", "-q", "-b", "other"]);
+    std::fs::write(repo.join("README"), "three\n").unwrap();
+    git(&repo, &["stash", "-q"]);
+
+    let status = backend.status(&repo, Some("other"), far_future()).unwrap();
+    assert_eq!(status.stashes, 1);
+
+    git(&repo, &["checkout", "-q", "main"]);
+    let status = backend.status(&repo, Some("main"), far_future()).unwrap();
+    assert_eq!(status.stashes, 2);
+}
+
+fn status_default_branch(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    git(&clone, &["checkout", "-q", "-b", "feature"]);
+    commit(&clone, "feature", "1\n");
+
+    let origin = clone.with_file_name("repo");
+    commit(&origin, "main1", "1\n");
+    commit(&origin, "main2", "2\n");
+    git(&clone, &["fetch", "-q"]);
+
+    let status = backend
+        .status(&clone, Some("feature"), far_future())
+        .unwrap();
+    assert_eq!(
+        status.default_branch,
+        Some((
+            "main".to_string(),
+            Divergence {
+                ahead: 1,
+                behind: 2
+            }
+        ))
+    );
+    assert_eq!(status.upstream, Upstream::NotSet);
+    assert_eq!(status.unpushed(), 1);
+}
+
+// Merges
+
+fn branch_merged(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "-b", "merged"]);
+    commit(&repo, "merged", "1\n");
+    git(&repo, &["checkout", "-q", "-b", "unmerged"]);
+    commit(&repo, "unmerged", "1\n");
+    git(&repo, &["checkout", "-q", "main"]);
+    git(&repo, &["merge", "-q", "--ff-only", "merged"]);
+
+    assert!(backend.is_branch_merged(&repo, "merged", "main"));
+    assert!(backend.is_branch_merged(&repo, "main", "main"));
+    assert!(!backend.is_branch_merged(&repo, "unmerged", "main"));
+    assert!(!backend.is_branch_merged(&repo, "missing", "main"));
+}
+
+fn squash_merged(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "-b", "squashed"]);
+    commit(&repo, "one", "1\n");
+    commit(&repo, "two", "2\n");
+    git(&repo, &["checkout", "-q", "-b", "unmerged", "main"]);
+    commit(&repo, "three", "3\n");
+    git(&repo, &["checkout", "-q", "main"]);
+    git(&repo, &["merge", "-q", "--squash", "squashed"]);
+    git(&repo, &["commit", "-q", "-m", "squashed"]);
+    commit(&repo, "later", "4\n");
+
+    assert!(backend.is_squash_merged(&repo, "squashed", "main"));
+    assert!(!backend.is_branch_merged(&repo, "squashed", "main"));
+    assert!(!backend.is_squash_merged(&repo, "unmerged", "main"));
+
+    // A branch whose tree the target already has is merged too
+    git(&repo, &["branch", "same-tree", "main"]);
+    assert!(backend.is_squash_merged(&repo, "same-tree", "main"));
+}
+
+fn own_commits(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["branch", "fresh"]);
+    git(&repo, &["checkout", "-q", "-b", "fast-forward"]);
+    commit(&repo, "ff", "1\n");
+    git(&repo, &["checkout", "-q", "-b", "no-ff", "main"]);
+    commit(&repo, "no-ff", "1\n");
+    git(&repo, &["checkout", "-q", "-b", "unmerged", "main"]);
+    commit(&repo, "unmerged", "1\n");
+    git(&repo, &["checkout", "-q", "main"]);
+    git(&repo, &["merge", "-q", "--ff-only", "fast-forward"]);
+    git(&repo, &["merge", "-q", "--no-ff", "-m", "merge", "no-ff"]);
+
+    assert!(!backend.has_own_commits(&repo, "fresh", "main"));
+    assert!(backend.has_own_commits(&repo, "fast-forward", "main"));
+    assert!(backend.has_own_commits(&repo, "no-ff", "main"));
+    assert!(backend.has_own_commits(&repo, "unmerged", "main"));
+    assert!(!backend.has_own_commits(&repo, "missing", "main"));
+}
+
+fn upstream_gone(backend: &dyn GitBackend) {
+    let (_tmp, clone) = clone();
+    git(&clone, &["branch", "--track", "gone", "origin/remote-only"]);
+    git(&clone, &["branch", "local-only"]);
+    assert!(!backend.is_upstream_gone(&clone, "gone"));
+
+    let origin = clone.with_file_name("repo");
+    git(&origin, &["branch", "-D", "remote-only"]);
+    git(&clone, &["fetch", "-q", "--prune"]);
+
+    assert!(backend.is_upstream_gone(&clone, "gone"));
+    assert!(!backend.is_upstream_gone(&clone, "main"));
+    assert!(!backend.is_upstream_gone(&clone, "local-only"));
+}
+
+fn last_commit_date(backend: &dyn GitBackend) {
+    let (_tmp, repo) = repo();
+    git(&repo, &["checkout", "-q", "-b", "feature"]);
+    // A file named like the branch mustn't make the revision ambiguous
+    commit(&repo, "feature", "1\n");
+    let timestamp: i64 = git(&repo, &["log", "-1", "--format=%ct"]).parse().unwrap();
+
+    let date = backend.last_commit_date(&repo, "feature").unwrap();
+    assert_eq!(date.timestamp(), timestamp);
+    assert_eq!(backend.last_commit_date(&repo, "missing"), None);
+}
+
+// Paths
+// Paths
+
+#[cfg(unix)]
+fn non_utf8_path(backend: &dyn GitBackend) {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let (_tmp, repo) = repo();
+    let path = repo.with_file_name(OsStr::from_bytes(b"caf\xe9"));
+
+    backend.add_worktree(&repo, "cafe", &path, true).unwrap();
+
+    let worktrees = backend.list_worktrees(&repo).unwrap();
+    assert_eq!(worktrees[1].path, path);
+    assert!(backend.status(&path, Some("cafe"), far_future()).is_some());
+
+    backend.remove_worktree(&repo, &path, false).unwrap();
+    assert!(!path.exists());
+}
+
+#[cfg(not(unix))]
+fn non_utf8_path(_backend: &dyn GitBackend) {}
@@ -0,0 +1,145 @@
+//! Pluggable implementations of the git operations dev-cli relies on
+//!
+//! Commands use the backend carried by their `Context`, normally
+//! [`backend()`]: the git CLI by default or libgit2 in-process with the
+//! `native-git` feature. Operations without a native equivalent (cloning,
+//! pushing) always use the CLI, and the native backend moves worktrees with
+//! it too, as libgit2 can't.
+
+// Kept in native builds as the reference for the conformance suite
+#[cfg_attr(feature = "native-git", allow(dead_code))]
+mod cli;
+#[cfg(feature = "native-git")]
+mod native;
+
+#[cfg(test)]
+mod conformance;
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::Result;
+
+use super::status::WorktreeStatus;
+use super::worktree::WorktreeInfo;
+
+/// Git operations on branches, refs, worktrees and status
+///
+/// `repo` may be any directory inside the repository or one of its
+/// worktrees; `worktree` must be the root or a subdirectory of a worktree.
+pub trait GitBackend: Send + Sync {
+    /// Commit id a full ref name (e.g. `refs/heads/main`) points to, if it exists
+    fn resolve_ref(&self, repo: &Path, refname: &str) -> Result<Option<String>>;
+
+    /// Branch checked out in a worktree (None when detached)
+    fn current_branch(&self, worktree: &Path) -> Result<Option<String>>;
+
+    /// Default branch: origin/HEAD, falling back to master if only master
+    /// exists locally, otherwise main
+    fn default_branch(&self, repo: &Path) -> String;
+
+    /// Local branch names, plus remote branch names with `origin/` stripped,
+    /// sorted and deduplicated
+    fn list_branches(&self, repo: &Path, include_remote: bool) -> Result<Vec<String>>;
+
+    /// Delete a local branch (only if merged into HEAD unless forced)
+    fn delete_branch(&self, repo: &Path, name: &str, force: bool) -> Result<()>;
+
+    /// All worktrees, the main one (or the bare repository) first; the one
+    /// containing `repo` is marked current
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>>;
+
+    /// Check out `branch` into a new worktree at `path`, creating the branch
+    /// from HEAD or from `origin/<branch>` as needed
+    fn add_worktree(
+        &self,
+        repo: &Path,
+        branch: &str,
+        path: &Path,
+        create_branch: bool,
+    ) -> Result<()>;
+
+    /// Remove a linked worktree (`force` discards uncommitted changes)
+    fn remove_worktree(&self, repo: &Path, path: &Path, force: bool) -> Result<()>;
+
+    /// Move a linked worktree from `from` to `to`, whose parent must exist
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()>;
+
+    /// Forget linked worktrees whose directories are gone, unless locked
+    fn prune_worktrees(&self, repo: &Path) -> Result<()>;
+
+    /// Whether `branch` is fully merged into `target` (its tip is an ancestor)
+    ///
+    /// Like the other merge queries, `branch` and `target` may be any
+    /// revision, e.g. `origin/main`, and failures count as false.
+    fn is_branch_merged(&self, repo: &Path, branch: &str, target: &str) -> bool;
+
+    /// Whether `branch` was squash-merged into `target`: a commit on the
+    /// target since the merge base makes the same change as the whole
+    /// branch, or the two trees are already identical
+    fn is_squash_merged(&self, repo: &Path, branch: &str, target: &str) -> bool;
+
+    /// Whether `branch` has commits of its own, rather than only pointing at
+    /// a commit the target has been at, as a branch fresh from it does
+    ///
+    /// Commits the target doesn't have count, as does a tip the target only
+    /// reached through a merge. A fast-forward merge leaves the tip in the
+    /// target's own history, so there the branch's reflog has to show that
+    /// it moved after it was created.
+    fn has_own_commits(&self, repo: &Path, branch: &str, target: &str) -> bool;
+
+    /// Whether the upstream of a local branch has been deleted on the remote
+    fn is_upstream_gone(&self, repo: &Path, branch: &str) -> bool;
+
+    /// Committer date of the last commit on `branch`
+    fn last_commit_date(&self, repo: &Path, branch: &str) -> Option<DateTime<Utc>>;
+
+    /// Status of a worktree, or None on failure or when `deadline` passes
+    fn status(
+        &self,
+        worktree: &Path,
+        branch: Option<&str>,
+        deadline: Instant,
+    ) -> Option<WorktreeStatus>;
+}
+
+#[cfg(not(feature = "native-git"))]
+static BACKEND: cli::CliBackend = cli::CliBackend;
+
+#[cfg(feature = "native-git")]
+static BACKEND: native::NativeBackend = native::NativeBackend;
+
+/// The backend selected at build time
+pub fn backend() -> &'static dyn GitBackend {
+    &BACKEND
+}
+
+/// Normalise a path reported by git (drops trailing separators)
+fn clean_path(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+/// Branch names from full ref names: `refs/heads/<name>`, `refs/remotes/origin/<name>`
+/// (without `origin/HEAD`) and `refs/remotes/<remote>/<name>` as `<remote>/<name>`,
+/// sorted and deduplicated
+fn branch_names<'a>(refnames: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut branches: Vec<String> = refnames
+        .into_iter()
+        .filter(|refname| *refname != "refs/remotes/origin/HEAD")
+        .filter_map(|refname| {
+            refname
+                .strip_prefix("refs/heads/")
+                .or_else(|| refname.strip_prefix("refs/remotes/origin/"))
+                .or_else(|| refname.strip_prefix("refs/remotes/"))
+        })
+        .filter(|branch| !branch.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    // Remove duplicates (local and remote versions of the same branch)
+    branches.sort();
+    branches.dedup();
+    branches
+}
@@ -0,0 +1,642 @@
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{
+    BranchType, Commit, ErrorCode, Oid, Repository, RepositoryState, Sort, Status, StatusOptions,
+    Tree, WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions,
+};
+
+use crate::error::{DevError, Result};
+use crate::git::status::{Divergence, Operation, Upstream, WorktreeStatus};
+use crate::git::worktree::WorktreeInfo;
+
+use super::{branch_names, clean_path, GitBackend};
+
+/// Backend that uses libgit2 in-process
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeBackend;
+
+impl GitBackend for NativeBackend {
+    fn resolve_ref(&self, repo: &Path, refname: &str) -> Result<Option<String>> {
+        let repo = open(repo)?;
+
+        let commit = match repo.find_reference(refname) {
+            Ok(reference) => reference.peel_to_commit().ok().map(|c| c.id().to_string()),
+            Err(e) if e.code() == ErrorCode::NotFound || e.code() == ErrorCode::InvalidSpec => None,
+            Err(e) => return Err(git_error("Failed to resolve reference", e)),
+        };
+
+        Ok(commit)
+    }
+
+    fn current_branch(&self, worktree: &Path) -> Result<Option<String>> {
+        let repo = open(worktree)?;
+        let head = repo
+            .find_reference("HEAD")
+            .map_err(|e| git_error("Failed to read HEAD", e))?;
+
+        // Might be in detached HEAD state
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(str::to_string))
+    }
+
+    fn default_branch(&self, repo: &Path) -> String {
+        let repo = match open(repo) {
+            Ok(repo) => repo,
+            Err(_) => return "main".to_string(),
+        };
+
+        if let Ok(head) = repo.find_reference("refs/remotes/origin/HEAD") {
+            if let Some(branch) = head
+                .symbolic_target()
+                .and_then(|target| target.strip_prefix("refs/remotes/origin/"))
+            {
+                return branch.to_string();
+            }
+        }
+
+        let has_local = |name: &str| repo.find_branch(name, BranchType::Local).is_ok();
+
+        if !has_local("main") && has_local("master") {
+            return "master".to_string();
+        }
+
+        "main".to_string()
+    }
+
+    fn list_branches(&self, repo: &Path, include_remote: bool) -> Result<Vec<String>> {
+        let repo = open(repo)?;
+
+        let filter = if include_remote {
+            None
+        } else {
+            Some(BranchType::Local)
+        };
+
+        let branches = repo
+            .branches(filter)
+            .map_err(|e| git_error("Failed to list branches", e))?;
+
+        let refnames: Vec<String> = branches
+            .filter_map(|branch| branch.ok())
+            .filter_map(|(branch, _)| branch.get().name().map(str::to_string))
+            .collect();
+
+        Ok(branch_names(refnames.iter().map(String::as_str)))
+    }
+
+    fn delete_branch(&self, repo: &Path, name: &str, force: bool) -> Result<()> {
+        let repo = open(repo)?;
+        let context = "Failed to delete branch";
+
+        let mut branch = repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|e| git_error(context, e))?;
+
+        if !force {
+            let tip = branch
+                .get()
+                .peel_to_commit()
+                .map_err(|e| git_error(context, e))?
+                .id();
+
+            // Like `git branch -d`: merged into its upstream, or else into HEAD
+            let target = branch
+                .upstream()
+                .ok()
+                .and_then(|upstream| upstream.get().target())
+                .or_else(|| repo.head().ok().and_then(|head| head.target()));
+
+            let merged = target.is_some_and(|target| {
+                target == tip || repo.graph_descendant_of(target, tip).unwrap_or(false)
+            });
+
+            if !merged {
+                return Err(DevError::GitError(format!(
+                    "{}: the branch '{}' is not fully merged",
+                    context, name
+                )));
+            }
+        }
+
+        branch.delete().map_err(|e| git_error(context, e))
+    }
+
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>> {
+        let main = open_main(repo)?;
+        let current_dir = repo.canonicalize().ok();
+        let mut worktrees = Vec::new();
+
+        if main.is_bare() {
+            worktrees.push(WorktreeInfo::new(
+                clean_path(main.path()),
+                None,
+                String::new(),
+                true,
+                false,
+                current_dir.as_deref(),
+            ));
+        } else if let Some(workdir) = main.workdir() {
+            let (branch, commit) = read_head(&main, &main.path().join("HEAD"));
+            worktrees.push(WorktreeInfo::new(
+                clean_path(workdir),
+                branch.clone(),
+                commit,
+                false,
+                branch.is_none(),
+                current_dir.as_deref(),
+            ));
+        }
+
+        let names = main
+            .worktrees()
+            .map_err(|e| git_error("Failed to list worktrees", e))?;
+
+        let mut linked = Vec::new();
+        for name in names.iter().flatten() {
+            let worktree = match main.find_worktree(name) {
+                Ok(worktree) => worktree,
+                Err(_) => continue,
+            };
+
+            let head_file = main.path().join("worktrees").join(name).join("HEAD");
+            let (branch, commit) = read_head(&main, &head_file);
+
+            linked.push(WorktreeInfo::new(
+                clean_path(worktree.path()),
+                branch.clone(),
+                commit,
+                false,
+                branch.is_none(),
+                current_dir.as_deref(),
+            ));
+        }
+
+        // git lists linked worktrees ordered by path
+        linked.sort_by(|a, b| a.path.cmp(&b.path));
+        worktrees.extend(linked);
+
+        Ok(worktrees)
+    }
+
+    fn add_worktree(
+        &self,
+        repo: &Path,
+        branch: &str,
+        path: &Path,
+        create_branch: bool,
+    ) -> Result<()> {
+        let repo = open(repo)?;
+        let context = "Failed to create worktree";
+
+        let (reference, created) = if create_branch {
+            let head = repo
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .map_err(|e| git_error(context, e))?;
+            let local = repo
+                .branch(branch, &head, false)
+                .map_err(|e| git_error(context, e))?;
+            (local.into_reference(), true)
+        } else {
+            match repo.find_branch(branch, BranchType::Local) {
+                Ok(local) => (local.into_reference(), false),
+                Err(e) if e.code() == ErrorCode::NotFound => {
+                    // Like `git worktree add`, start a tracking branch from origin
+                    let remote = repo
+                        .find_branch(&format!("origin/{}", branch), BranchType::Remote)
+                        .map_err(|_| {
+                            DevError::GitError(format!(
+                                "{}: invalid reference: {}",
+                                context, branch
+                            ))
+                        })?;
+                    let commit = remote
+                        .get()
+                        .peel_to_commit()
+                        .map_err(|e| git_error(context, e))?;
+                    let mut local = repo
+                        .branch(branch, &commit, false)
+                        .map_err(|e| git_error(context, e))?;
+                    local
+                        .set_upstream(Some(&format!("origin/{}", branch)))
+                        .map_err(|e| git_error(context, e))?;
+                    (local.into_reference(), true)
+                }
+                Err(e) => return Err(git_error(context, e)),
+            }
+        };
+
+        let mut opts = WorktreeAddOptions::new();
+        opts.reference(Some(&reference));
+
+        let name = worktree_name(&repo, path);
+        if let Err(e) = repo.worktree(&name, path, Some(&opts)) {
+            // Don't leave behind a branch created for the failed worktree
+            if created {
+                if let Ok(mut local) = repo.find_branch(branch, BranchType::Local) {
+                    let _ = local.delete();
+                }
+            }
+            return Err(git_error(context, e));
+        }
+
+        Ok(())
+    }
+
+    fn remove_worktree(&self, repo: &Path, path: &Path, force: bool) -> Result<()> {
+        let main = open_main(repo)?;
+        let context = "Failed to remove worktree";
+        let target = clean_path(path);
+
+        let names = main.worktrees().map_err(|e| git_error(context, e))?;
+
+        let worktree = names
+            .iter()
+            .flatten()
+            .filter_map(|name| main.find_worktree(name).ok())
+            .find(|wt| clean_path(wt.path()) == target)
+            .ok_or_else(|| {
+                DevError::GitError(format!(
+                    "{}: '{}' is not a working tree",
+                    context,
+                    path.display()
+                ))
+            })?;
+
+        if let Ok(WorktreeLockStatus::Locked(_)) = worktree.is_locked() {
+            return Err(DevError::GitError(format!(
+                "{}: '{}' is locked",
+                context,
+                path.display()
+            )));
+        }
+
+        if !force {
+            if let Ok(repo) = Repository::open_from_worktree(&worktree) {
+                let mut opts = StatusOptions::new();
+                opts.include_untracked(true);
+                let dirty = repo
+                    .statuses(Some(&mut opts))
+                    .map(|statuses| !statuses.is_empty())
+                    .unwrap_or(false);
+
+                if dirty {
+                    return Err(DevError::GitError(format!(
+                        "{}: '{}' contains modified or untracked files, use --force to delete it",
+                        context,
+                        path.display()
+                    )));
+                }
+            }
+        }
+
+        let mut opts = WorktreePruneOptions::new();
+        opts.valid(true).working_tree(true);
+        worktree
+            .prune(Some(&mut opts))
+            .map_err(|e| git_error(context, e))
+    }
+
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+        // libgit2 has no way to move a worktree
+        super::cli::CliBackend.move_worktree(repo, from, to)
+    }
+
+    fn prune_worktrees(&self, repo: &Path) -> Result<()> {
+        let main = open_main(repo)?;
+        let context = "Failed to prune worktrees";
+
+        let names = main.worktrees().map_err(|e| git_error(context, e))?;
+
+        for name in names.iter().flatten() {
+            let worktree = match main.find_worktree(name) {
+                Ok(worktree) => worktree,
+                Err(_) => continue,
+            };
+
+            // Like `git worktree prune`, only those whose directory is gone
+            if worktree.validate().is_err() && worktree.is_prunable(None).unwrap_or(false) {
+                worktree.prune(None).map_err(|e| git_error(context, e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_branch_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        open(repo)
+            .ok()
+            .and_then(|repo| is_ancestor(&repo, branch, target))
+            .unwrap_or(false)
+    }
+
+    /// Compares the patch-id of the whole branch against those of the
+    /// target's commits since the merge base, as `git cherry` would
+    fn is_squash_merged(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        open(repo)
+            .ok()
+            .and_then(|repo| squash_merged(&repo, branch, target))
+            .unwrap_or(false)
+    }
+
+    fn has_own_commits(&self, repo: &Path, branch: &str, target: &str) -> bool {
+        open(repo)
+            .ok()
+            .and_then(|repo| own_commits(&repo, branch, target))
+            .unwrap_or(false)
+    }
+
+    fn is_upstream_gone(&self, repo: &Path, branch: &str) -> bool {
+        let repo = match open(repo) {
+            Ok(repo) => repo,
+            Err(_) => return false,
+        };
+
+        match repo.branch_upstream_name(&format!("refs/heads/{}", branch)) {
+            Ok(upstream) => upstream
+                .as_str()
+                .is_some_and(|name| repo.refname_to_id(name).is_err()),
+            Err(_) => false,
+        }
+    }
+
+    fn last_commit_date(&self, repo: &Path, branch: &str) -> Option<DateTime<Utc>> {
+        let repo = open(repo).ok()?;
+        let commit = find_commit(&repo, branch)?;
+
+        Utc.timestamp_opt(commit.time().seconds(), 0).single()
+    }
+
+    fn status(
+        &self,
+        worktree: &Path,
+        branch: Option<&str>,
+        deadline: Instant,
+    ) -> Option<WorktreeStatus> {
+        // libgit2 can't be interrupted, so run it on a thread we can abandon
+        let worktree = worktree.to_path_buf();
+        let branch = branch.map(str::to_string);
+        let default = self.default_branch(&worktree);
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(compute_status(&worktree, branch.as_deref(), default));
+        });
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        rx.recv_timeout(timeout).ok().flatten()
+    }
+}
+
+/// Open the repository containing `path`
+fn open(path: &Path) -> Result<Repository> {
+    Repository::discover(path).map_err(|_| DevError::NotGitRepository)
+}
+
+/// Open the main repository (not a linked worktree) containing `path`
+fn open_main(path: &Path) -> Result<Repository> {
+    let repo = open(path)?;
+
+    if !repo.is_worktree() {
+        return Ok(repo);
+    }
+
+    Repository::open(repo.commondir()).map_err(|_| DevError::NotGitRepository)
+}
+
+fn git_error(context: &str, error: git2::Error) -> DevError {
+    DevError::GitError(format!("{}: {}", context, error.message()))
+}
+
+/// The commit a revision (e.g. `origin/main`) points to
+fn find_commit<'r>(repo: &'r Repository, rev: &str) -> Option<Commit<'r>> {
+    repo.revparse_single(rev).ok()?.peel_to_commit().ok()
+}
+
+/// Whether `branch` is `target` or one of its ancestors
+fn is_ancestor(repo: &Repository, branch: &str, target: &str) -> Option<bool> {
+    let tip = find_commit(repo, branch)?.id();
+    let target = find_commit(repo, target)?.id();
+
+    Some(tip == target || repo.graph_descendant_of(target, tip).ok()?)
+}
+
+fn squash_merged(repo: &Repository, branch: &str, target: &str) -> Option<bool> {
+    let tip = find_commit(repo, branch)?;
+    let target = find_commit(repo, target)?;
+
+    // Nothing left to merge if the branch tree is identical to the target's
+    if tip.tree_id() == target.tree_id() {
+        return Some(true);
+    }
+
+    let base = repo.merge_base(target.id(), tip.id()).ok()?;
+    let base_tree = repo.find_commit(base).ok()?.tree().ok()?;
+    let squashed = patch_id(repo, &base_tree, &tip.tree().ok()?)?;
+
+    let mut walk = repo.revwalk().ok()?;
+    walk.push(target.id()).ok()?;
+    walk.hide(base).ok()?;
+
+    for oid in walk.flatten() {
+        let commit = repo.find_commit(oid).ok()?;
+        if commit.parent_count() != 1 {
+            continue;
+        }
+
+        let parent = commit.parent(0).ok()?;
+        if patch_id(repo, &parent.tree().ok()?, &commit.tree().ok()?) == Some(squashed) {
+            return Some(true);
+        }
+    }
+
+    Some(false)
+}
+
+/// Patch-id of the change from `old` to `new`
+fn patch_id(repo: &Repository, old: &Tree, new: &Tree) -> Option<Oid> {
+    repo.diff_tree_to_tree(Some(old), Some(new), None)
+        .ok()?
+        .patchid(None)
+        .ok()
+}
+
+fn own_commits(repo: &Repository, branch: &str, target: &str) -> Option<bool> {
+    let tip = find_commit(repo, branch)?.id();
+
+    if !is_ancestor(repo, branch, target)? {
+        return Some(true);
+    }
+
+    // Following first parents back from the target stops just after the
+    // tip when the target has been at it
+    let mut walk = repo.revwalk().ok()?;
+    walk.simplify_first_parent().ok()?;
+    walk.set_sorting(Sort::TOPOLOGICAL).ok()?;
+    walk.push(find_commit(repo, target)?.id()).ok()?;
+    walk.hide(tip).ok()?;
+
+    let on_first_parents = match walk.flatten().last() {
+        Some(oldest) => repo.find_commit(oldest).ok()?.parent_id(0).ok() == Some(tip),
+        None => true,
+    };
+    if !on_first_parents {
+        return Some(true);
+    }
+
+    Some(
+        repo.reflog(&format!("refs/heads/{}", branch))
+            .is_ok_and(|reflog| reflog.len() > 1),
+    )
+}
+
+/// Read a HEAD file: the checked-out branch (None when detached) and the
+/// commit it points to (all zeros for an unborn branch)
+fn read_head(repo: &Repository, head_file: &Path) -> (Option<String>, String) {
+    let contents = std::fs::read_to_string(head_file).unwrap_or_default();
+    let contents = contents.trim();
+
+    match contents.strip_prefix("ref: ") {
+        Some(refname) => {
+            let commit = repo
+                .refname_to_id(refname)
+                .unwrap_or_else(|_| Oid::zero())
+                .to_string();
+            let branch = refname.strip_prefix("refs/heads/").unwrap_or(refname);
+            (Some(branch.to_string()), commit)
+        }
+        None => (None, contents.to_string()),
+    }
+}
+
+/// Administrative name for a new worktree: the directory name, made unique
+/// the way git does by appending a number
+fn worktree_name(repo: &Repository, path: &Path) -> String {
+    let base = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "worktree".to_string());
+
+    let admin_dir = repo.commondir().join("worktrees");
+    let mut name = base.clone();
+    let mut n = 1;
+
+    while admin_dir.join(&name).exists() {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+
+    name
+}
+
+fn compute_status(
+    worktree: &Path,
+    branch: Option<&str>,
+    default: String,
+) -> Option<WorktreeStatus> {
+    let mut repo = open(worktree).ok()?;
+    let mut status = WorktreeStatus::default();
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .renames_head_to_index(true);
+
+    for entry in repo.statuses(Some(&mut opts)).ok()?.iter() {
+        let flags = entry.status();
+
+        if flags.is_conflicted() {
+            status.conflicted += 1;
+            continue;
+        }
+
+        if flags.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            status.staged += 1;
+        }
+
+        if flags.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+        ) {
+            status.unstaged += 1;
+        }
+
+        if flags.contains(Status::WT_NEW) {
+            status.untracked += 1;
+        }
+    }
+
+    let head = repo.head().ok().and_then(|head| head.target());
+
+    if let Some(branch) = branch {
+        let refname = format!("refs/heads/{}", branch);
+        status.upstream = match repo.branch_upstream_name(&refname) {
+            Ok(upstream) => match upstream.as_str().map(|name| repo.refname_to_id(name)) {
+                Some(Ok(upstream)) => Upstream::Tracking(divergence(&repo, head, upstream)),
+                _ => Upstream::Gone,
+            },
+            Err(_) => Upstream::NotSet,
+        };
+    }
+
+    status.operation = match repo.state() {
+        RepositoryState::Clean => None,
+        RepositoryState::Merge => Some(Operation::Merge),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(Operation::Revert),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+            Some(Operation::CherryPick)
+        }
+        RepositoryState::Bisect => Some(Operation::Bisect),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailbox
+        | RepositoryState::ApplyMailboxOrRebase => Some(Operation::Rebase),
+    };
+
+    if let Some(branch) = branch {
+        let wip = format!("WIP on {}:", branch);
+        let on = format!("On {}:", branch);
+        let mut stashes = 0;
+        let _ = repo.stash_foreach(|_, message, _| {
+            if message.starts_with(&wip) || message.starts_with(&on) {
+                stashes += 1;
+            }
+            true
+        });
+        status.stashes = stashes;
+    }
+
+    if branch != Some(default.as_str()) {
+        let base = [
+            format!("refs/remotes/origin/{}", default),
+            format!("refs/heads/{}", default),
+        ]
+        .iter()
+        .find_map(|refname| repo.refname_to_id(refname).ok());
+
+        if let Some(base) = base {
+            status.default_branch = Some((default, divergence(&repo, head, base)));
+        }
+    }
+
+    Some(status)
+}
+
+/// Divergence of `head` from `base` (zero when HEAD is unborn)
+fn divergence(repo: &Repository, head: Option<Oid>, base: Oid) -> Divergence {
+    head.and_then(|head| repo.graph_ahead_behind(head, base).ok())
+        .map(|(ahead, behind)| Divergence { ahead, behind })
+        .unwrap_or_default()
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::layout::branch_slug;
+use crate::error::{DevError, Result};
+
+use super::backend::GitBackend;
+use super::run_git_command;
+
+/// Directory holding the bare repository inside a hub
+pub const BARE_DIR: &str = ".bare";
+
+/// Clone a repository into the bare "hub" layout:
+///
+/// ```text
+/// project/
+///   .bare/   bare clone
+///   .git     file containing `gitdir: ./.bare`
+///   main/    worktree for the default branch
+/// ```
+///
+/// Returns the path of the default branch worktree. Cloning and fetching
+/// use the git CLI in every build, for the user's credential helpers and SSH
+/// config; only the worktree is added with `git`.
+pub fn clone_hub(git: &dyn GitBackend, url: &str, hub_path: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(hub_path)?;
+
+    run_git_command(&["clone", "--bare", url, BARE_DIR], Some(hub_path))
+        .map_err(|e| DevError::GitError(format!("Failed to clone repository: {}", e)))?;
+
+    std::fs::write(hub_path.join(".git"), format!("gitdir: ./{}\n", BARE_DIR))?;
+
+    // Bare clones don't track remote branches; restore the normal refspec
+    run_git_command(
+        &[
+            "config",
+            "remote.origin.fetch",
+            "+refs/heads/*:refs/remotes/origin/*",
+        ],
+        Some(hub_path),
+    )?;
+    run_git_command(&["fetch", "origin"], Some(hub_path))?;
+    let _ = run_git_command(&["remote", "set-head", "origin", "--auto"], Some(hub_path));
+
+    let default_branch = run_git_command(&["symbolic-ref", "--short", "HEAD"], Some(hub_path))?
+        .trim()
+        .to_string();
+    let worktree_path = hub_path.join(branch_slug(&default_branch));
+
+    // A bare clone copies every branch as a local branch; keep only the
+    // default one, like a regular clone, so the rest come from origin/*
+    let local_branches = run_git_command(
+        &["for-each-ref", "--format=%(refname:short)", "refs/heads"],
+        Some(hub_path),
+    )?;
+    for branch in local_branches.lines().map(str::trim) {
+        if !branch.is_empty() && branch != default_branch {
+            run_git_command(&["branch", "-D", branch], Some(hub_path))?;
+        }
+    }
+
+    git.add_worktree(hub_path, &default_branch, &worktree_path, false)?;
+    run_git_command(
+        &[
+            "branch",
+            "--set-upstream-to",
+            &format!("origin/{}", default_branch),
+            &default_branch,
+        ],
+        Some(hub_path),
+    )?;
+
+    Ok(worktree_path)
+}
+
+/// Find the hub directory for a path inside a bare-layout project, if any
+///
+/// Asks the git CLI, like [`is_git_repository`](super::is_git_repository),
+/// so the answer matches what the user's git sees.
+pub fn find_hub_root(path: &Path) -> Option<PathBuf> {
+    let common_dir = run_git_command(&["rev-parse", "--git-common-dir"], Some(path)).ok()?;
+    let common_dir = path.join(common_dir.trim()).canonicalize().ok()?;
+
+    let is_bare = run_git_command(&["config", "--bool", "core.bare"], Some(&common_dir)).ok()?;
+    if is_bare.trim() != "true" {
+        return None;
+    }
+
+    let hub = common_dir.parent()?;
+    if hub.join(".git").is_file() {
+        Some(hub.to_path_buf())
+    } else {
+        None
+    }
+}
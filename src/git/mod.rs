@@ -1,17 +1,29 @@
+pub mod backend;
 pub mod branch;
+pub mod hub;
 pub mod status;
 pub mod worktree;
 
+pub use backend::{backend, GitBackend};
 pub use branch::*;
+pub use hub::*;
 pub use status::*;
 pub use worktree::*;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::error::{DevError, Result};
 
+/// Oldest git with every worktree command dev runs (`git worktree move`
+/// and `git worktree remove` arrived in 2.17)
+pub const MIN_GIT_VERSION: (u32, u32) = (2, 17);
+
 /// Check if a directory is inside a git repository
+///
+/// Asks the git CLI in every build: this gates commands on whether `git`
+/// itself will work there, and only the CLI honours `GIT_DIR` and friends
+/// the way the user's own git commands do.
 pub fn is_git_repository(path: &Path) -> bool {
     Command::new("git")
         .args(["rev-parse", "--git-dir"])
@@ -45,7 +57,10 @@ pub fn init_repository(path: &Path) -> Result<()> {
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(DevError::GitError(format!("Failed to init repository: {}", stderr)));
+        return Err(DevError::GitError(format!(
+            "Failed to init repository: {}",
+            stderr
+        )));
     }
 
     Ok(())
@@ -54,18 +69,70 @@ pub fn init_repository(path: &Path) -> Result<()> {
 /// Clone a repository from a URL
 pub fn clone_repository(url: &str, path: &Path) -> Result<()> {
     let output = Command::new("git")
-        .args(["clone", url, path.to_str().unwrap_or(".")])
+        .args(["clone", url])
+        .arg(path)
         .output()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(DevError::GitError(format!("Failed to clone repository: {}", stderr)));
+        return Err(DevError::GitError(format!(
+            "Failed to clone repository: {}",
+            stderr
+        )));
     }
 
     Ok(())
 }
 
+/// Resolve the git dir of a worktree from its `.git` file or directory
+pub fn git_dir(worktree_path: &Path) -> Option<PathBuf> {
+    let dot_git = worktree_path.join(".git");
+
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let dir = contents.trim().strip_prefix("gitdir:")?.trim();
+    Some(worktree_path.join(dir))
+}
+
+/// The tracked files at or under `paths`, all relative to `worktree`
+///
+/// Uses the git CLI in every build, as libgit2 can't read a sparse index
+/// and would miss files there.
+pub fn tracked_files(worktree: &Path, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("git")
+        .args(["--literal-pathspecs", "ls-files", "-z", "--"])
+        .args(paths)
+        .current_dir(worktree)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(DevError::GitError(format!(
+            "Failed to list tracked files: {}",
+            stderr
+        )));
+    }
+
+    Ok(output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| PathBuf::from(String::from_utf8_lossy(name).into_owned()))
+        .collect())
+}
+
 /// Run a git command and return the output
+///
+/// For porcelain the backends don't cover (cloning, fetching, committing,
+/// config), which should behave exactly as if the user had typed it: with
+/// their credential helpers, SSH config and hooks.
 pub fn run_git_command(args: &[&str], cwd: Option<&Path>) -> Result<String> {
     let mut cmd = Command::new("git");
     cmd.args(args);
@@ -1,65 +1,109 @@
 use std::path::Path;
-use std::process::Command;
+use std::time::{Duration, Instant};
 
-use super::worktree::WorktreeStatus;
+use super::backend::GitBackend;
 
-/// Get the status of a worktree (clean, ahead, behind, diverged, modified)
-pub fn get_worktree_status(worktree_path: &Path, branch: &str) -> WorktreeStatus {
-    // First check for uncommitted changes
-    if has_uncommitted_changes(worktree_path) {
-        return WorktreeStatus::Modified;
-    }
+/// How long computing the status of one worktree may take before giving up
+pub const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
 
-    // Then check ahead/behind status
-    get_ahead_behind_status(worktree_path, branch)
+/// Local changes of a worktree and how its branch relates to upstream and
+/// the default branch
+///
+/// Semver-stable, like the types of its fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WorktreeStatus {
+    /// Files with changes in the index
+    pub staged: usize,
+    /// Tracked files with changes not in the index
+    pub unstaged: usize,
+    pub untracked: usize,
+    /// Files with unresolved merge conflicts
+    pub conflicted: usize,
+    /// Stash entries created on this branch
+    pub stashes: usize,
+    pub upstream: Upstream,
+    /// Divergence from the default branch; None on the default branch itself
+    /// or when it can't be resolved
+    pub default_branch: Option<(String, Divergence)>,
+    /// Operation stopped half-way, waiting for the user
+    pub operation: Option<Operation>,
 }
 
-/// Check if the worktree has uncommitted changes
-fn has_uncommitted_changes(worktree_path: &Path) -> bool {
-    Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(worktree_path)
-        .output()
-        .map(|output| {
-            output.status.success() && !String::from_utf8_lossy(&output.stdout).trim().is_empty()
-        })
-        .unwrap_or(false)
+/// Number of commits on each side of two refs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Divergence {
+    pub ahead: usize,
+    pub behind: usize,
 }
 
-/// Get the ahead/behind status relative to the remote
-fn get_ahead_behind_status(worktree_path: &Path, branch: &str) -> WorktreeStatus {
-    let output = Command::new("git")
-        .args([
-            "rev-list",
-            "--left-right",
-            "--count",
-            &format!("origin/{}...HEAD", branch),
-        ])
-        .current_dir(worktree_path)
-        .output();
-
-    match output {
-        Ok(output) if output.status.success() => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let parts: Vec<&str> = stdout.trim().split('\t').collect();
+/// The relationship between a branch and its upstream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Upstream {
+    /// Tracking a remote branch
+    Tracking(Divergence),
+    /// No upstream configured (or detached HEAD)
+    #[default]
+    NotSet,
+    /// Upstream configured but deleted on the remote
+    Gone,
+}
 
-            if parts.len() == 2 {
-                let behind: usize = parts[0].parse().unwrap_or(0);
-                let ahead: usize = parts[1].parse().unwrap_or(0);
+/// An operation in progress in a worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
 
-                match (ahead, behind) {
-                    (0, 0) => WorktreeStatus::Clean,
-                    (a, 0) if a > 0 => WorktreeStatus::Ahead(a),
-                    (0, b) if b > 0 => WorktreeStatus::Behind(b),
-                    (a, b) => WorktreeStatus::Diverged { ahead: a, behind: b },
-                }
-            } else {
-                WorktreeStatus::Clean
-            }
+impl Operation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Operation::Rebase => "rebasing",
+            Operation::Merge => "merging",
+            Operation::CherryPick => "cherry-picking",
+            Operation::Revert => "reverting",
+            Operation::Bisect => "bisecting",
         }
-        _ => {
-            // If we can't get ahead/behind (e.g., no remote tracking), assume clean
-            WorktreeStatus::Clean
+    }
+}
+
+impl WorktreeStatus {
+    /// True if anything in the working tree or index differs from HEAD
+    pub fn has_changes(&self) -> bool {
+        self.staged + self.unstaged + self.untracked + self.conflicted > 0
+    }
+
+    /// True if there are no changes and the branch is in sync with its upstream
+    pub fn is_clean(&self) -> bool {
+        !self.has_changes()
+            && self.operation.is_none()
+            && self.upstream == Upstream::Tracking(Divergence::default())
+    }
+
+    /// Commits that exist only locally: ahead of upstream, or ahead of the
+    /// default branch when the branch was never pushed
+    pub fn unpushed(&self) -> usize {
+        match self.upstream {
+            Upstream::Tracking(d) => d.ahead,
+            Upstream::NotSet => self.default_branch.as_ref().map_or(0, |(_, d)| d.ahead),
+            Upstream::Gone => 0,
         }
     }
 }
+
+/// Get the status of the worktree at `worktree_path`
+///
+/// Returns None if git fails or doesn't answer within `STATUS_TIMEOUT`,
+/// e.g. on a slow network filesystem.
+pub fn get_worktree_status(
+    git: &dyn GitBackend,
+    worktree_path: &Path,
+    branch: Option<&str>,
+) -> Option<WorktreeStatus> {
+    git.status(worktree_path, branch, Instant::now() + STATUS_TIMEOUT)
+}
@@ -1,11 +1,18 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
 
+use crate::config::{layout, EffectiveConfig, Overrides};
 use crate::error::{DevError, Result};
 
-use super::status::get_worktree_status;
+use super::backend::{backend, GitBackend};
+use super::status::{get_worktree_status, WorktreeStatus, STATUS_TIMEOUT};
 
 /// Information about a git worktree
+///
+/// Semver-stable.
 #[derive(Debug, Clone)]
 pub struct WorktreeInfo {
     pub path: PathBuf,
@@ -14,227 +21,248 @@ pub struct WorktreeInfo {
     pub is_current: bool,
     pub is_bare: bool,
     pub is_detached: bool,
-    pub status: WorktreeStatus,
+    /// Computed on first use, see [`WorktreeInfo::status`]
+    status: OnceLock<Option<WorktreeStatus>>,
 }
 
-/// Status of a worktree relative to its remote
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum WorktreeStatus {
-    Clean,
-    Ahead(usize),
-    Behind(usize),
-    Diverged { ahead: usize, behind: usize },
-    Modified,
-    Unknown,
-}
-
-impl WorktreeStatus {
-    pub fn icon(&self) -> &'static str {
-        match self {
-            WorktreeStatus::Clean => "✓",
-            WorktreeStatus::Ahead(_) => "↑",
-            WorktreeStatus::Behind(_) => "↓",
-            WorktreeStatus::Diverged { .. } => "⇅",
-            WorktreeStatus::Modified => "●",
-            WorktreeStatus::Unknown => "?",
+impl WorktreeInfo {
+    /// Describe a worktree as reported by a backend; `is_current` is derived
+    /// from `current_dir`
+    pub(crate) fn new(
+        path: PathBuf,
+        branch: Option<String>,
+        commit: String,
+        is_bare: bool,
+        is_detached: bool,
+        current_dir: Option<&Path>,
+    ) -> Self {
+        let is_current = current_dir.map(|cd| cd.starts_with(&path)).unwrap_or(false);
+
+        Self {
+            path,
+            branch,
+            commit,
+            is_current,
+            is_bare,
+            is_detached,
+            status: OnceLock::new(),
         }
     }
 
-    pub fn label(&self) -> String {
-        match self {
-            WorktreeStatus::Clean => "clean".to_string(),
-            WorktreeStatus::Ahead(n) => format!("ahead {}", n),
-            WorktreeStatus::Behind(n) => format!("behind {}", n),
-            WorktreeStatus::Diverged { ahead, behind } => {
-                format!("diverged +{} -{}", ahead, behind)
-            }
-            WorktreeStatus::Modified => "modified".to_string(),
-            WorktreeStatus::Unknown => "unknown".to_string(),
-        }
+    /// Status of the worktree, computed with the default backend on first
+    /// call unless [`load_statuses`] or `load_status` already did
+    ///
+    /// None when it can't be determined: bare repositories, missing
+    /// directories, or git timing out.
+    pub fn status(&self) -> Option<&WorktreeStatus> {
+        self.load_status(backend())
     }
-}
-
-/// List all worktrees in the repository
-pub fn list_worktrees() -> Result<Vec<WorktreeInfo>> {
-    let output = Command::new("git")
-        .args(["worktree", "list", "--porcelain"])
-        .output()?;
 
-    if !output.status.success() {
-        return Err(DevError::NotGitRepository);
+    /// Status of the worktree, computed with `git` on first call
+    pub fn load_status(&self, git: &dyn GitBackend) -> Option<&WorktreeStatus> {
+        self.status
+            .get_or_init(|| {
+                if self.is_bare {
+                    return None;
+                }
+                get_worktree_status(git, &self.path, self.branch.as_deref())
+            })
+            .as_ref()
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let current_dir = std::env::current_dir().ok();
+/// Upper bound on concurrent status computations
+const MAX_STATUS_THREADS: usize = 8;
+
+/// Compute the status of all worktrees concurrently with `git`
+///
+/// Afterwards `WorktreeInfo::status` returns immediately. Each worktree
+/// is subject to the timeout in `get_worktree_status`.
+pub fn load_statuses(git: &dyn GitBackend, worktrees: &[WorktreeInfo]) {
+    for_each_worktree(worktrees, |_, wt| {
+        wt.load_status(git);
+    });
+}
 
-    parse_worktree_list(&stdout, current_dir.as_deref())
+/// [`load_statuses`], also working out `extra` for each worktree in the
+/// same workers
+///
+/// `extra` runs on a thread of its own while git works, so a worktree it
+/// is slow on (e.g. on a network filesystem) can be given up on: anything
+/// not known within `STATUS_TIMEOUT` of the call is None.
+pub fn load_statuses_with<T, F>(
+    git: &dyn GitBackend,
+    worktrees: &[WorktreeInfo],
+    extra: F,
+) -> Vec<Option<T>>
+where
+    T: Send + 'static,
+    F: Fn(&Path) -> Option<T> + Send + Sync + 'static,
+{
+    let deadline = Instant::now() + STATUS_TIMEOUT;
+    let extra = Arc::new(extra);
+    let results: Vec<Mutex<Option<T>>> = worktrees.iter().map(|_| Mutex::new(None)).collect();
+
+    for_each_worktree(worktrees, |i, wt| {
+        let (tx, rx) = mpsc::channel();
+        let (extra, path) = (Arc::clone(&extra), wt.path.clone());
+        // Left to finish on its own if it misses the deadline
+        thread::spawn(move || {
+            let _ = tx.send(extra(&path));
+        });
+
+        wt.load_status(git);
+
+        let wait = deadline.saturating_duration_since(Instant::now());
+        if let Ok(value) = rx.recv_timeout(wait) {
+            *results[i].lock().unwrap_or_else(|e| e.into_inner()) = value;
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.into_inner().unwrap_or_else(|e| e.into_inner()))
+        .collect()
 }
 
-/// Parse the porcelain output of `git worktree list`
-fn parse_worktree_list(output: &str, current_dir: Option<&Path>) -> Result<Vec<WorktreeInfo>> {
-    let mut worktrees = Vec::new();
-    let mut current: Option<WorktreeInfoBuilder> = None;
-
-    for line in output.lines() {
-        if line.starts_with("worktree ") {
-            // Save previous worktree if exists
-            if let Some(builder) = current.take() {
-                if let Some(wt) = builder.build(current_dir) {
-                    worktrees.push(wt);
+/// Run `job` for each worktree (with its index) on a pool of threads
+fn for_each_worktree(worktrees: &[WorktreeInfo], job: impl Fn(usize, &WorktreeInfo) + Sync) {
+    // Mostly waiting on git subprocesses, so not tied to the CPU count
+    let threads = MAX_STATUS_THREADS.min(worktrees.len());
+
+    let next = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                match worktrees.get(i) {
+                    Some(wt) => job(i, wt),
+                    None => break,
                 }
-            }
-            current = Some(WorktreeInfoBuilder {
-                path: Some(PathBuf::from(line.strip_prefix("worktree ").unwrap())),
-                ..Default::default()
             });
-        } else if let Some(ref mut builder) = current {
-            if line.starts_with("HEAD ") {
-                builder.commit = Some(line.strip_prefix("HEAD ").unwrap().to_string());
-            } else if line.starts_with("branch ") {
-                let branch = line
-                    .strip_prefix("branch refs/heads/")
-                    .unwrap_or(line.strip_prefix("branch ").unwrap_or(""))
-                    .to_string();
-                builder.branch = Some(branch);
-            } else if line == "bare" {
-                builder.is_bare = true;
-            } else if line == "detached" {
-                builder.is_detached = true;
-            }
         }
-    }
-
-    // Don't forget the last worktree
-    if let Some(builder) = current {
-        if let Some(wt) = builder.build(current_dir) {
-            worktrees.push(wt);
-        }
-    }
-
-    Ok(worktrees)
+    });
 }
 
-#[derive(Default)]
-struct WorktreeInfoBuilder {
-    path: Option<PathBuf>,
-    branch: Option<String>,
-    commit: Option<String>,
-    is_bare: bool,
-    is_detached: bool,
+/// Where worktrees for the current repository are placed, based on the
+/// project's or global `worktree_path_template`
+#[derive(Debug, Clone)]
+pub struct WorktreeLayout {
+    pub template: String,
+    pub project: String,
+    pub repo_root: PathBuf,
 }
 
-impl WorktreeInfoBuilder {
-    fn build(self, current_dir: Option<&Path>) -> Option<WorktreeInfo> {
-        let path = self.path?;
-        let commit = self.commit.unwrap_or_default();
-
-        let is_current = current_dir
-            .map(|cd| cd.starts_with(&path))
-            .unwrap_or(false);
-
-        // Get status for non-bare worktrees
-        let status = if self.is_bare || self.is_detached {
-            WorktreeStatus::Unknown
-        } else if let Some(ref branch) = self.branch {
-            get_worktree_status(&path, branch)
-        } else {
-            WorktreeStatus::Unknown
+impl WorktreeLayout {
+    /// Load the layout for the repository containing `repo`
+    pub fn load(config_dir: &Path, git: &dyn GitBackend, repo: &Path) -> Result<Self> {
+        let main = get_main_worktree(git, repo)?
+            .ok_or_else(|| DevError::GitError("Cannot determine main worktree".to_string()))?;
+        let repo_root = main.path;
+
+        let settings = EffectiveConfig::load(config_dir, git, &repo_root, &Overrides::default())?;
+
+        let project = match settings.project {
+            Some(p) => p.name,
+            None => repo_root
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
         };
 
-        Some(WorktreeInfo {
-            path,
-            branch: self.branch,
-            commit,
-            is_current,
-            is_bare: self.is_bare,
-            is_detached: self.is_detached,
-            status,
+        Ok(Self {
+            template: settings.worktree_path_template.value,
+            project,
+            repo_root,
         })
     }
-}
 
-/// Create a new worktree for a branch
-pub fn create_worktree(branch: &str, create_branch: bool) -> Result<PathBuf> {
-    let repo_root = super::get_repository_root(&std::env::current_dir()?)?;
-
-    // Worktree goes in parent directory with branch name
-    let worktree_path = repo_root
-        .parent()
-        .ok_or_else(|| DevError::GitError("Cannot determine parent directory".to_string()))?
-        .join(sanitize_branch_name(branch));
+    /// Path the worktree for a branch should live at
+    pub fn path_for(&self, branch: &str) -> PathBuf {
+        layout::expand_template(&self.template, &self.project, branch, &self.repo_root)
+    }
+}
 
-    if worktree_path.exists() {
-        return Err(DevError::WorktreeDirectoryExists(
-            worktree_path.display().to_string(),
+/// Ensure no other worktree is (or was) registered at the given path
+pub fn check_worktree_path_collision(git: &dyn GitBackend, repo: &Path, path: &Path) -> Result<()> {
+    if let Some(existing) = git
+        .list_worktrees(repo)?
+        .into_iter()
+        .find(|wt| wt.path == path)
+    {
+        return Err(DevError::WorktreePathCollision(
+            path.display().to_string(),
+            existing.branch.unwrap_or_else(|| "detached".to_string()),
         ));
     }
 
-    let mut args = vec!["worktree", "add"];
-    if create_branch {
-        args.push("-b");
-    }
-    args.push(worktree_path.to_str().unwrap());
-    args.push(branch);
-
-    let output = Command::new("git")
-        .args(&args)
-        .current_dir(&repo_root)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(DevError::GitError(format!(
-            "Failed to create worktree: {}",
-            stderr.trim()
-        )));
+    if path.exists() {
+        return Err(DevError::WorktreeDirectoryExists(
+            path.display().to_string(),
+        ));
     }
 
-    Ok(worktree_path)
+    Ok(())
 }
 
-/// Remove a worktree
-pub fn remove_worktree(path: &Path) -> Result<()> {
-    let output = Command::new("git")
-        .args(["worktree", "remove", path.to_str().unwrap()])
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(DevError::GitError(format!(
-            "Failed to remove worktree: {}",
-            stderr.trim()
-        )));
+/// Create a new worktree for a branch at the given path
+pub fn create_worktree(
+    git: &dyn GitBackend,
+    repo: &Path,
+    branch: &str,
+    worktree_path: &Path,
+    create_branch: bool,
+) -> Result<()> {
+    check_worktree_path_collision(git, repo, worktree_path)?;
+
+    if let Some(parent) = worktree_path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
 
-    Ok(())
+    git.add_worktree(repo, branch, worktree_path, create_branch)
 }
 
-/// Prune stale worktree references
-pub fn prune_worktrees() -> Result<()> {
-    let output = Command::new("git")
-        .args(["worktree", "prune"])
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(DevError::GitError(format!(
-            "Failed to prune worktrees: {}",
-            stderr.trim()
-        )));
+/// Move a worktree to a new location
+pub fn move_worktree(git: &dyn GitBackend, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+    check_worktree_path_collision(git, repo, to)?;
+
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
     }
 
-    Ok(())
+    git.move_worktree(repo, from, to)
 }
 
 /// Find a worktree by branch name
-pub fn find_worktree_by_branch(branch: &str) -> Result<Option<WorktreeInfo>> {
-    let worktrees = list_worktrees()?;
+pub fn find_worktree_by_branch(
+    git: &dyn GitBackend,
+    repo: &Path,
+    branch: &str,
+) -> Result<Option<WorktreeInfo>> {
+    let worktrees = git.list_worktrees(repo)?;
     Ok(worktrees
         .into_iter()
         .find(|wt| wt.branch.as_deref() == Some(branch)))
 }
 
-/// Sanitize a branch name for use as a directory name
-fn sanitize_branch_name(branch: &str) -> String {
-    branch.replace('/', "-")
+/// Get the main worktree of the repository containing `repo_path`
+///
+/// Normally this is the first entry in the worktree list. In a bare hub
+/// layout the first entry is the bare repository itself, so the worktree
+/// for the default branch is used instead.
+pub fn get_main_worktree(git: &dyn GitBackend, repo_path: &Path) -> Result<Option<WorktreeInfo>> {
+    let worktrees = git.list_worktrees(repo_path)?;
+
+    if worktrees.first().is_some_and(|wt| wt.is_bare) {
+        let default_branch = git.default_branch(repo_path);
+        if let Some(wt) = worktrees
+            .iter()
+            .find(|wt| wt.branch.as_deref() == Some(default_branch.as_str()))
+        {
+            return Ok(Some(wt.clone()));
+        }
+    }
+
+    Ok(worktrees.into_iter().find(|wt| !wt.is_bare))
 }
@@ -0,0 +1,260 @@
+//! Commands run at points in the life of a worktree or project
+//!
+//! Hooks are configured under `hooks` in the global config, in a project's
+//! config and in a `.dev.json` committed to the repository, and run in that
+//! order; committed hooks only run once the user has approved the file
+//! they are in (see [`TrustedHooks`]). Each runs through the shell with
+//! the worktree as its working directory, the worktree's environment and
+//! `DEV_HOOK` set to the event, and without stdin: hooks can't ask
+//! questions.
+//!
+//! [`TrustedHooks`]: crate::config::TrustedHooks
+
+use std::fmt;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::WorktreeEnv;
+use crate::error::{DevError, Result};
+
+/// How long a hook may run when it doesn't set its own `timeout`
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often a running hook is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A point at which hooks run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// After `dev --create` has made a worktree and installed dependencies
+    PostCreate,
+    /// Before `dev remove` deletes a worktree
+    PreRemove,
+    /// After switching to a worktree
+    PostSwitch,
+    /// After `dev init` has set up a project
+    PostInit,
+    /// Before a worktree is opened in the editor
+    PreOpen,
+}
+
+impl HookEvent {
+    /// Name used as the config key and in `DEV_HOOK`
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PostCreate => "post_create",
+            HookEvent::PreRemove => "pre_remove",
+            HookEvent::PostSwitch => "post_switch",
+            HookEvent::PostInit => "post_init",
+            HookEvent::PreOpen => "pre_open",
+        }
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What a failing hook does to the command that ran it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Stop the command with an error
+    #[default]
+    Abort,
+    /// Print a warning and carry on
+    Warn,
+}
+
+/// A command to run for an event
+///
+/// Written in config either as a command string or as
+/// `{"run": "...", "timeout": 60, "on_failure": "warn"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "HookSpec")]
+pub struct Hook {
+    /// Shell command line
+    pub run: String,
+
+    /// Seconds before the hook is killed (defaults to [`DEFAULT_TIMEOUT`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    pub on_failure: FailurePolicy,
+}
+
+impl Hook {
+    /// How long the hook may run before it is killed
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+}
+
+/// The forms a hook can be written in
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookSpec {
+    Command(String),
+    Full {
+        run: String,
+        #[serde(default)]
+        timeout: Option<u64>,
+        #[serde(default)]
+        on_failure: FailurePolicy,
+    },
+}
+
+impl From<HookSpec> for Hook {
+    fn from(spec: HookSpec) -> Self {
+        match spec {
+            HookSpec::Command(run) => Hook {
+                run,
+                timeout: None,
+                on_failure: FailurePolicy::default(),
+            },
+            HookSpec::Full {
+                run,
+                timeout,
+                on_failure,
+            } => Hook {
+                run,
+                timeout,
+                on_failure,
+            },
+        }
+    }
+}
+
+/// Hooks for each event, as stored under `hooks` in a config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_create: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_remove: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_switch: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_init: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_open: Vec<Hook>,
+}
+
+impl Hooks {
+    /// The hooks for an event, in the order they run
+    pub fn get(&self, event: HookEvent) -> &[Hook] {
+        match event {
+            HookEvent::PostCreate => &self.post_create,
+            HookEvent::PreRemove => &self.pre_remove,
+            HookEvent::PostSwitch => &self.post_switch,
+            HookEvent::PostInit => &self.post_init,
+            HookEvent::PreOpen => &self.pre_open,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.post_create.is_empty()
+            && self.pre_remove.is_empty()
+            && self.post_switch.is_empty()
+            && self.post_init.is_empty()
+            && self.pre_open.is_empty()
+    }
+
+    /// Add `other`'s hooks after these ones
+    pub fn extend(&mut self, other: &Hooks) {
+        self.post_create.extend_from_slice(&other.post_create);
+        self.pre_remove.extend_from_slice(&other.pre_remove);
+        self.post_switch.extend_from_slice(&other.post_switch);
+        self.post_init.extend_from_slice(&other.post_init);
+        self.pre_open.extend_from_slice(&other.pre_open);
+    }
+}
+
+/// Run a hook in `worktree` and wait for it
+///
+/// Its output goes straight to the terminal. Failing to start, exiting
+/// unsuccessfully or outliving its timeout (which kills it) is reported as
+/// [`DevError::HookFailed`]; the caller applies the hook's failure policy.
+pub fn run(event: HookEvent, hook: &Hook, worktree: &Path, env: &WorktreeEnv) -> Result<()> {
+    let failed = |reason: String| DevError::HookFailed(event.to_string(), hook.run.clone(), reason);
+
+    let mut child = env
+        .apply(&mut shell_command(&hook.run))
+        .env("DEV_HOOK", event.name())
+        .current_dir(worktree)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+
+    let deadline = Instant::now() + hook.timeout();
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if status.success() {
+                return Ok(());
+            }
+
+            return Err(failed(match status.code() {
+                Some(code) => format!("exit code {}", code),
+                None => "killed by a signal".to_string(),
+            }));
+        }
+
+        if Instant::now() >= deadline {
+            kill_tree(&mut child);
+            return Err(failed(format!(
+                "timed out after {}s",
+                hook.timeout().as_secs()
+            )));
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// A command running `script` through the platform shell
+///
+/// On Unix it leads a process group of its own, so that everything the
+/// script starts can be killed with it.
+#[cfg(unix)]
+fn shell_command(script: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", script]).process_group(0);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell_command(script: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", script]);
+    cmd
+}
+
+/// Kill a hook along with anything it started, and reap it
+fn kill_tree(child: &mut Child) {
+    if cfg!(unix) {
+        // A negative pid signals the hook's whole process group
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
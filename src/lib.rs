@@ -0,0 +1,36 @@
+//! Git worktree and project management, as used by the `dev` CLI
+//!
+//! The `dev` binary is a presentation layer over this crate. [`api`] holds
+//! the operations behind its commands and returns data instead of printing;
+//! [`config`], [`git`], [`hooks`], [`package_manager`] and [`worktree_files`]
+//! expose the pieces those operations are built from.
+//!
+//! # Stability
+//!
+//! These follow semantic versioning (while at 0.x, breaking changes bump
+//! the minor version):
+//!
+//! - the functions and types in [`api`]
+//! - [`WorktreeInfo`](git::WorktreeInfo)
+//! - [`WorktreeStatus`](git::WorktreeStatus), with [`Divergence`](git::Divergence),
+//!   [`Upstream`](git::Upstream) and [`Operation`](git::Operation)
+//! - [`ProjectConfig`](config::ProjectConfig)
+//! - [`DevError`](error::DevError) and its [`code`](error::DevError::code)s
+//!
+//! Their structs may gain fields and `DevError` may gain variants in any
+//! release: outside this crate they can't be built with struct literals or
+//! matched exhaustively, so that isn't a breaking change.
+//!
+//! Everything else is public because the binary needs it, but may change
+//! in any release: the [`GitBackend`](git::GitBackend) trait, the free
+//! functions in `git`, `config`, `executable`, `hooks`, `package_manager`
+//! and `worktree_files`, and the on-disk config format.
+
+pub mod api;
+pub mod config;
+pub mod error;
+pub mod executable;
+pub mod git;
+pub mod hooks;
+pub mod package_manager;
+pub mod worktree_files;
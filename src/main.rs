@@ -1,44 +1,56 @@
 mod cli;
 mod commands;
-mod config;
+mod context;
 mod editor;
-mod error;
-mod git;
-mod package_manager;
 mod shell;
 mod ui;
 
 use clap::Parser;
 use colored::Colorize;
+use dev_cli::{api, config, error, executable, git, hooks, package_manager, worktree_files};
 
-use crate::cli::{Cli, Commands};
+use crate::cli::{either, Cli, Commands};
+use crate::config::Overrides;
+use crate::context::Context;
 use crate::error::Result;
+use crate::ui::output::OutputFormat;
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("{} {}", "Error:".red(), e);
+    let cli = Cli::parse();
+    let format = cli.format;
+
+    let prompter = ui::prompts::prompter(cli.yes, cli.no_input);
+
+    if let Err(e) = Context::from_env(prompter).and_then(|ctx| run(&ctx, cli)) {
+        match format {
+            OutputFormat::Json => ui::json::print_error(&e),
+            _ => eprintln!("{} {}", "Error:".red(), e),
+        }
         std::process::exit(1);
     }
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+fn run(ctx: &Context, cli: Cli) -> Result<()> {
+    let overrides = cli.overrides();
+    let format = cli.format;
 
     // Handle flags first
     if cli.list {
-        return commands::list::run();
+        return commands::list::run(ctx, format);
     }
 
     if let Some(branch) = cli.create {
-        return commands::create::run(&branch);
+        let track_remote = either(cli.track_remote, cli.diverge);
+        return commands::create::run(ctx, &branch, track_remote, &overrides);
     }
 
     if cli.cleanup {
-        return commands::cleanup::run();
+        let delete_branches = either(cli.delete_branches, cli.keep_branches);
+        return commands::cleanup::run(ctx, cli.older_than, delete_branches);
     }
 
-    if cli.completion {
-        return commands::completion::run();
+    if let Some(shell) = cli.completion {
+        return commands::completion::run(ctx, shell);
     }
 
     // Handle subcommands
@@ -47,17 +59,77 @@ fn run() -> Result<()> {
             Commands::Init {
                 name,
                 clone,
+                bare_layout,
                 existing,
+                devbox,
                 no_devbox,
-            } => commands::init::run(name, clone, existing, no_devbox),
-            Commands::Projects => commands::project::list_projects(),
-            Commands::Config { set, get } => commands::config_cmd::run(set, get),
+            } => commands::init::run(
+                ctx,
+                name,
+                clone,
+                bare_layout,
+                existing,
+                either(devbox, no_devbox),
+                &overrides,
+            ),
+            Commands::Remove {
+                branch,
+                force,
+                delete_branch,
+                keep_branch,
+                delete_remote,
+                keep_remote,
+            } => commands::remove::run(
+                ctx,
+                &branch,
+                force,
+                either(delete_branch, keep_branch),
+                either(delete_remote, keep_remote),
+                &overrides,
+            ),
+            Commands::MigrateWorktrees { dry_run } => commands::migrate::run(ctx, dry_run),
+            Commands::Install {
+                branch,
+                force,
+                packages,
+                all_packages,
+            } => commands::install::run(
+                ctx,
+                branch.as_deref(),
+                force,
+                &packages,
+                all_packages,
+                &overrides,
+            ),
+            Commands::Open { branch, file, wait } => {
+                commands::open::run(ctx, branch.as_deref(), file.as_deref(), wait, &overrides)
+            }
+            Commands::SyncFiles { branch, dry_run } => {
+                commands::sync_files::run(ctx, branch.as_deref(), dry_run, &overrides)
+            }
+            Commands::Env { branch, syntax } => {
+                commands::env::run(ctx, branch.as_deref(), syntax, format)
+            }
+            Commands::ShellInit { shell } => {
+                ctx.out
+                    .println(shell::integration::shell_init_script(shell));
+                Ok(())
+            }
+            Commands::Projects => commands::project::list_projects(ctx, format),
+            Commands::Doctor { fix } => commands::doctor::run(ctx, fix, &overrides, format),
+            Commands::Complete { words } => commands::completion::complete(ctx, &words),
+            Commands::DevboxShell => commands::env::enter_devbox(ctx, &overrides),
+            Commands::Config {
+                set,
+                get,
+                effective,
+            } => commands::config_cmd::run(ctx, set, get, effective, &overrides, format),
         };
     }
 
     // Handle positional target argument
     if let Some(target) = cli.target {
-        return handle_target(&target);
+        return handle_target(ctx, &target, &overrides);
     }
 
     // No arguments - show help
@@ -65,28 +137,28 @@ fn run() -> Result<()> {
     Ok(())
 }
 
-fn handle_target(target: &str) -> Result<()> {
-    // Detect context: are we in a project or global?
-    let context = detect_context();
+fn handle_target(ctx: &Context, target: &str, overrides: &Overrides) -> Result<()> {
+    // Detect location: are we in a project or global?
+    let location = detect_location(ctx);
 
-    match context {
-        Context::Project(_) => {
+    match location {
+        Location::Project(_) => {
             // In a project - treat target as branch name
-            commands::switch::run(target)
+            commands::switch::run(ctx, target, overrides)
         }
-        Context::GitRepo => {
+        Location::GitRepo => {
             // In a git repo but not a registered project - treat as branch
-            commands::switch::run(target)
+            commands::switch::run(ctx, target, overrides)
         }
-        Context::Global => {
+        Location::Global => {
             // Global context - treat target as project name
-            commands::project::run(target)
+            commands::project::run(ctx, target, overrides)
         }
     }
 }
 
 #[derive(Debug)]
-enum Context {
+enum Location {
     /// In a registered project
     Project(String),
     /// In a git repo but not a registered project
@@ -95,25 +167,20 @@ enum Context {
     Global,
 }
 
-fn detect_context() -> Context {
-    let current_dir = match std::env::current_dir() {
-        Ok(dir) => dir,
-        Err(_) => return Context::Global,
-    };
-
+fn detect_location(ctx: &Context) -> Location {
     // Check if we're in a registered project
-    if let Ok(projects) = config::project::ProjectConfig::list_all() {
+    if let Ok(projects) = config::project::ProjectConfig::list_all(&ctx.config_dir) {
         for project in projects {
-            if current_dir.starts_with(&project.path) {
-                return Context::Project(project.name);
+            if ctx.cwd.starts_with(&project.path) {
+                return Location::Project(project.name);
             }
         }
     }
 
     // Check if we're in a git repository
-    if git::is_git_repository(&current_dir) {
-        return Context::GitRepo;
+    if git::is_git_repository(&ctx.cwd) {
+        return Location::GitRepo;
     }
 
-    Context::Global
+    Location::Global
 }
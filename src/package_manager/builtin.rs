@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::PackageManager;
+
+/// A package manager dev knows out of the box
+#[derive(Debug)]
+pub struct Builtin {
+    name: &'static str,
+    ecosystem: &'static str,
+    /// Any of these at the project root means the manager is in use
+    markers: &'static [&'static str],
+    /// Lockfiles the manager writes, newest format first
+    lockfiles: &'static [&'static str],
+    install: &'static [&'static str],
+    version: &'static [&'static str],
+    deps_dir: Option<&'static str>,
+    relocatable: bool,
+    offline_args: &'static [&'static str],
+    /// Argument installing a single workspace member, `{}` being its path
+    member_arg: Option<&'static str>,
+}
+
+/// The built-in package managers, grouped by ecosystem and in lockfile
+/// precedence order within each
+///
+/// Ecosystems are "javascript", "rust", "python", "go", "ruby", "php" and
+/// "elixir". A JavaScript project with no lockfile at all falls back to npm.
+pub static BUILTINS: [Builtin; 12] = [
+    Builtin {
+        name: "bun",
+        ecosystem: "javascript",
+        markers: &["bun.lock", "bun.lockb"],
+        lockfiles: &["bun.lock", "bun.lockb"],
+        install: &["bun", "install"],
+        version: &["bun", "--version"],
+        deps_dir: Some("node_modules"),
+        relocatable: true,
+        offline_args: &[],
+        member_arg: Some("--filter=./{}"),
+    },
+    Builtin {
+        name: "yarn",
+        ecosystem: "javascript",
+        markers: &["yarn.lock"],
+        lockfiles: &["yarn.lock"],
+        install: &["yarn", "install"],
+        version: &["yarn", "--version"],
+        deps_dir: Some("node_modules"),
+        relocatable: true,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "pnpm",
+        ecosystem: "javascript",
+        markers: &["pnpm-lock.yaml"],
+        lockfiles: &["pnpm-lock.yaml"],
+        install: &["pnpm", "install"],
+        version: &["pnpm", "--version"],
+        deps_dir: Some("node_modules"),
+        // node_modules links into pnpm's store, which is shared already
+        relocatable: false,
+        offline_args: &["--prefer-offline"],
+        member_arg: Some("--filter=./{}"),
+    },
+    Builtin {
+        name: "npm",
+        ecosystem: "javascript",
+        markers: &["package-lock.json", "package.json"],
+        lockfiles: &["package-lock.json"],
+        install: &["npm", "install"],
+        version: &["npm", "--version"],
+        deps_dir: Some("node_modules"),
+        relocatable: true,
+        offline_args: &[],
+        member_arg: Some("--workspace={}"),
+    },
+    Builtin {
+        name: "cargo",
+        ecosystem: "rust",
+        markers: &["Cargo.toml"],
+        lockfiles: &["Cargo.lock"],
+        install: &["cargo", "fetch"],
+        version: &["cargo", "--version"],
+        deps_dir: None,
+        relocatable: false,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "uv",
+        ecosystem: "python",
+        markers: &["uv.lock"],
+        lockfiles: &["uv.lock"],
+        install: &["uv", "sync"],
+        version: &["uv", "--version"],
+        // Virtualenvs hold absolute paths, so can't be shared between worktrees
+        deps_dir: Some(".venv"),
+        relocatable: false,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "poetry",
+        ecosystem: "python",
+        markers: &["poetry.lock"],
+        lockfiles: &["poetry.lock"],
+        install: &["poetry", "install"],
+        version: &["poetry", "--version"],
+        deps_dir: None,
+        relocatable: false,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "pip",
+        ecosystem: "python",
+        markers: &["requirements.txt"],
+        lockfiles: &["requirements.txt"],
+        install: &["pip", "install", "-r", "requirements.txt"],
+        version: &["pip", "--version"],
+        deps_dir: None,
+        relocatable: false,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "go",
+        ecosystem: "go",
+        markers: &["go.mod"],
+        lockfiles: &["go.sum"],
+        install: &["go", "mod", "download"],
+        version: &["go", "version"],
+        deps_dir: None,
+        relocatable: false,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "bundler",
+        ecosystem: "ruby",
+        markers: &["Gemfile"],
+        lockfiles: &["Gemfile.lock"],
+        install: &["bundle", "install"],
+        version: &["bundle", "--version"],
+        deps_dir: None,
+        relocatable: false,
+        offline_args: &["--prefer-local"],
+        member_arg: None,
+    },
+    Builtin {
+        name: "composer",
+        ecosystem: "php",
+        markers: &["composer.json"],
+        lockfiles: &["composer.lock"],
+        install: &["composer", "install"],
+        version: &["composer", "--version"],
+        deps_dir: Some("vendor"),
+        relocatable: true,
+        offline_args: &[],
+        member_arg: None,
+    },
+    Builtin {
+        name: "mix",
+        ecosystem: "elixir",
+        markers: &["mix.exs"],
+        lockfiles: &["mix.lock"],
+        install: &["mix", "deps.get"],
+        version: &["mix", "--version"],
+        deps_dir: Some("deps"),
+        relocatable: true,
+        offline_args: &[],
+        member_arg: None,
+    },
+];
+
+impl PackageManager for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn ecosystem(&self) -> &str {
+        self.ecosystem
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        self.markers.iter().any(|marker| path.join(marker).exists())
+    }
+
+    fn lockfile(&self, path: &Path) -> Option<PathBuf> {
+        self.lockfiles
+            .iter()
+            .map(|lockfile| path.join(lockfile))
+            .find(|lockfile| lockfile.exists())
+    }
+
+    fn install_command(&self) -> Command {
+        command(self.install)
+    }
+
+    fn version_command(&self) -> Option<Command> {
+        Some(command(self.version))
+    }
+
+    fn deps_dir(&self) -> Option<&str> {
+        self.deps_dir
+    }
+
+    fn relocatable(&self) -> bool {
+        self.relocatable
+    }
+
+    fn offline_args(&self) -> &[&str] {
+        self.offline_args
+    }
+
+    fn member_args(&self, members: &[PathBuf]) -> Vec<String> {
+        match self.member_arg {
+            Some(arg) => members
+                .iter()
+                .map(|member| arg.replace("{}", &member.to_string_lossy()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn command(argv: &[&str]) -> Command {
+    let mut cmd = Command::new(argv[0]);
+    cmd.args(&argv[1..]);
+    cmd
+}
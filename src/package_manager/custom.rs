@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use super::PackageManager;
+
+/// A package manager defined under `package_managers` in config
+///
+/// Written as `{"name": "rebar", "marker": "rebar.config", "install":
+/// "rebar3 get-deps", "lockfile": "rebar.lock"}`. The install command runs
+/// through the shell. Giving it the `ecosystem` of a built-in manager (say
+/// "javascript") uses it in that one's place; otherwise it is an ecosystem
+/// of its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomPackageManager {
+    pub name: String,
+
+    /// File at the project root that means the manager is in use
+    pub marker: String,
+
+    /// Shell command line that installs the dependencies
+    pub install: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfile: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecosystem: Option<String>,
+}
+
+impl PackageManager for CustomPackageManager {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ecosystem(&self) -> &str {
+        self.ecosystem.as_deref().unwrap_or(&self.name)
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        path.join(&self.marker).exists()
+    }
+
+    fn lockfile(&self, path: &Path) -> Option<PathBuf> {
+        let lockfile = path.join(self.lockfile.as_ref()?);
+        lockfile.exists().then_some(lockfile)
+    }
+
+    fn install_command(&self) -> Command {
+        shell_command(&self.install)
+    }
+
+    /// The first word of the install command rather than the shell
+    fn executable(&self) -> String {
+        self.install
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(script: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", script]);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell_command(script: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", script]);
+    cmd
+}
@@ -1,99 +1,226 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use colored::Colorize;
+use sha2::{Digest, Sha256};
 
+use crate::config::WorktreeEnv;
 use crate::error::Result;
 
-/// Detected package manager type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PackageManager {
-    Npm,
-    Yarn,
-    Pnpm,
-}
+mod builtin;
+mod custom;
+mod package;
+mod record;
+mod workspace;
 
-impl PackageManager {
-    pub fn install_command(&self) -> &'static str {
-        match self {
-            PackageManager::Npm => "npm install",
-            PackageManager::Yarn => "yarn install",
-            PackageManager::Pnpm => "pnpm install",
-        }
+pub use builtin::{Builtin, BUILTINS};
+pub use custom::CustomPackageManager;
+pub use package::{discover, plan, select_packages, selected_packages, Package, Planned};
+pub use record::{deps_status, DepsStatus, InstallRecord};
+pub use workspace::{detect_workspace, Workspace, WorkspaceKind};
+
+/// Something that installs a project's dependencies
+///
+/// Package managers belong to an ecosystem ("javascript", "rust", ...), and a
+/// project uses at most one per ecosystem: a Rust backend with a JavaScript
+/// frontend gets cargo and one of the JavaScript managers.
+pub trait PackageManager: Send + Sync {
+    /// Name shown in output and kept in install records
+    fn name(&self) -> &str;
+
+    /// Family of alternatives this manager is one of
+    fn ecosystem(&self) -> &str;
+
+    /// Whether the project at `path` uses this manager
+    fn detect(&self, path: &Path) -> bool;
+
+    /// The project's lockfile, if it has one
+    fn lockfile(&self, path: &Path) -> Option<PathBuf>;
+
+    /// Command that installs the dependencies when run in the project
+    fn install_command(&self) -> Command;
+
+    /// Command printing the manager's version, which is recorded with each
+    /// install so that upgrading the manager marks dependencies stale
+    fn version_command(&self) -> Option<Command> {
+        None
+    }
+
+    /// Program the manager runs, looked for on PATH to tell whether it's
+    /// installed
+    fn executable(&self) -> String {
+        self.install_command()
+            .get_program()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Directory inside the project the dependencies are installed into,
+    /// for managers that don't keep them in a global cache
+    fn deps_dir(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether `deps_dir` still works when copied into another worktree
+    fn relocatable(&self) -> bool {
+        false
+    }
+
+    /// Install arguments that use packages already cached locally without
+    /// checking the registry
+    fn offline_args(&self) -> &[&str] {
+        &[]
     }
 
-    pub fn name(&self) -> &'static str {
-        match self {
-            PackageManager::Npm => "npm",
-            PackageManager::Yarn => "yarn",
-            PackageManager::Pnpm => "pnpm",
+    /// Install arguments limiting a workspace install to some of its
+    /// members, given relative to the workspace root; empty when the
+    /// manager always installs the whole workspace
+    fn member_args(&self, members: &[PathBuf]) -> Vec<String> {
+        let _ = members;
+        Vec::new()
+    }
+}
+
+/// The package managers a project uses, at most one per ecosystem
+///
+/// Those in `custom` are checked before the built-in ones, each in order,
+/// and the first detected in an ecosystem wins.
+pub fn detect<'a>(path: &Path, custom: &'a [CustomPackageManager]) -> Vec<&'a dyn PackageManager> {
+    let candidates = custom
+        .iter()
+        .map(|pm| pm as &dyn PackageManager)
+        .chain(BUILTINS.iter().map(|pm| pm as &dyn PackageManager));
+
+    let mut found: Vec<&dyn PackageManager> = Vec::new();
+    for pm in candidates {
+        if !found.iter().any(|f| f.ecosystem() == pm.ecosystem()) && pm.detect(path) {
+            found.push(pm);
         }
     }
+
+    found
 }
 
-/// Detect the package manager used in a project
-pub fn detect_package_manager(path: &Path) -> Option<PackageManager> {
-    if path.join("yarn.lock").exists() {
-        Some(PackageManager::Yarn)
-    } else if path.join("pnpm-lock.yaml").exists() {
-        Some(PackageManager::Pnpm)
-    } else if path.join("package-lock.json").exists() {
-        Some(PackageManager::Npm)
-    } else if path.join("package.json").exists() {
-        // Has package.json but no lock file - default to npm
-        Some(PackageManager::Npm)
-    } else {
-        None
+fn remove_deps_dir(dir: &Path) -> Result<bool> {
+    if !dir.exists() {
+        return Ok(false);
     }
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(true)
 }
 
-/// Check if package.json exists
-pub fn has_package_json(path: &Path) -> bool {
-    path.join("package.json").exists()
+/// Install a package's dependencies, returning whether it succeeded
+///
+/// The package manager's own output goes straight to the terminal. With
+/// `prefer_offline`, managers that can use packages already in their cache
+/// or store do so without checking the registry.
+pub fn install(planned: &Planned, env: &WorktreeEnv, prefer_offline: bool) -> Result<bool> {
+    let package = &planned.package;
+    let pm = package.manager;
+    let mut cmd = pm.install_command();
+
+    if !planned.members.is_empty() {
+        let members: Vec<PathBuf> = planned
+            .members
+            .iter()
+            .filter_map(|member| member.strip_prefix(&package.path).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        cmd.args(pm.member_args(&members));
+    }
+
+    if prefer_offline {
+        cmd.args(pm.offline_args());
+    }
+
+    let status = env.apply(&mut cmd).current_dir(package.dir()).status()?;
+
+    if status.success() {
+        InstallRecord::current(package).save(&package.worktree)?;
+    }
+
+    Ok(status.success())
 }
 
-/// Check if node_modules exists
-pub fn has_node_modules(path: &Path) -> bool {
-    path.join("node_modules").exists()
+/// SHA-256 of a project's lockfile, or None without one
+pub fn lockfile_hash(pm: &dyn PackageManager, path: &Path) -> Option<String> {
+    let contents = std::fs::read(pm.lockfile(path)?).ok()?;
+    Some(format!("{:x}", Sha256::digest(contents)))
 }
 
-/// Install dependencies for a project
-pub fn install_dependencies(path: &Path) -> Result<bool> {
-    let pm = match detect_package_manager(path) {
-        Some(pm) => pm,
-        None => {
-            // No package.json found
-            return Ok(false);
-        }
+/// Give a package the dependencies of the same package in the first of the
+/// `siblings` worktrees installed from an identical lockfile by the same
+/// package manager version, returning which sibling was used
+///
+/// Only managers with a relocatable `deps_dir` can share one, and never for
+/// a workspace, whose members can hold dependencies of their own. Files are
+/// reflinked where the filesystem supports it and hardlinked otherwise, so
+/// this takes seconds and little space; symlinks are kept as they are.
+/// Returns None, changing nothing, when no sibling matches.
+pub fn reuse_dependencies(package: &Package, siblings: &[PathBuf]) -> Result<Option<PathBuf>> {
+    let pm = package.manager;
+    let dir = match pm.deps_dir() {
+        Some(dir) if pm.relocatable() && package.workspace.is_none() => dir,
+        _ => return Ok(None),
     };
 
-    println!(
-        "{}",
-        format!("Installing dependencies using {}...", pm.name()).blue()
-    );
+    // Without a lockfile, identical manifests can still resolve differently
+    let wanted = InstallRecord::current(package);
+    if wanted.lockfile_hash.is_none() {
+        return Ok(None);
+    }
+
+    let source = siblings.iter().find(|sibling| {
+        sibling.as_path() != package.worktree
+            && sibling.join(&package.path).join(dir).exists()
+            && InstallRecord::load(sibling, &package.path, pm.name()).as_ref() == Some(&wanted)
+    });
 
-    let (cmd, args) = match pm {
-        PackageManager::Npm => ("npm", vec!["install"]),
-        PackageManager::Yarn => ("yarn", vec!["install"]),
-        PackageManager::Pnpm => ("pnpm", vec!["install"]),
+    let source = match source {
+        Some(source) => source,
+        None => return Ok(None),
     };
 
-    let status = Command::new(cmd)
-        .args(&args)
-        .current_dir(path)
-        .status()?;
+    // Start from nothing so no stale packages are left behind
+    let target = package.dir().join(dir);
+    remove_deps_dir(&target)?;
+    link_tree(&source.join(&package.path).join(dir), &target)?;
+    wanted.save(&package.worktree)?;
 
-    if status.success() {
-        println!(
-            "{}",
-            format!("Dependencies installed successfully using {}", pm.name()).green()
-        );
-        Ok(true)
-    } else {
-        println!(
-            "{}",
-            format!("Failed to install dependencies with {}", pm.name()).red()
-        );
-        Ok(false)
+    Ok(Some(source.clone()))
+}
+
+/// Recreate the directory tree `from` at `to`, sharing file contents
+fn link_tree(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let (src, dst) = (entry.path(), to.join(entry.file_name()));
+
+        if file_type.is_symlink() {
+            symlink(&std::fs::read_link(&src)?, &dst)?;
+        } else if file_type.is_dir() {
+            link_tree(&src, &dst)?;
+        } else if reflink_copy::reflink(&src, &dst).is_err() {
+            std::fs::hard_link(&src, &dst)?;
+        }
     }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> Result<()> {
+    // Package managers on Windows link packages as junctions, which
+    // `symlink_dir` recreates well enough for resolution
+    std::os::windows::fs::symlink_dir(target, link)?;
+    Ok(())
 }
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use super::workspace::{detect_workspace, Workspace};
+use super::{detect, CustomPackageManager, PackageManager};
+use crate::error::{DevError, Result};
+use crate::git;
+
+/// Name of the package selection in a worktree's git dir
+const SELECTION_FILE: &str = "dev-packages.json";
+
+/// How far below the worktree root packages are looked for
+const MAX_DEPTH: usize = 4;
+
+/// Directories never searched for packages: installed dependencies and
+/// build output
+const SKIPPED_DIRS: [&str; 7] = [
+    "node_modules",
+    "target",
+    "vendor",
+    "deps",
+    "_build",
+    "dist",
+    "build",
+];
+
+/// A directory of a worktree that a package manager installs dependencies in
+#[derive(Clone)]
+pub struct Package<'a> {
+    pub worktree: PathBuf,
+    /// Relative to the worktree root; empty for the root itself
+    pub path: PathBuf,
+    pub manager: &'a dyn PackageManager,
+    /// The workspace this package is the root of, if it is one
+    pub workspace: Option<Workspace>,
+}
+
+impl Package<'_> {
+    /// Absolute path of the package's directory
+    pub fn dir(&self) -> PathBuf {
+        self.worktree.join(&self.path)
+    }
+
+    /// The package's path for display, `.` for the worktree root
+    pub fn display_path(&self) -> String {
+        if self.path.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            self.path.to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// A package to install and, when it is a workspace, which members to
+/// install it for
+#[derive(Clone)]
+pub struct Planned<'a> {
+    pub package: Package<'a>,
+    /// Members to limit the install to, relative to the worktree root;
+    /// empty for the whole package
+    pub members: Vec<PathBuf>,
+}
+
+/// Every package in a worktree, at most one per ecosystem in each directory
+///
+/// Below the root, only a directory with a lockfile of its own is a package;
+/// a bare manifest there is usually a test fixture or an example. Directories
+/// that are members of a workspace further up are left out, as installing
+/// the workspace installs them too. Hidden directories, nested
+/// repositories and those holding dependencies or build output aren't
+/// searched.
+pub fn discover<'a>(worktree: &Path, custom: &'a [CustomPackageManager]) -> Vec<Package<'a>> {
+    let mut found = Vec::new();
+    walk(worktree, Path::new(""), 0, custom, &mut found);
+
+    let covered = |package: &Package| {
+        found.iter().any(|root: &Package| {
+            root.manager.ecosystem() == package.manager.ecosystem()
+                && root.workspace.as_ref().is_some_and(|workspace| {
+                    package
+                        .path
+                        .strip_prefix(&root.path)
+                        .is_ok_and(|relative| workspace.contains(relative))
+                })
+        })
+    };
+
+    let keep: Vec<bool> = found.iter().map(|package| !covered(package)).collect();
+    found
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(package, keep)| keep.then_some(package))
+        .collect()
+}
+
+fn walk<'a>(
+    worktree: &Path,
+    relative: &Path,
+    depth: usize,
+    custom: &'a [CustomPackageManager],
+    found: &mut Vec<Package<'a>>,
+) {
+    let dir = worktree.join(relative);
+
+    for manager in detect(&dir, custom) {
+        if depth > 0 && manager.lockfile(&dir).is_none() {
+            continue;
+        }
+
+        found.push(Package {
+            worktree: worktree.to_path_buf(),
+            path: relative.to_path_buf(),
+            manager,
+            workspace: detect_workspace(&dir, manager.ecosystem()),
+        });
+    }
+
+    if depth == MAX_DEPTH {
+        return;
+    }
+
+    let mut children: Vec<_> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|entry| entry.file_name())
+            .collect(),
+        Err(_) => return,
+    };
+    children.sort();
+
+    for name in children {
+        let skipped = name.to_string_lossy().starts_with('.')
+            || SKIPPED_DIRS.iter().any(|skipped| name == *skipped)
+            || dir.join(&name).join(".git").exists();
+
+        if !skipped {
+            walk(worktree, &relative.join(&name), depth + 1, custom, found);
+        }
+    }
+}
+
+/// The packages of a worktree to install
+///
+/// With no `patterns` that's all of them. Otherwise it's those whose path
+/// (`.` for the root) matches a glob pattern, and workspaces with a member
+/// that does, installed for just the matching members where the package
+/// manager can do that.
+pub fn plan<'a>(
+    worktree: &Path,
+    custom: &'a [CustomPackageManager],
+    patterns: &[String],
+) -> Result<Vec<Planned<'a>>> {
+    let patterns = compile(patterns)?;
+    let matches = |path: &Path| patterns.iter().any(|pattern| pattern.matches_path(path));
+
+    let mut planned = Vec::new();
+
+    for package in discover(worktree, custom) {
+        if patterns.is_empty() || matches(Path::new(&package.display_path())) {
+            planned.push(Planned {
+                package,
+                members: Vec::new(),
+            });
+            continue;
+        }
+
+        let members: Vec<PathBuf> =
+            match package.workspace.as_ref().and_then(|w| w.members.as_ref()) {
+                Some(members) => members
+                    .iter()
+                    .map(|member| package.path.join(member))
+                    .filter(|member| matches(member))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+        if !members.is_empty() {
+            planned.push(Planned { package, members });
+        }
+    }
+
+    Ok(planned)
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<glob::Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern.trim_start_matches("./").trim_end_matches('/')).map_err(
+                |e| DevError::ConfigError(format!("Invalid package pattern '{}': {}", pattern, e)),
+            )
+        })
+        .collect()
+}
+
+/// The packages chosen for a worktree with [`select_packages`], if any
+pub fn selected_packages(worktree: &Path) -> Option<Vec<String>> {
+    let contents = std::fs::read_to_string(git::git_dir(worktree)?.join(SELECTION_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Install only packages matching `patterns` in a worktree from now on, or
+/// all of them again when `patterns` is empty
+///
+/// Kept in the worktree's git dir alongside the install records.
+pub fn select_packages(worktree: &Path, patterns: &[String]) -> Result<()> {
+    compile(patterns)?;
+
+    let dir = git::git_dir(worktree).ok_or(DevError::NotGitRepository)?;
+    let path = dir.join(SELECTION_FILE);
+
+    if patterns.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    } else {
+        std::fs::write(path, serde_json::to_string_pretty(patterns)?)?;
+    }
+
+    Ok(())
+}
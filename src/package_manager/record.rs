@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::{lockfile_hash, plan, CustomPackageManager, Package, PackageManager};
+use crate::error::Result;
+use crate::git;
+
+/// Name of the record in a worktree's git dir
+const RECORD_FILE: &str = "dev-install.json";
+
+/// What a package manager last installed a package's dependencies from
+///
+/// Kept, one per package and package manager, in the worktree's git dir, so
+/// it never shows up in `git status` and goes away with the worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallRecord {
+    /// The package's directory relative to the worktree root
+    #[serde(default, skip_serializing_if = "is_root")]
+    pub package: PathBuf,
+    pub package_manager: String,
+    /// The package manager's version, if it could be asked
+    pub version: Option<String>,
+    /// SHA-256 of the lockfile, if there is one
+    pub lockfile_hash: Option<String>,
+}
+
+impl InstallRecord {
+    /// What installing a package would record now
+    pub fn current(package: &Package) -> Self {
+        let (pm, dir) = (package.manager, package.dir());
+        Self {
+            package: package.path.clone(),
+            package_manager: pm.name().to_string(),
+            version: version(pm, &dir),
+            lockfile_hash: lockfile_hash(pm, &dir),
+        }
+    }
+
+    /// The record of a package manager's last install of the package at
+    /// `package` (relative to the root) in a worktree, if any
+    pub fn load(worktree: &Path, package: &Path, package_manager: &str) -> Option<Self> {
+        load_all(worktree)
+            .into_iter()
+            .find(|record| record.package == package && record.package_manager == package_manager)
+    }
+
+    /// Store the record in a worktree's git dir, replacing the previous one
+    /// for the same package and package manager (outside a repository there
+    /// is nowhere to keep it, so nothing is written)
+    pub fn save(&self, worktree: &Path) -> Result<()> {
+        let dir = match git::git_dir(worktree) {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+
+        let mut records = load_all(worktree);
+        records.retain(|record| {
+            record.package != self.package || record.package_manager != self.package_manager
+        });
+        records.push(self.clone());
+
+        std::fs::write(
+            dir.join(RECORD_FILE),
+            serde_json::to_string_pretty(&records)?,
+        )?;
+        Ok(())
+    }
+}
+
+fn is_root(package: &Path) -> bool {
+    package.as_os_str().is_empty()
+}
+
+/// Every install record in a worktree
+fn load_all(worktree: &Path) -> Vec<InstallRecord> {
+    git::git_dir(worktree)
+        .and_then(|dir| std::fs::read_to_string(dir.join(RECORD_FILE)).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a worktree's dependencies match its lockfile
+///
+/// Ordered by how much attention the dependencies need, so the status of a
+/// worktree with several package managers is the greatest of theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DepsStatus {
+    /// Installed from the current lockfile with the current package manager
+    UpToDate,
+    /// Installed, but not by dev, so there's nothing to compare against
+    Unrecorded,
+    /// Nothing installed
+    Missing,
+    /// The lockfile or package manager changed since the last install
+    Stale,
+}
+
+impl DepsStatus {
+    /// Whether an install is needed to bring the dependencies up to date
+    pub fn needs_install(&self) -> bool {
+        matches!(self, DepsStatus::Stale | DepsStatus::Missing)
+    }
+}
+
+/// Compare the dependencies of a worktree's packages, those matching
+/// `patterns` if any are given, with their lockfiles; None if it has no
+/// packages to install
+pub fn deps_status(
+    worktree: &Path,
+    custom: &[CustomPackageManager],
+    patterns: &[String],
+) -> Result<Option<DepsStatus>> {
+    Ok(plan(worktree, custom, patterns)?
+        .iter()
+        .map(|planned| planned.package.status())
+        .max())
+}
+
+impl Package<'_> {
+    /// Compare the package's dependencies with its lockfile
+    pub fn status(&self) -> DepsStatus {
+        let pm = self.manager;
+        let record = InstallRecord::load(&self.worktree, &self.path, pm.name());
+
+        // Without a directory of its own to look at, a manager counts as
+        // having installed only what dev saw it install
+        let installed = match pm.deps_dir() {
+            Some(dir) => self.dir().join(dir).exists(),
+            None => record.is_some(),
+        };
+        if !installed {
+            return DepsStatus::Missing;
+        }
+
+        match record {
+            None => DepsStatus::Unrecorded,
+            Some(record) if record == InstallRecord::current(self) => DepsStatus::UpToDate,
+            Some(_) => DepsStatus::Stale,
+        }
+    }
+}
+
+/// The package manager's version, asked once per package manager per process
+fn version(pm: &dyn PackageManager, path: &Path) -> Option<String> {
+    static VERSIONS: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+    let mut versions = VERSIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    versions
+        .entry(pm.name().to_string())
+        .or_insert_with(|| {
+            let output = pm
+                .version_command()?
+                .current_dir(path)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .clone()
+}
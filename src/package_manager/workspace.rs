@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// A tool whose config makes a directory the root of a workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceKind {
+    /// `pnpm-workspace.yaml`
+    Pnpm,
+    /// `workspaces` in `package.json`, as used by npm, yarn and bun
+    PackageJson,
+    /// `turbo.json`
+    Turbo,
+    /// `nx.json`
+    Nx,
+    /// `[workspace]` in `Cargo.toml`
+    Cargo,
+}
+
+impl WorkspaceKind {
+    /// File the workspace is defined in
+    pub fn file(&self) -> &'static str {
+        match self {
+            WorkspaceKind::Pnpm => "pnpm-workspace.yaml",
+            WorkspaceKind::PackageJson => "package.json",
+            WorkspaceKind::Turbo => "turbo.json",
+            WorkspaceKind::Nx => "nx.json",
+            WorkspaceKind::Cargo => "Cargo.toml",
+        }
+    }
+}
+
+/// A directory whose package manager installs for the packages below it too
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub kinds: Vec<WorkspaceKind>,
+    /// Member directories, relative to the workspace root; None when the
+    /// workspace takes in every package below it, as turbo and nx do
+    /// without a member list of the package manager's
+    pub members: Option<Vec<PathBuf>>,
+}
+
+impl Workspace {
+    /// Whether `path`, relative to the workspace root, is a member
+    pub fn contains(&self, path: &Path) -> bool {
+        match &self.members {
+            Some(members) => members.iter().any(|member| member == path),
+            None => !path.as_os_str().is_empty(),
+        }
+    }
+}
+
+/// The workspace of an ecosystem rooted at `dir`, if there is one
+pub fn detect_workspace(dir: &Path, ecosystem: &str) -> Option<Workspace> {
+    match ecosystem {
+        "javascript" => javascript_workspace(dir),
+        "rust" => cargo_workspace(dir),
+        _ => None,
+    }
+}
+
+fn javascript_workspace(dir: &Path) -> Option<Workspace> {
+    let mut kinds = Vec::new();
+    let mut patterns = Vec::new();
+
+    if let Some(packages) = pnpm_packages(dir) {
+        kinds.push(WorkspaceKind::Pnpm);
+        patterns.extend(packages);
+    }
+
+    if let Some(packages) = package_json_workspaces(dir) {
+        kinds.push(WorkspaceKind::PackageJson);
+        patterns.extend(packages);
+    }
+
+    for kind in [WorkspaceKind::Turbo, WorkspaceKind::Nx] {
+        if dir.join(kind.file()).exists() {
+            kinds.push(kind);
+        }
+    }
+
+    if kinds.is_empty() {
+        return None;
+    }
+
+    // turbo.json and nx.json don't list members of their own
+    let listed = kinds
+        .iter()
+        .any(|kind| matches!(kind, WorkspaceKind::Pnpm | WorkspaceKind::PackageJson));
+    let members = listed.then(|| expand_members(dir, &patterns, &[]));
+
+    Some(Workspace { kinds, members })
+}
+
+fn cargo_workspace(dir: &Path) -> Option<Workspace> {
+    #[derive(Deserialize)]
+    struct Manifest {
+        workspace: Option<CargoWorkspace>,
+    }
+
+    #[derive(Deserialize)]
+    struct CargoWorkspace {
+        #[serde(default)]
+        members: Vec<String>,
+        #[serde(default)]
+        exclude: Vec<String>,
+    }
+
+    let contents = std::fs::read_to_string(dir.join(WorkspaceKind::Cargo.file())).ok()?;
+    let workspace = toml::from_str::<Manifest>(&contents).ok()?.workspace?;
+
+    Some(Workspace {
+        kinds: vec![WorkspaceKind::Cargo],
+        members: Some(expand_members(dir, &workspace.members, &workspace.exclude)),
+    })
+}
+
+/// Member patterns from `pnpm-workspace.yaml`
+fn pnpm_packages(dir: &Path) -> Option<Vec<String>> {
+    #[derive(Deserialize)]
+    struct PnpmWorkspace {
+        #[serde(default)]
+        packages: Vec<String>,
+    }
+
+    let contents = std::fs::read_to_string(dir.join(WorkspaceKind::Pnpm.file())).ok()?;
+    let workspace: PnpmWorkspace = serde_yaml::from_str(&contents).ok()?;
+    Some(workspace.packages)
+}
+
+/// Member patterns from `workspaces` in `package.json`, written either as
+/// a list or as `{"packages": [...]}`
+fn package_json_workspaces(dir: &Path) -> Option<Vec<String>> {
+    #[derive(Deserialize)]
+    struct PackageJson {
+        workspaces: Option<Workspaces>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Workspaces {
+        List(Vec<String>),
+        Object {
+            #[serde(default)]
+            packages: Vec<String>,
+        },
+    }
+
+    let contents = std::fs::read_to_string(dir.join(WorkspaceKind::PackageJson.file())).ok()?;
+    match serde_json::from_str::<PackageJson>(&contents)
+        .ok()?
+        .workspaces?
+    {
+        Workspaces::List(packages) | Workspaces::Object { packages } => Some(packages),
+    }
+}
+
+/// Directories under `root` matched by the member patterns, relative to it
+///
+/// Patterns starting with `!`, and those in `exclude`, take matches back
+/// out.
+fn expand_members(root: &Path, patterns: &[String], exclude: &[String]) -> Vec<PathBuf> {
+    let escaped = glob::Pattern::escape(&root.to_string_lossy());
+
+    let mut excluded: Vec<glob::Pattern> = exclude
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(pattern.trim_start_matches("./")).ok())
+        .collect();
+    let mut members = Vec::new();
+
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            excluded.extend(glob::Pattern::new(negated.trim_start_matches("./")).ok());
+            continue;
+        }
+
+        let full = format!("{}/{}", escaped, pattern.trim_start_matches("./"));
+        let paths = match glob::glob(&full) {
+            Ok(paths) => paths,
+            Err(_) => continue,
+        };
+
+        for path in paths.flatten().filter(|path| path.is_dir()) {
+            if let Ok(relative) = path.strip_prefix(root) {
+                if !members.iter().any(|m| m == relative) {
+                    members.push(relative.to_path_buf());
+                }
+            }
+        }
+    }
+
+    members.retain(|member| {
+        !excluded.iter().any(|pattern| pattern.matches_path(member))
+            && !member.components().any(|c| c.as_os_str() == "node_modules")
+    });
+    members
+}
@@ -1,67 +1,321 @@
-use clap::CommandFactory;
-use clap_complete::{generate, Shell};
+use clap::{Arg, Command, CommandFactory};
 
 use crate::cli::Cli;
-use crate::config::global::Shell as ConfigShell;
-use crate::error::Result;
-
-/// Generate shell completion script
-pub fn generate_completion(shell: ConfigShell) -> Result<()> {
-    let mut cmd = Cli::command();
-    let shell = match shell {
-        ConfigShell::Zsh => Shell::Zsh,
-        ConfigShell::Bash => Shell::Bash,
-        ConfigShell::Fish => Shell::Fish,
+use crate::config::global::Shell;
+use crate::config::{GlobalConfig, ProjectConfig};
+use crate::context::Context;
+use crate::editor::BUILTIN_EDITORS;
+use crate::git::is_git_repository;
+
+/// A completion candidate with an optional description
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub value: String,
+    pub description: String,
+}
+
+impl Candidate {
+    fn new(value: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// The completion script for a shell
+///
+/// The scripts are thin wrappers around `dev __complete`, so completions
+/// always follow the CLI definition in `cli.rs`.
+pub fn completion_script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => BASH_COMPLETION,
+        Shell::Zsh => ZSH_COMPLETION,
+        Shell::Fish => FISH_COMPLETION,
+        Shell::PowerShell => POWERSHELL_COMPLETION,
+    }
+}
+
+/// Compute candidates for the words typed after `dev`
+///
+/// The last word is the one being completed (possibly empty).
+pub fn complete(ctx: &Context, words: &[String]) -> Vec<Candidate> {
+    let root = Cli::command();
+
+    let (current, typed) = match words.split_last() {
+        Some((current, typed)) => (current.as_str(), typed),
+        None => ("", words),
+    };
+
+    // Walk the typed words to find the active subcommand and position
+    let mut subcommand: Option<&Command> = None;
+    let mut positionals = 0;
+    let mut pending_value: Option<&Arg> = None;
+
+    for word in typed {
+        if pending_value.take().is_some() {
+            continue;
+        }
+
+        if word.starts_with('-') && word.len() > 1 {
+            if !word.contains('=') {
+                pending_value = find_flag(&root, subcommand, word).filter(|arg| takes_value(arg));
+            }
+        } else if subcommand.is_none() && positionals == 0 {
+            match root.find_subcommand(word) {
+                Some(sub) => subcommand = Some(sub),
+                None => positionals += 1,
+            }
+        } else {
+            positionals += 1;
+        }
+    }
+
+    let candidates = if let Some(arg) = pending_value {
+        arg_values(ctx, arg)
+    } else if current.starts_with('-') {
+        flags(&root, subcommand)
+    } else {
+        match subcommand {
+            Some(sub) => positional_values(ctx, sub, positionals),
+            None if positionals == 0 => target_values(ctx, &root),
+            None => Vec::new(),
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|c| c.value.starts_with(current))
+        .collect()
+}
+
+/// Look up a flag on the active subcommand or the root command
+fn find_flag<'a>(
+    root: &'a Command,
+    subcommand: Option<&'a Command>,
+    word: &str,
+) -> Option<&'a Arg> {
+    let matches = |arg: &&Arg| {
+        if let Some(long) = word.strip_prefix("--") {
+            arg.get_long() == Some(long)
+        } else {
+            let short = word.trim_start_matches('-').chars().last();
+            short.is_some() && arg.get_short() == short
+        }
+    };
+
+    subcommand
+        .and_then(|sub| sub.get_arguments().find(matches))
+        .or_else(|| root.get_arguments().find(matches))
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+/// Flags of the active command (plus global flags of the root)
+fn flags(root: &Command, subcommand: Option<&Command>) -> Vec<Candidate> {
+    let args: Vec<&Arg> = match subcommand {
+        Some(sub) => sub
+            .get_arguments()
+            .chain(root.get_arguments().filter(|arg| arg.is_global_set()))
+            .collect(),
+        None => root.get_arguments().collect(),
+    };
+
+    let mut candidates: Vec<Candidate> = args
+        .into_iter()
+        .filter(|arg| !arg.is_hide_set() && !arg.is_positional())
+        .filter_map(|arg| {
+            let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+            arg.get_long()
+                .map(|long| Candidate::new(format!("--{}", long), help))
+        })
+        .collect();
+
+    candidates.push(Candidate::new("--help", "Print help"));
+    candidates
+}
+
+/// Values for a flag that takes an argument
+fn arg_values(ctx: &Context, arg: &Arg) -> Vec<Candidate> {
+    let possible = possible_values(arg);
+    if !possible.is_empty() {
+        return possible;
+    }
+
+    match arg.get_id().as_str() {
+        "create" => remote_branches(ctx),
+        "editor" => BUILTIN_EDITORS
+            .iter()
+            .map(|e| Candidate::new(*e, "editor"))
+            .collect(),
+        "get" => config_keys(""),
+        "set" => config_keys("="),
+        _ => Vec::new(),
+    }
+}
+
+/// Values for the nth positional argument of a subcommand
+fn positional_values(ctx: &Context, sub: &Command, index: usize) -> Vec<Candidate> {
+    let arg = match sub.get_positionals().nth(index) {
+        Some(arg) => arg,
+        None => return Vec::new(),
+    };
+
+    let possible = possible_values(arg);
+    if !possible.is_empty() {
+        return possible;
+    }
+
+    match (sub.get_name(), arg.get_id().as_str()) {
+        ("remove", "branch")
+        | ("env", "branch")
+        | ("open", "branch")
+        | ("install", "branch")
+        | ("sync-files", "branch") => worktree_branches(ctx),
+        _ => Vec::new(),
+    }
+}
+
+/// The bare `dev <TARGET>`: branches inside a repo, projects outside, plus subcommands
+fn target_values(ctx: &Context, root: &Command) -> Vec<Candidate> {
+    let mut candidates = if is_git_repository(&ctx.cwd) {
+        worktree_branches(ctx)
+    } else {
+        projects(ctx)
     };
 
-    generate(shell, &mut cmd, "dev", &mut std::io::stdout());
-
-    Ok(())
-}
-
-/// Generate zsh completion with custom dynamic completions
-pub fn generate_zsh_completion() {
-    let completion_script = r#"#compdef dev
-
-_dev_completion() {
-  local context state line
-  typeset -A opt_args
-
-  _arguments -C \
-    '(-l --list)'{-l,--list}'[List all worktrees with status]' \
-    '(-c --create)'{-c,--create}'[Create new branch and worktree]:branch name:' \
-    '--cleanup[Remove unused worktrees]' \
-    '--completion[Generate shell completion script]' \
-    '*::arg:->args' && return
-
-  case $state in
-    args)
-      # Check if we're in a project context
-      if git rev-parse --git-dir &>/dev/null; then
-        # We're in a git repo - complete with branch names
-        local -a branches
-        branches=($(git branch -a --format="%(refname:short)" 2>/dev/null | sed 's|origin/||g' | sort -u))
-        _describe 'branches' branches
-      else
-        # We're in global context - complete with project names
-        local -a projects
-        local config_dir="${XDG_CONFIG_HOME:-$HOME/.config}/dev/projects"
-        if [[ -d "$config_dir" ]]; then
-          projects=($(ls -1 "$config_dir" 2>/dev/null | sed 's/\.json$//'))
-          _describe 'projects' projects
-        fi
-      fi
-      ;;
-  esac
-}
-
-compdef _dev_completion dev"#;
-
-    println!("{}", completion_script);
-    println!();
-    println!("# To install this completion script:");
-    println!("# 1. Save the output to a file: dev --completion > ~/.zsh_completions/_dev");
-    println!("# 2. Add this line to your ~/.zshrc: fpath=(~/.zsh_completions $fpath)");
-    println!("# 3. Add this line to your ~/.zshrc: autoload -U compinit && compinit");
-    println!("# 4. Restart your shell or run: source ~/.zshrc");
+    candidates.extend(
+        root.get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| {
+                let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+                Candidate::new(sub.get_name(), about)
+            }),
+    );
+
+    candidates
+}
+
+fn possible_values(arg: &Arg) -> Vec<Candidate> {
+    arg.get_possible_values()
+        .into_iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| {
+            let help = v.get_help().map(|h| h.to_string()).unwrap_or_default();
+            Candidate::new(v.get_name(), help)
+        })
+        .collect()
+}
+
+fn worktree_branches(ctx: &Context) -> Vec<Candidate> {
+    ctx.git
+        .list_worktrees(&ctx.cwd)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|wt| !wt.is_bare)
+        .filter_map(|wt| {
+            let path = wt.path.display().to_string();
+            wt.branch.map(|branch| Candidate::new(branch, path))
+        })
+        .collect()
+}
+
+/// Branches that exist on the remote but not locally
+fn remote_branches(ctx: &Context) -> Vec<Candidate> {
+    let local = ctx.git.list_branches(&ctx.cwd, false).unwrap_or_default();
+
+    ctx.git
+        .list_branches(&ctx.cwd, true)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|branch| !local.contains(branch))
+        .map(|branch| Candidate::new(branch, "remote branch"))
+        .collect()
+}
+
+fn projects(ctx: &Context) -> Vec<Candidate> {
+    ProjectConfig::list_all(&ctx.config_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| Candidate::new(p.name, p.path.display().to_string()))
+        .collect()
+}
+
+fn config_keys(suffix: &str) -> Vec<Candidate> {
+    GlobalConfig::KEYS
+        .iter()
+        .map(|key| Candidate::new(format!("{}{}", key, suffix), "config key"))
+        .collect()
+}
+
+const BASH_COMPLETION: &str = r#"# dev-cli bash completion
+# Add to ~/.bashrc: eval "$(dev --completion bash)"
+_dev() {
+  local IFS=$'\n'
+  local words=("${COMP_WORDS[@]:1:COMP_CWORD}")
+  COMPREPLY=($(command dev __complete -- "${words[@]}" 2>/dev/null | cut -f1))
 }
+complete -o default -F _dev dev"#;
+
+const ZSH_COMPLETION: &str = r#"#compdef dev
+# dev-cli zsh completion
+# Save to a directory in $fpath as _dev, or add to ~/.zshrc: eval "$(dev --completion zsh)"
+_dev() {
+  local -a lines described
+  local line value desc
+  lines=("${(@f)$(command dev __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+
+  for line in $lines; do
+    [[ -z $line ]] && continue
+    value=${line%%$'\t'*}
+    desc=${line#*$'\t'}
+    [[ $desc == $line ]] && desc=""
+    described+=("${value//:/\\:}${desc:+:$desc}")
+  done
+
+  if (( ${#described} )); then
+    _describe -t candidates 'dev' described
+  else
+    _files
+  fi
+}
+
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+  _dev "$@"
+else
+  compdef _dev dev
+fi"#;
+
+const FISH_COMPLETION: &str = r#"# dev-cli fish completion
+# Save to ~/.config/fish/completions/dev.fish
+function __dev_complete
+    set -l tokens (commandline -opc)
+    set -e tokens[1]
+    command dev __complete -- $tokens (commandline -ct) 2>/dev/null
+end
+
+complete -c dev -f -a '(__dev_complete)'"#;
+
+const POWERSHELL_COMPLETION: &str = r#"# dev-cli PowerShell completion
+# Add to your $PROFILE: Invoke-Expression (& dev --completion powershell | Out-String)
+Register-ArgumentCompleter -Native -CommandName dev -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $words = @($commandAst.CommandElements |
+        Select-Object -Skip 1 |
+        Where-Object { $_.Extent.EndOffset -le $cursorPosition } |
+        ForEach-Object { $_.ToString() })
+    if ($wordToComplete -eq '') {
+        $words += ''
+    }
+
+    $devExe = Get-Command dev -CommandType Application | Select-Object -First 1
+    & $devExe __complete -- @words 2>$null | ForEach-Object {
+        $value, $description = $_ -split "`t", 2
+        if (-not $description) { $description = $value }
+        [System.Management.Automation.CompletionResult]::new(
+            $value, $value, 'ParameterValue', $description)
+    }
+}"#;
@@ -1,15 +1,14 @@
 use std::path::Path;
 use std::process::Command;
 
+use crate::config::{EffectiveConfig, WorktreeEnv};
 use crate::error::{DevError, Result};
+use crate::executable;
+use crate::ui::output::Output;
 
 /// Check if devbox is installed
 pub fn is_devbox_installed() -> bool {
-    Command::new("which")
-        .arg("devbox")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    executable::find("devbox").is_some()
 }
 
 /// Check if a directory has a devbox.json
@@ -17,6 +16,13 @@ pub fn has_devbox_config(path: &Path) -> bool {
     path.join("devbox.json").exists()
 }
 
+/// Whether the shell should enter devbox when moving to `path`
+pub fn should_enter(settings: &EffectiveConfig, path: &Path) -> bool {
+    settings.auto_devbox.value
+        && settings.project.as_ref().is_some_and(|p| p.uses_devbox)
+        && has_devbox_config(path)
+}
+
 /// Check if we're currently in a devbox shell
 pub fn in_devbox_shell() -> bool {
     std::env::var("DEVBOX_SHELL_ENABLED").is_ok()
@@ -61,24 +67,21 @@ pub fn get_devbox_shell_command(path: &Path) -> String {
 }
 
 /// Print instructions for entering devbox shell
-pub fn print_devbox_instructions(path: &Path) {
+pub fn print_devbox_instructions(out: &Output, path: &Path) {
     use colored::Colorize;
 
     if in_devbox_shell() {
-        println!(
-            "{}",
-            "Already in devbox shell. Navigate to the project:".yellow()
-        );
-        println!("  cd {}", path.display());
+        out.println("Already in devbox shell. Navigate to the project:".yellow());
+        out.println(format!("  cd {}", path.display()));
     } else {
-        println!("{}", "Enter devbox shell:".blue());
-        println!("  {}", get_devbox_shell_command(path));
+        out.println("Enter devbox shell:".blue());
+        out.println(format!("  {}", get_devbox_shell_command(path)));
     }
 }
 
 /// Launch devbox shell (replaces current process on Unix)
 #[cfg(unix)]
-pub fn exec_devbox_shell(path: &Path) -> Result<()> {
+pub fn exec_devbox_shell(path: &Path, env: &WorktreeEnv) -> Result<()> {
     use std::os::unix::process::CommandExt;
 
     if in_devbox_shell() {
@@ -94,7 +97,8 @@ pub fn exec_devbox_shell(path: &Path) -> Result<()> {
         )));
     }
 
-    let err = Command::new("devbox")
+    let err = env
+        .apply(&mut Command::new("devbox"))
         .arg("shell")
         .current_dir(path)
         .exec();
@@ -107,8 +111,25 @@ pub fn exec_devbox_shell(path: &Path) -> Result<()> {
 }
 
 #[cfg(not(unix))]
-pub fn exec_devbox_shell(path: &Path) -> Result<()> {
-    // On non-Unix systems, just print instructions
-    print_devbox_instructions(path);
+pub fn exec_devbox_shell(path: &Path, env: &WorktreeEnv) -> Result<()> {
+    // The process can't be replaced here, so wait for the shell instead
+    if in_devbox_shell() {
+        return Err(DevError::DevboxError(
+            "Already in a devbox shell. Cannot nest devbox shells.".to_string(),
+        ));
+    }
+
+    if !has_devbox_config(path) {
+        return Err(DevError::DevboxError(format!(
+            "No devbox.json found at {}",
+            path.display()
+        )));
+    }
+
+    env.apply(&mut Command::new("devbox"))
+        .arg("shell")
+        .current_dir(path)
+        .status()
+        .map_err(|e| DevError::DevboxError(format!("Failed to launch devbox shell: {}", e)))?;
     Ok(())
 }
@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use crate::config::global::Shell;
+use crate::error::Result;
+
+/// Environment variable naming the file the shell wrapper reads after `dev` exits
+pub const CD_FILE_VAR: &str = "DEV_CD_FILE";
+
+/// Ask the shell wrapper to cd into `path` (and optionally enter devbox)
+///
+/// The wrapper reads the target directory from the first line of the file
+/// and an optional `devbox` marker from the second, on which it runs
+/// `dev __devbox-shell` so the worktree's environment reaches devbox.
+/// Returns false when the wrapper isn't installed, so callers can fall back
+/// to printing a `cd` hint.
+pub fn request_cd(path: &Path, enter_devbox: bool) -> Result<bool> {
+    let cd_file = match std::env::var_os(CD_FILE_VAR) {
+        Some(file) => file,
+        None => return Ok(false),
+    };
+
+    let mut contents = format!("{}\n", path.display());
+    if enter_devbox {
+        contents.push_str("devbox\n");
+    }
+
+    std::fs::write(cd_file, contents)?;
+    Ok(true)
+}
+
+/// The wrapper function for a shell
+pub fn shell_init_script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash | Shell::Zsh => POSIX_INIT,
+        Shell::Fish => FISH_INIT,
+        Shell::PowerShell => POWERSHELL_INIT,
+    }
+}
+
+const POSIX_INIT: &str = r#"# dev-cli shell integration
+# Add to your shell rc file: eval "$(dev shell-init bash)" (or zsh)
+dev() {
+  local dev_cd_file dev_status dev_target dev_devbox
+  dev_cd_file="$(command mktemp "${TMPDIR:-/tmp}/dev-cd.XXXXXX")" || {
+    command dev "$@"
+    return
+  }
+
+  DEV_CD_FILE="$dev_cd_file" command dev "$@"
+  dev_status=$?
+
+  if [ -s "$dev_cd_file" ]; then
+    {
+      IFS= read -r dev_target
+      IFS= read -r dev_devbox
+    } < "$dev_cd_file"
+
+    if [ -d "$dev_target" ] && [ "$dev_target" != "$PWD" ]; then
+      builtin cd -- "$dev_target" || dev_status=$?
+    fi
+
+    if [ "$dev_devbox" = "devbox" ] && [ -z "$DEVBOX_SHELL_ENABLED" ]; then
+      command dev __devbox-shell
+    fi
+  fi
+
+  command rm -f -- "$dev_cd_file"
+  return $dev_status
+}"#;
+
+const FISH_INIT: &str = r#"# dev-cli shell integration
+# Add to ~/.config/fish/config.fish: dev shell-init fish | source
+function dev --wraps dev --description 'dev-cli with directory switching'
+    set -l dev_cd_file (command mktemp)
+    or begin
+        command dev $argv
+        return
+    end
+
+    DEV_CD_FILE=$dev_cd_file command dev $argv
+    set -l dev_status $status
+
+    if test -s $dev_cd_file
+        set -l lines (cat $dev_cd_file)
+
+        if test -d "$lines[1]"; and test "$lines[1]" != "$PWD"
+            builtin cd -- $lines[1]; or set dev_status $status
+        end
+
+        if test (count $lines) -ge 2; and test "$lines[2]" = devbox; and not set -q DEVBOX_SHELL_ENABLED
+            command dev __devbox-shell
+        end
+    end
+
+    command rm -f -- $dev_cd_file
+    return $dev_status
+end"#;
+
+const POWERSHELL_INIT: &str = r#"# dev-cli shell integration
+# Add to your $PROFILE: Invoke-Expression (& dev shell-init powershell | Out-String)
+function dev {
+    $devExe = Get-Command dev -CommandType Application | Select-Object -First 1
+    $devCdFile = [System.IO.Path]::GetTempFileName()
+
+    $env:DEV_CD_FILE = $devCdFile
+    try {
+        & $devExe @args
+        $devStatus = $LASTEXITCODE
+    } finally {
+        Remove-Item Env:DEV_CD_FILE -ErrorAction SilentlyContinue
+    }
+
+    $lines = @(Get-Content -LiteralPath $devCdFile -ErrorAction SilentlyContinue)
+    if ($lines.Count -ge 1 -and (Test-Path -LiteralPath $lines[0] -PathType Container)) {
+        Set-Location -LiteralPath $lines[0]
+    }
+    if ($lines.Count -ge 2 -and $lines[1] -eq 'devbox' -and -not $env:DEVBOX_SHELL_ENABLED) {
+        & $devExe __devbox-shell
+    }
+
+    Remove-Item -LiteralPath $devCdFile -ErrorAction SilentlyContinue
+    $global:LASTEXITCODE = $devStatus
+}"#;
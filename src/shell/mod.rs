@@ -1,5 +1,6 @@
 pub mod completion;
 pub mod devbox;
+pub mod integration;
 
 pub use completion::*;
 pub use devbox::*;
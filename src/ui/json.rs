@@ -0,0 +1,300 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::effective::{Setting, Source};
+use crate::config::ProjectConfig;
+use crate::error::{DevError, Result};
+use crate::git::{Divergence, Operation, Upstream, WorktreeInfo, WorktreeStatus};
+use crate::package_manager::DepsStatus;
+use crate::ui::output::Output;
+
+/// Version of the JSON output schema; bumped on breaking changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Top-level wrapper for every JSON document dev-cli prints
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    kind: &'a str,
+    data: T,
+}
+
+/// Error document printed when a command fails in JSON mode
+#[derive(Serialize)]
+struct ErrorEnvelope<'a> {
+    schema_version: u32,
+    error: ErrorJson<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorJson<'a> {
+    code: &'a str,
+    message: String,
+}
+
+/// Data of the `worktrees` document
+#[derive(Serialize)]
+pub struct WorktreeListJson {
+    /// Root of the bare hub, when the repository uses that layout
+    pub hub: Option<String>,
+    pub worktrees: Vec<WorktreeJson>,
+}
+
+/// A worktree as exposed in JSON output
+#[derive(Serialize)]
+pub struct WorktreeJson {
+    pub branch: Option<String>,
+    pub path: String,
+    pub commit: String,
+    pub is_current: bool,
+    pub is_bare: bool,
+    pub is_detached: bool,
+    pub status: StatusJson,
+    /// One of "up_to_date", "stale", "missing", "unrecorded"; absent without
+    /// a package manager
+    pub deps: Option<&'static str>,
+}
+
+/// Worktree status as exposed in JSON output
+#[derive(Serialize, Default)]
+pub struct StatusJson {
+    /// One of "clean", "ahead", "behind", "diverged", "modified", "unknown"
+    pub state: &'static str,
+    /// Commits ahead of upstream
+    pub ahead: usize,
+    /// Commits behind upstream
+    pub behind: usize,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub stashes: usize,
+    pub no_upstream: bool,
+    pub upstream_gone: bool,
+    /// Divergence from the default branch, absent on the default branch itself
+    pub default_branch: Option<DefaultBranchJson>,
+    /// One of "rebase", "merge", "cherry_pick", "revert", "bisect"
+    pub operation: Option<&'static str>,
+}
+
+/// Divergence from the default branch as exposed in JSON output
+#[derive(Serialize)]
+pub struct DefaultBranchJson {
+    pub name: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl WorktreeJson {
+    pub fn new(wt: &WorktreeInfo, deps: Option<DepsStatus>) -> Self {
+        Self {
+            branch: wt.branch.clone(),
+            path: wt.path.display().to_string(),
+            commit: wt.commit.clone(),
+            is_current: wt.is_current,
+            is_bare: wt.is_bare,
+            is_detached: wt.is_detached,
+            status: StatusJson::from(wt.status()),
+            deps: deps.map(deps_key),
+        }
+    }
+}
+
+/// Stable name of a dependency status in JSON and plain output
+pub fn deps_key(deps: DepsStatus) -> &'static str {
+    match deps {
+        DepsStatus::UpToDate => "up_to_date",
+        DepsStatus::Stale => "stale",
+        DepsStatus::Missing => "missing",
+        DepsStatus::Unrecorded => "unrecorded",
+    }
+}
+
+impl From<Option<&WorktreeStatus>> for StatusJson {
+    fn from(status: Option<&WorktreeStatus>) -> Self {
+        let status = match status {
+            Some(status) => status,
+            None => {
+                return Self {
+                    state: "unknown",
+                    ..Default::default()
+                }
+            }
+        };
+
+        let upstream = match status.upstream {
+            Upstream::Tracking(d) => d,
+            _ => Divergence::default(),
+        };
+
+        let state = if status.has_changes() {
+            "modified"
+        } else {
+            match (upstream.ahead, upstream.behind) {
+                (0, 0) => "clean",
+                (_, 0) => "ahead",
+                (0, _) => "behind",
+                _ => "diverged",
+            }
+        };
+
+        Self {
+            state,
+            ahead: upstream.ahead,
+            behind: upstream.behind,
+            staged: status.staged,
+            unstaged: status.unstaged,
+            untracked: status.untracked,
+            conflicted: status.conflicted,
+            stashes: status.stashes,
+            no_upstream: status.upstream == Upstream::NotSet,
+            upstream_gone: status.upstream == Upstream::Gone,
+            default_branch: status
+                .default_branch
+                .as_ref()
+                .map(|(name, d)| DefaultBranchJson {
+                    name: name.clone(),
+                    ahead: d.ahead,
+                    behind: d.behind,
+                }),
+            operation: status.operation.map(|op| match op {
+                Operation::Rebase => "rebase",
+                Operation::Merge => "merge",
+                Operation::CherryPick => "cherry_pick",
+                Operation::Revert => "revert",
+                Operation::Bisect => "bisect",
+            }),
+        }
+    }
+}
+
+/// A registered project as exposed in JSON output
+#[derive(Serialize)]
+pub struct ProjectJson {
+    pub name: String,
+    pub path: String,
+    pub remote_url: Option<String>,
+    pub uses_devbox: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+}
+
+impl From<&ProjectConfig> for ProjectJson {
+    fn from(project: &ProjectConfig) -> Self {
+        Self {
+            name: project.name.clone(),
+            path: project.path.display().to_string(),
+            remote_url: project.remote_url.clone(),
+            uses_devbox: project.uses_devbox,
+            created_at: project.created_at,
+            last_accessed: project.last_accessed,
+        }
+    }
+}
+
+/// Data of the `config` document
+#[derive(Serialize)]
+pub struct ConfigJson {
+    /// Path of the global config file
+    pub path: String,
+    pub entries: Vec<ConfigEntryJson>,
+}
+
+/// Data of the `effective_config` document
+#[derive(Serialize)]
+pub struct EffectiveConfigJson {
+    /// Project the settings were resolved for, if any
+    pub project: Option<String>,
+    pub entries: Vec<ConfigEntryJson>,
+}
+
+/// A config entry as exposed in JSON output
+#[derive(Serialize)]
+pub struct ConfigEntryJson {
+    pub key: String,
+    pub value: String,
+    /// One of "global", "project", "flag"; only set for effective settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'static str>,
+}
+
+impl ConfigEntryJson {
+    pub fn new(key: &str, value: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn from_setting<T: std::fmt::Display>(key: &str, setting: &Setting<T>) -> Self {
+        let source = match setting.source {
+            Source::Global => "global",
+            Source::Project(_) => "project",
+            Source::Flag => "flag",
+        };
+
+        Self {
+            source: Some(source),
+            ..Self::new(key, &setting.value)
+        }
+    }
+}
+
+/// Data of the `doctor` document
+#[derive(Serialize)]
+pub struct DoctorJson {
+    pub tools: Vec<ToolJson>,
+    pub problems: Vec<ProblemJson>,
+}
+
+/// An external tool as exposed in JSON output
+#[derive(Serialize)]
+pub struct ToolJson {
+    pub name: String,
+    /// One of "git", "editor", "devbox", "package_manager"
+    pub kind: &'static str,
+    /// Where the tool was found; absent when it wasn't
+    pub path: Option<String>,
+    pub version: Option<String>,
+    pub required: bool,
+}
+
+/// A problem found by `dev doctor` as exposed in JSON output
+#[derive(Serialize)]
+pub struct ProblemJson {
+    /// One of "config", "registry", "git", "worktrees", "tools"
+    pub check: &'static str,
+    pub message: String,
+    /// What `--fix` does about it; absent when it can't be fixed
+    pub fix: Option<&'static str>,
+    pub fixed: bool,
+}
+
+/// Print a JSON document of the given kind
+pub fn print<T: Serialize>(out: &Output, kind: &str, data: T) -> Result<()> {
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        kind,
+        data,
+    };
+
+    out.println(serde_json::to_string_pretty(&envelope)?);
+    Ok(())
+}
+
+/// Print an error as a JSON document to stderr
+pub fn print_error(error: &DevError) {
+    let envelope = ErrorEnvelope {
+        schema_version: SCHEMA_VERSION,
+        error: ErrorJson {
+            code: error.code(),
+            message: error.to_string(),
+        },
+    };
+
+    match serde_json::to_string_pretty(&envelope) {
+        Ok(json) => eprintln!("{}", json),
+        Err(_) => eprintln!("{}", error),
+    }
+}
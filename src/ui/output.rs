@@ -1,54 +1,170 @@
-use colored::Colorize;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::sync::Mutex;
 
-use crate::git::worktree::WorktreeStatus;
+use colored::{Color, Colorize};
 
-/// Print a success message
-pub fn success(message: &str) {
-    println!("{} {}", "✓".green(), message);
+use crate::git::{Divergence, Upstream, WorktreeStatus};
+
+/// How listing commands render their results
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned, colored output for humans
+    #[default]
+    Table,
+    /// Tab-separated values without colors or headers
+    Plain,
+    /// Versioned JSON documents
+    Json,
 }
 
-/// Print an info message
-pub fn info(message: &str) {
-    println!("{}", message.blue());
+/// Destination for everything commands print
+///
+/// Normally stdout and stderr; tests capture both in memory.
+pub struct Output {
+    stdout: Mutex<Box<dyn Write + Send>>,
+    stderr: Mutex<Box<dyn Write + Send>>,
 }
 
-/// Print a warning message
-pub fn warning(message: &str) {
-    println!("{} {}", "⚠".yellow(), message.yellow());
+impl Output {
+    pub fn new(stdout: impl Write + Send + 'static, stderr: impl Write + Send + 'static) -> Self {
+        Self {
+            stdout: Mutex::new(Box::new(stdout)),
+            stderr: Mutex::new(Box::new(stderr)),
+        }
+    }
+
+    /// Output to the process's stdout and stderr
+    pub fn stdio() -> Self {
+        Self::new(io::stdout(), io::stderr())
+    }
+
+    /// Print a line to stdout
+    pub fn println(&self, line: impl Display) {
+        write_line(&self.stdout, line);
+    }
+
+    /// Print a line to stderr
+    pub fn eprintln(&self, line: impl Display) {
+        write_line(&self.stderr, line);
+    }
+
+    /// Print a success message
+    pub fn success(&self, message: &str) {
+        self.println(format!("{} {}", "✓".green(), message));
+    }
+
+    /// Print an info message
+    pub fn info(&self, message: &str) {
+        self.println(message.blue());
+    }
+
+    /// Print a warning message
+    pub fn warning(&self, message: &str) {
+        self.println(format!("{} {}", "⚠".yellow(), message.yellow()));
+    }
 }
 
-/// Print an error message
-pub fn error(message: &str) {
-    eprintln!("{} {}", "✗".red(), message.red());
+fn write_line(stream: &Mutex<Box<dyn Write + Send>>, line: impl Display) {
+    let mut stream = stream.lock().unwrap_or_else(|e| e.into_inner());
+    // Nothing sensible to do if the terminal or pipe went away
+    let _ = writeln!(stream, "{}", line);
 }
 
-/// Print a gray/dim message
-pub fn dim(message: &str) {
-    println!("{}", message.dimmed());
+/// Get colored status icon (the most important aspect of the status)
+pub fn status_icon(status: Option<&WorktreeStatus>) -> colored::ColoredString {
+    let icon = match status {
+        None => "?",
+        Some(s) if s.conflicted > 0 || s.operation.is_some() => "!",
+        Some(s) if s.has_changes() => "●",
+        Some(s) => match s.upstream {
+            Upstream::Tracking(d) if d.ahead > 0 && d.behind > 0 => "⇅",
+            Upstream::Tracking(d) if d.ahead > 0 => "↑",
+            Upstream::Tracking(d) if d.behind > 0 => "↓",
+            Upstream::Tracking(_) => "✓",
+            Upstream::NotSet => "○",
+            Upstream::Gone => "⊘",
+        },
+    };
+
+    icon.color(status_color(status))
 }
 
-/// Get colored status icon
-pub fn status_icon(status: &WorktreeStatus) -> colored::ColoredString {
-    match status {
-        WorktreeStatus::Clean => "✓".green(),
-        WorktreeStatus::Ahead(_) => "↑".blue(),
-        WorktreeStatus::Behind(_) => "↓".yellow(),
-        WorktreeStatus::Diverged { .. } => "⇅".magenta(),
-        WorktreeStatus::Modified => "●".red(),
-        WorktreeStatus::Unknown => "?".dimmed(),
+/// Get colored compact status label, e.g. `rebasing !1 +2 ~1 ?3 ↑2 ↓1 $1 (main ↑5)`
+pub fn status_label(status: Option<&WorktreeStatus>) -> colored::ColoredString {
+    status_text(status).color(status_color(status))
+}
+
+/// Get the uncolored compact status label
+pub fn status_text(status: Option<&WorktreeStatus>) -> String {
+    let status = match status {
+        Some(status) => status,
+        None => return "unknown".to_string(),
+    };
+
+    let mut parts = Vec::new();
+
+    if let Some(operation) = status.operation {
+        parts.push(operation.label().to_string());
+    }
+
+    for (symbol, count) in [
+        ("!", status.conflicted),
+        ("+", status.staged),
+        ("~", status.unstaged),
+        ("?", status.untracked),
+    ] {
+        if count > 0 {
+            parts.push(format!("{}{}", symbol, count));
+        }
+    }
+
+    match status.upstream {
+        Upstream::Tracking(d) => parts.extend(divergence_parts(d)),
+        Upstream::NotSet => parts.push("no upstream".to_string()),
+        Upstream::Gone => parts.push("upstream gone".to_string()),
+    }
+
+    if status.stashes > 0 {
+        parts.push(format!("${}", status.stashes));
+    }
+
+    if let Some((ref branch, d)) = status.default_branch {
+        let divergence = divergence_parts(d);
+        if !divergence.is_empty() {
+            parts.push(format!("({} {})", branch, divergence.join(" ")));
+        }
+    }
+
+    if parts.is_empty() {
+        "clean".to_string()
+    } else {
+        parts.join(" ")
     }
 }
 
-/// Get colored status label
-pub fn status_label(status: &WorktreeStatus) -> colored::ColoredString {
+fn divergence_parts(d: Divergence) -> Vec<String> {
+    let mut parts = Vec::new();
+    if d.ahead > 0 {
+        parts.push(format!("↑{}", d.ahead));
+    }
+    if d.behind > 0 {
+        parts.push(format!("↓{}", d.behind));
+    }
+    parts
+}
+
+fn status_color(status: Option<&WorktreeStatus>) -> Color {
     match status {
-        WorktreeStatus::Clean => "clean".green(),
-        WorktreeStatus::Ahead(n) => format!("ahead {}", n).blue().into(),
-        WorktreeStatus::Behind(n) => format!("behind {}", n).yellow().into(),
-        WorktreeStatus::Diverged { ahead, behind } => {
-            format!("diverged +{} -{}", ahead, behind).magenta().into()
-        }
-        WorktreeStatus::Modified => "modified".red(),
-        WorktreeStatus::Unknown => "unknown".dimmed(),
+        None => Color::BrightBlack,
+        Some(s) if s.conflicted > 0 || s.operation.is_some() || s.has_changes() => Color::Red,
+        Some(s) => match s.upstream {
+            Upstream::Tracking(d) if d.ahead > 0 && d.behind > 0 => Color::Magenta,
+            Upstream::Tracking(d) if d.ahead > 0 => Color::Blue,
+            Upstream::Tracking(d) if d.behind > 0 => Color::Yellow,
+            Upstream::Tracking(_) => Color::Green,
+            Upstream::NotSet => Color::BrightBlack,
+            Upstream::Gone => Color::Yellow,
+        },
     }
 }
@@ -1,7 +1,42 @@
+use std::io::IsTerminal;
+
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 
 use crate::error::{DevError, Result};
 
+/// Asks the user questions
+///
+/// `flags` names the command-line flags that answer the question instead,
+/// for the error shown when nobody can be asked.
+pub trait Prompter {
+    /// Ask for confirmation
+    fn confirm(&self, message: &str, default: bool, flags: &str) -> Result<bool>;
+
+    /// Ask whether to go ahead with what was just shown, which `--yes`
+    /// answers
+    fn proceed(&self, message: &str) -> Result<bool>;
+
+    /// Ask for text input
+    fn input(&self, message: &str, default: Option<&str>, flags: &str) -> Result<String>;
+
+    /// Ask to select from a list, returning the index of the choice
+    fn select(&self, message: &str, items: &[String], flags: &str) -> Result<usize>;
+}
+
+/// The prompter for this run
+///
+/// Prompts are interactive only when stdin and stderr are terminals and
+/// neither `--yes` nor `--no-input` was given.
+pub fn prompter(assume_yes: bool, no_input: bool) -> Box<dyn Prompter> {
+    let terminal = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+
+    if terminal && !assume_yes && !no_input {
+        Box::new(Prompts::new())
+    } else {
+        Box::new(NoPrompts { assume_yes })
+    }
+}
+
 /// Interactive prompts using dialoguer
 pub struct Prompts {
     theme: ColorfulTheme,
@@ -19,9 +54,10 @@ impl Prompts {
             theme: ColorfulTheme::default(),
         }
     }
+}
 
-    /// Ask for confirmation
-    pub fn confirm(&self, message: &str, default: bool) -> Result<bool> {
+impl Prompter for Prompts {
+    fn confirm(&self, message: &str, default: bool, _flags: &str) -> Result<bool> {
         Confirm::with_theme(&self.theme)
             .with_prompt(message)
             .default(default)
@@ -29,8 +65,11 @@ impl Prompts {
             .map_err(|_| DevError::UserCancelled)
     }
 
-    /// Ask for text input
-    pub fn input(&self, message: &str, default: Option<&str>) -> Result<String> {
+    fn proceed(&self, message: &str) -> Result<bool> {
+        self.confirm(message, false, "--yes")
+    }
+
+    fn input(&self, message: &str, default: Option<&str>, _flags: &str) -> Result<String> {
         let mut input = Input::<String>::with_theme(&self.theme).with_prompt(message);
 
         if let Some(def) = default {
@@ -40,8 +79,7 @@ impl Prompts {
         input.interact_text().map_err(|_| DevError::UserCancelled)
     }
 
-    /// Ask to select from a list
-    pub fn select<T: ToString>(&self, message: &str, items: &[T]) -> Result<usize> {
+    fn select(&self, message: &str, items: &[String], _flags: &str) -> Result<usize> {
         Select::with_theme(&self.theme)
             .with_prompt(message)
             .items(items)
@@ -51,6 +89,55 @@ impl Prompts {
     }
 }
 
+/// Answers prompts without asking anyone
+///
+/// With `--yes`, the go-ahead is given and every other prompt takes its
+/// default, so questions that default to no (such as deleting a branch)
+/// still need their own flag; otherwise every prompt fails, naming the
+/// flags that answer it.
+pub struct NoPrompts {
+    pub assume_yes: bool,
+}
+
+impl NoPrompts {
+    fn input_required(message: &str, flags: &str) -> DevError {
+        DevError::InputRequired(message.to_string(), flags.to_string())
+    }
+}
+
+impl Prompter for NoPrompts {
+    fn confirm(&self, message: &str, default: bool, flags: &str) -> Result<bool> {
+        if self.assume_yes {
+            Ok(default)
+        } else {
+            Err(Self::input_required(message, flags))
+        }
+    }
+
+    fn proceed(&self, message: &str) -> Result<bool> {
+        if self.assume_yes {
+            Ok(true)
+        } else {
+            Err(Self::input_required(message, "--yes"))
+        }
+    }
+
+    fn input(&self, message: &str, default: Option<&str>, flags: &str) -> Result<String> {
+        match default {
+            Some(default) if self.assume_yes => Ok(default.to_string()),
+            _ => Err(Self::input_required(message, flags)),
+        }
+    }
+
+    fn select(&self, message: &str, items: &[String], flags: &str) -> Result<usize> {
+        if self.assume_yes && !items.is_empty() {
+            Ok(0)
+        } else {
+            Err(Self::input_required(message, flags))
+        }
+    }
+}
+
 /// What to do when a remote branch already exists
 #[derive(Debug, Clone, Copy)]
 pub enum RemoteBranchAction {
@@ -72,16 +159,24 @@ impl std::fmt::Display for RemoteBranchAction {
 }
 
 /// Ask what to do when a branch exists on remote
-pub fn prompt_remote_branch_action(prompts: &Prompts, branch: &str) -> Result<RemoteBranchAction> {
+pub fn prompt_remote_branch_action(
+    prompter: &dyn Prompter,
+    branch: &str,
+) -> Result<RemoteBranchAction> {
     let items = [
         RemoteBranchAction::Checkout,
         RemoteBranchAction::CreateDivergent,
         RemoteBranchAction::Cancel,
     ];
-
-    let selection = prompts.select(
-        &format!("Branch '{}' exists on remote. What would you like to do?", branch),
-        &items,
+    let labels: Vec<String> = items.iter().map(ToString::to_string).collect();
+
+    let selection = prompter.select(
+        &format!(
+            "Branch '{}' exists on remote. What would you like to do?",
+            branch
+        ),
+        &labels,
+        "--track-remote or --diverge",
     )?;
 
     Ok(items[selection])
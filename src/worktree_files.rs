@@ -0,0 +1,286 @@
+//! Untracked files carried from the main worktree into the others
+//!
+//! A fresh checkout lacks the gitignored files that make it runnable, like
+//! `.env` or local certificates. A project's `worktree_files` lists glob
+//! patterns, relative to the worktree root, of files and directories to
+//! bring across and how. Files git tracks, in either worktree, are never
+//! touched.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::WorktreeEnv;
+use crate::error::{DevError, Result};
+use crate::git;
+
+/// How a matched file gets into a worktree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileMode {
+    /// A copy of its own
+    #[default]
+    Copy,
+    /// A symlink to the main worktree's file, so edits are shared
+    Symlink,
+    /// A copy with each `{{VAR}}` replaced by that worktree environment
+    /// variable, e.g. `{{DEV_BRANCH}}`
+    Template,
+}
+
+/// Files to bring into worktrees
+///
+/// Written in config either as a glob pattern, copied, or as
+/// `{"pattern": ".env*", "mode": "template"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "WorktreeFileSpec")]
+pub struct WorktreeFile {
+    pub pattern: String,
+    pub mode: FileMode,
+}
+
+/// The forms a `worktree_files` entry can be written in
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorktreeFileSpec {
+    Pattern(String),
+    Full {
+        pattern: String,
+        #[serde(default)]
+        mode: FileMode,
+    },
+}
+
+impl From<WorktreeFileSpec> for WorktreeFile {
+    fn from(spec: WorktreeFileSpec) -> Self {
+        match spec {
+            WorktreeFileSpec::Pattern(pattern) => WorktreeFile {
+                pattern,
+                mode: FileMode::default(),
+            },
+            WorktreeFileSpec::Full { pattern, mode } => WorktreeFile { pattern, mode },
+        }
+    }
+}
+
+/// What syncing did (or, in a dry run, would do) to a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    Created,
+    Updated,
+    Unchanged,
+    /// Left alone because git tracks it in the source or the target
+    Tracked,
+}
+
+/// A path matched by `worktree_files` and what became of it
+#[derive(Debug, Clone)]
+pub struct SyncedFile {
+    /// Relative to the worktree root
+    pub path: PathBuf,
+    pub mode: FileMode,
+    pub action: SyncAction,
+}
+
+/// Bring the files matching `rules` from `source` into `target`
+///
+/// A path matched by several rules takes the mode of the first. Existing
+/// copies that differ from the source are overwritten. With `dry_run`
+/// nothing is written and the result says what would change.
+pub fn sync(
+    rules: &[WorktreeFile],
+    source: &Path,
+    target: &Path,
+    env: &WorktreeEnv,
+    dry_run: bool,
+) -> Result<Vec<SyncedFile>> {
+    let matches = expand(rules, source)?;
+
+    // A branch may track what the main worktree only has locally, so both
+    // sides are checked
+    let paths: Vec<PathBuf> = matches.keys().cloned().collect();
+    let mut tracked = git::tracked_files(source, &paths)?;
+    tracked.extend(git::tracked_files(target, &paths)?);
+
+    let mut synced = Vec::new();
+
+    for (path, mode) in matches {
+        let action = if tracked.iter().any(|t| t.starts_with(&path)) {
+            SyncAction::Tracked
+        } else {
+            let from = source.join(&path);
+            let to = target.join(&path);
+            match mode {
+                FileMode::Copy => sync_copy(&from, &to, None, dry_run)?,
+                FileMode::Template => sync_copy(&from, &to, Some(env), dry_run)?,
+                FileMode::Symlink => sync_symlink(&from, &to, dry_run)?,
+            }
+        };
+
+        synced.push(SyncedFile { path, mode, action });
+    }
+
+    Ok(synced)
+}
+
+/// Paths under `source` matched by the rules, each with its mode
+fn expand(rules: &[WorktreeFile], source: &Path) -> Result<BTreeMap<PathBuf, FileMode>> {
+    let root = glob::Pattern::escape(&source.to_string_lossy());
+    let mut matches = BTreeMap::new();
+
+    for rule in rules {
+        let invalid = |e: &dyn std::fmt::Display| {
+            DevError::ConfigError(format!(
+                "Invalid worktree_files pattern '{}': {}",
+                rule.pattern, e
+            ))
+        };
+
+        let pattern = format!("{}/{}", root, rule.pattern.trim_start_matches('/'));
+        for entry in glob::glob(&pattern).map_err(|e| invalid(&e))? {
+            let path = entry.map_err(|e| invalid(&e))?;
+            let relative = match path.strip_prefix(source) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => continue,
+            };
+
+            // The repository itself is never a worktree file
+            if relative.as_os_str().is_empty() || relative.starts_with(".git") {
+                continue;
+            }
+
+            matches.entry(relative).or_insert(rule.mode);
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Copy a file or directory, rendering it as a template when given an env
+fn sync_copy(
+    from: &Path,
+    to: &Path,
+    template: Option<&WorktreeEnv>,
+    dry_run: bool,
+) -> Result<SyncAction> {
+    let existed = to.symlink_metadata().is_ok();
+
+    // A symlink or a file of the wrong kind is replaced outright
+    let wrong_kind = to.is_symlink() || (existed && to.is_dir() != from.is_dir());
+    if wrong_kind && !dry_run {
+        remove(to)?;
+    }
+
+    let copied = copy_tree(from, to, template, dry_run)?;
+    let changed = wrong_kind || copied;
+
+    Ok(match (existed, changed) {
+        (false, _) => SyncAction::Created,
+        (true, true) => SyncAction::Updated,
+        (true, false) => SyncAction::Unchanged,
+    })
+}
+
+/// Copy `from` over `to` file by file, returning whether anything differed
+fn copy_tree(
+    from: &Path,
+    to: &Path,
+    template: Option<&WorktreeEnv>,
+    dry_run: bool,
+) -> Result<bool> {
+    if from.is_dir() {
+        let mut changed = false;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            changed |= copy_tree(
+                &entry.path(),
+                &to.join(entry.file_name()),
+                template,
+                dry_run,
+            )?;
+        }
+        return Ok(changed);
+    }
+
+    let contents = std::fs::read(from)?;
+    let contents = match template {
+        Some(env) => render(from, &contents, env)?.into_bytes(),
+        None => contents,
+    };
+
+    if std::fs::read(to).is_ok_and(|existing| existing == contents) {
+        return Ok(false);
+    }
+
+    if !dry_run {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(to, contents)?;
+    }
+
+    Ok(true)
+}
+
+/// Replace each `{{VAR}}` with the worktree environment variable
+fn render(path: &Path, contents: &[u8], env: &WorktreeEnv) -> Result<String> {
+    let mut text = String::from_utf8(contents.to_vec())
+        .map_err(|_| DevError::Other(format!("Template {} is not UTF-8 text", path.display())))?;
+
+    for (key, value) in env.vars() {
+        text = text.replace(&format!("{{{{{}}}}}", key), value);
+    }
+
+    Ok(text)
+}
+
+/// Point `to` at `from`, replacing whatever is there
+fn sync_symlink(from: &Path, to: &Path, dry_run: bool) -> Result<SyncAction> {
+    if std::fs::read_link(to).is_ok_and(|target| target == from) {
+        return Ok(SyncAction::Unchanged);
+    }
+
+    let existed = to.symlink_metadata().is_ok();
+
+    if !dry_run {
+        if existed {
+            remove(to)?;
+        } else if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        symlink(from, to)?;
+    }
+
+    Ok(if existed {
+        SyncAction::Updated
+    } else {
+        SyncAction::Created
+    })
+}
+
+/// Remove a file, symlink or directory tree
+fn remove(path: &Path) -> Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        std::fs::remove_dir_all(path)?;
+    } else {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(from: &Path, to: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(from, to)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(from, to)?;
+    } else {
+        std::os::windows::fs::symlink_file(from, to)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! The library API used from outside the crate
+
+mod common;
+
+use dev_cli::api;
+use dev_cli::error::DevError;
+use dev_cli::git::{backend, Upstream};
+use dev_cli::package_manager::DepsStatus;
+
+use common::{git, Sandbox};
+
+#[test]
+fn list_worktrees_includes_status() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+    std::fs::write(feature.join("new.txt"), "new\n").unwrap();
+
+    let list = api::list_worktrees(backend(), &sandbox.repo).unwrap();
+
+    assert_eq!(list.hub, None);
+    assert_eq!(list.worktrees.len(), 2);
+    assert!(list.worktrees[0].is_current);
+
+    let status = list.worktrees[1].status().unwrap();
+    assert_eq!(status.untracked, 1);
+    assert_eq!(status.upstream, Upstream::NotSet);
+}
+
+#[test]
+fn list_worktrees_with_deps_checks_each_worktree() {
+    let sandbox = Sandbox::new();
+    common::commit(&sandbox.repo, "package.json", "{}\n");
+    let feature = sandbox.add_worktree("feature");
+    std::fs::create_dir(feature.join("node_modules")).unwrap();
+
+    let (list, deps) =
+        api::list_worktrees_with_deps(&sandbox.config_dir, backend(), &sandbox.repo).unwrap();
+
+    assert_eq!(list.worktrees.len(), 2);
+    assert!(list.worktrees[1].status().is_some());
+    assert_eq!(
+        deps,
+        [Some(DepsStatus::Missing), Some(DepsStatus::Unrecorded)]
+    );
+}
+
+#[test]
+fn create_worktree_follows_layout() {
+    let sandbox = Sandbox::new();
+
+    let path = api::create_worktree(
+        &sandbox.config_dir,
+        backend(),
+        &sandbox.repo,
+        "feature",
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(path, sandbox.root.join("feature"));
+    assert_eq!(git(&path, &["branch", "--show-current"]), "feature");
+
+    let err = api::create_worktree(
+        &sandbox.config_dir,
+        backend(),
+        &sandbox.repo,
+        "feature",
+        true,
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), "branch_exists_locally");
+}
+
+#[test]
+fn worktree_env_for_branch() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+
+    let env = api::worktree_env(
+        &sandbox.config_dir,
+        backend(),
+        &sandbox.repo,
+        Some("feature"),
+    )
+    .unwrap();
+
+    assert_eq!(env.vars()["DEV_BRANCH"], "feature");
+    assert_eq!(env.vars()["DEV_WORKTREE"], feature.display().to_string());
+}
+
+#[test]
+fn unknown_project() {
+    let sandbox = Sandbox::new();
+
+    assert!(api::list_projects(&sandbox.config_dir).unwrap().is_empty());
+    assert!(matches!(
+        api::find_project(&sandbox.config_dir, "nope"),
+        Err(DevError::ProjectNotFound(_))
+    ));
+}
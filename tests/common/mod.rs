@@ -0,0 +1,154 @@
+//! Throwaway repositories and config directories for driving the `dev` binary
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+
+use assert_cmd::{cargo::cargo_bin_cmd, Command};
+use tempfile::TempDir;
+
+/// A temporary home with its own config dir and a repository on `main`
+pub struct Sandbox {
+    _tmp: TempDir,
+    pub root: PathBuf,
+    pub home: PathBuf,
+    pub config_dir: PathBuf,
+    pub repo: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        let tmp = TempDir::new().unwrap();
+        // Resolve symlinks (e.g. /tmp on macOS) so paths compare equal to git's
+        let root = tmp.path().canonicalize().unwrap();
+
+        let home = root.join("home");
+        let config_dir = root.join("config");
+        std::fs::create_dir(&home).unwrap();
+        std::fs::create_dir(&config_dir).unwrap();
+
+        let config = serde_json::json!({
+            "editor": "true",
+            "dev_path": root.join("dev"),
+            "auto_install_deps": false,
+            "auto_devbox": false,
+        });
+        std::fs::write(config_dir.join("config.json"), config.to_string()).unwrap();
+
+        let repo = root.join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        commit(&repo, "README", "hello\n");
+
+        Self {
+            _tmp: tmp,
+            root,
+            home,
+            config_dir,
+            repo,
+        }
+    }
+
+    /// `dev` running in the repository, isolated from the user's config
+    pub fn dev(&self) -> Command {
+        self.dev_in(&self.repo)
+    }
+
+    /// `dev` running in `dir`, isolated from the user's config
+    pub fn dev_in(&self, dir: &Path) -> Command {
+        let mut cmd = cargo_bin_cmd!("dev");
+        cmd.current_dir(dir)
+            .env("DEV_CONFIG_DIR", &self.config_dir)
+            .env("HOME", &self.home)
+            .env("NO_COLOR", "1")
+            .env_remove("DEV_CD_FILE")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .envs(IDENTITY);
+        cmd
+    }
+
+    /// Set a key in the global config file
+    pub fn set_config(&self, key: &str, value: serde_json::Value) {
+        let path = self.config_dir.join("config.json");
+        let mut config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        config[key] = value;
+        std::fs::write(path, config.to_string()).unwrap();
+    }
+
+    /// Register the repository as project `demo` with extra settings
+    pub fn register(&self, settings: serde_json::Value) {
+        let mut project = serde_json::json!({
+            "name": "demo",
+            "path": self.repo,
+            "created_at": "2024-01-01T00:00:00Z",
+            "last_accessed": "2024-01-01T00:00:00Z",
+        });
+        for (key, value) in settings.as_object().unwrap() {
+            project[key] = value.clone();
+        }
+
+        let projects = self.config_dir.join("projects");
+        std::fs::create_dir_all(&projects).unwrap();
+        std::fs::write(projects.join("demo.json"), project.to_string()).unwrap();
+    }
+
+    /// Replace the repository with a clone of it that has a `remote-only` branch
+    pub fn with_clone(mut self) -> Self {
+        git(&self.repo, &["branch", "remote-only"]);
+        git(&self.root, &["clone", "-q", "repo", "clone"]);
+        self.repo = self.root.join("clone");
+        self
+    }
+
+    /// Add a worktree for a new branch next to the repository
+    pub fn add_worktree(&self, branch: &str) -> PathBuf {
+        let path = self.root.join(branch);
+        git(
+            &self.repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                branch,
+                path.to_str().unwrap(),
+            ],
+        );
+        path
+    }
+}
+
+/// Run git with a fixed identity, panicking on failure
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = StdCommand::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .envs(IDENTITY)
+        .output()
+        .expect("failed to run git");
+
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+const IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "Test"),
+    ("GIT_AUTHOR_EMAIL", "test@example.com"),
+    ("GIT_COMMITTER_NAME", "Test"),
+    ("GIT_COMMITTER_EMAIL", "test@example.com"),
+];
+
+pub fn commit(dir: &Path, file: &str, contents: &str) {
+    std::fs::write(dir.join(file), contents).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", file]);
+}
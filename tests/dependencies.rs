@@ -0,0 +1,498 @@
+//! Dependency installs, with a stand-in `npm` that records each run
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use predicates::prelude::*;
+use serde_json::{json, Value};
+
+use common::{commit, Sandbox};
+
+/// A sandbox whose repository is an npm project, with a fake `npm` on PATH
+/// that "installs" by writing one package and logging its directory and
+/// arguments
+fn npm_sandbox() -> (Sandbox, PathBuf) {
+    let sandbox = Sandbox::new();
+    commit(&sandbox.repo, "package.json", "{}\n");
+    commit(&sandbox.repo, "package-lock.json", "{\"v\": 1}\n");
+    sandbox.set_config("auto_install_deps", json!(true));
+
+    let bin = sandbox.root.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    fake_installer(&sandbox, &bin, "npm");
+
+    (sandbox, bin)
+}
+
+/// Put a stand-in for a JavaScript package manager in `bin`, logging each
+/// install to `<name>.log`
+fn fake_installer(sandbox: &Sandbox, bin: &Path, name: &str) {
+    let script = format!(
+        "#!/bin/sh\n\
+         [ \"$1\" = --version ] && {{ echo 10.0.0; exit; }}\n\
+         mkdir -p node_modules/left-pad\n\
+         echo pad > node_modules/left-pad/index.js\n\
+         echo \"$(pwd) $*\" >> {}\n",
+        sandbox.root.join(format!("{}.log", name)).display()
+    );
+    let path = bin.join(name);
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn path_with(bin: &Path) -> String {
+    format!("{}:{}", bin.display(), std::env::var("PATH").unwrap())
+}
+
+fn npm_runs(sandbox: &Sandbox) -> usize {
+    npm_log(sandbox).len()
+}
+
+fn npm_log(sandbox: &Sandbox) -> Vec<String> {
+    std::fs::read_to_string(sandbox.root.join("npm.log"))
+        .map(|log| log.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Commit an npm package with a lockfile at `dir` in the repository
+fn add_package(sandbox: &Sandbox, dir: &str) {
+    std::fs::create_dir_all(sandbox.repo.join(dir)).unwrap();
+    commit(&sandbox.repo, &format!("{}/package.json", dir), "{}\n");
+    commit(&sandbox.repo, &format!("{}/package-lock.json", dir), "{}\n");
+}
+
+#[test]
+fn shared_deps_reuses_worktree_with_same_lockfile() {
+    let (sandbox, bin) = npm_sandbox();
+    sandbox.set_config("shared_deps", json!(true));
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 1);
+
+    sandbox
+        .dev()
+        .args(["--create", "two"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Reused dependencies from {}",
+            sandbox.root.join("one").display()
+        )));
+
+    assert_eq!(npm_runs(&sandbox), 1);
+    let package = sandbox.root.join("two/node_modules/left-pad/index.js");
+    assert_eq!(std::fs::read_to_string(package).unwrap(), "pad\n");
+}
+
+#[test]
+fn shared_deps_installs_when_lockfile_differs() {
+    let (sandbox, bin) = npm_sandbox();
+    sandbox.set_config("shared_deps", json!(true));
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+
+    commit(&sandbox.repo, "package-lock.json", "{\"v\": 2}\n");
+
+    sandbox
+        .dev()
+        .args(["--create", "two"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reused").not());
+
+    assert_eq!(npm_runs(&sandbox), 2);
+}
+
+#[test]
+fn without_shared_deps_every_worktree_installs() {
+    let (sandbox, bin) = npm_sandbox();
+
+    for branch in ["one", "two"] {
+        sandbox
+            .dev()
+            .args(["--create", branch])
+            .env("PATH", path_with(&bin))
+            .assert()
+            .success();
+    }
+
+    assert_eq!(npm_runs(&sandbox), 2);
+}
+
+#[test]
+fn changed_lockfile_marks_deps_stale_until_reinstalled() {
+    let (sandbox, bin) = npm_sandbox();
+    let one = sandbox.root.join("one");
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+
+    // The record lives in the worktree's git dir, not in the checkout
+    assert_eq!(
+        common::git(&one, &["status", "--porcelain"]),
+        "?? node_modules/"
+    );
+
+    commit(&one, "package-lock.json", "{\"v\": 2}\n");
+
+    let output = sandbox
+        .dev()
+        .args(["--list", "--format", "json"])
+        .env("PATH", path_with(&bin))
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["data"]["worktrees"][1]["deps"], "stale");
+
+    sandbox
+        .dev()
+        .arg("one")
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Dependencies are stale, reinstalling",
+        ));
+    assert_eq!(npm_runs(&sandbox), 2);
+
+    sandbox
+        .dev()
+        .args(["install", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dependencies are up to date"));
+    assert_eq!(npm_runs(&sandbox), 2);
+
+    sandbox
+        .dev()
+        .args(["install", "one", "--force"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 3);
+}
+
+#[test]
+fn bun_lockfile_takes_precedence_over_npm() {
+    let (sandbox, bin) = npm_sandbox();
+    fake_installer(&sandbox, &bin, "bun");
+    commit(&sandbox.repo, "bun.lock", "{}\n");
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installing dependencies using bun",
+        ));
+
+    assert_eq!(npm_runs(&sandbox), 0);
+    assert!(sandbox.root.join("bun.log").exists());
+}
+
+#[test]
+fn custom_package_manager_installs_alongside_builtin() {
+    let (sandbox, bin) = npm_sandbox();
+    commit(&sandbox.repo, "rebar.config", "{deps, []}.\n");
+    sandbox.set_config(
+        "package_managers",
+        json!([{
+            "name": "rebar",
+            "marker": "rebar.config",
+            "install": "echo fetched > rebar.log",
+        }]),
+    );
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installing dependencies using rebar",
+        ))
+        .stdout(predicate::str::contains(
+            "Installing dependencies using npm",
+        ));
+
+    assert_eq!(npm_runs(&sandbox), 1);
+    assert!(sandbox.root.join("one/rebar.log").exists());
+
+    // Both installs were recorded, so there's nothing left to do
+    sandbox
+        .dev()
+        .args(["install", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dependencies are up to date"));
+}
+
+#[test]
+fn nested_packages_install_separately() {
+    let (sandbox, bin) = npm_sandbox();
+    add_package(&sandbox, "web");
+    add_package(&sandbox, "tools/lint");
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installing dependencies in web using npm",
+        ))
+        .stdout(predicate::str::contains("tools/lint (npm): installed"))
+        .stdout(predicate::str::contains(". (npm): installed"));
+
+    let one = sandbox.root.join("one");
+    let dirs: Vec<String> = npm_log(&sandbox)
+        .iter()
+        .map(|line| line.split(' ').next().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        dirs,
+        [
+            one.display().to_string(),
+            one.join("tools/lint").display().to_string(),
+            one.join("web").display().to_string(),
+        ]
+    );
+}
+
+#[test]
+fn nested_manifests_without_lockfiles_are_not_packages() {
+    let (sandbox, bin) = npm_sandbox();
+    for fixture in ["test/fixtures/pkg", "examples/demo"] {
+        std::fs::create_dir_all(sandbox.repo.join(fixture)).unwrap();
+        commit(&sandbox.repo, &format!("{}/package.json", fixture), "{}\n");
+    }
+    commit(&sandbox.repo, "Cargo.toml", "[package]\nname = \"demo\"\n");
+    commit(&sandbox.repo, "Cargo.lock", "version = 3\n");
+    commit(
+        &sandbox.repo,
+        "examples/demo/Cargo.toml",
+        "[package]\nname = \"example\"\n",
+    );
+    // Stands in for cargo so nothing is fetched
+    sandbox.set_config(
+        "package_managers",
+        json!([{
+            "name": "fetch",
+            "marker": "Cargo.toml",
+            "install": format!("pwd >> {}", sandbox.root.join("fetch.log").display()),
+            "lockfile": "Cargo.lock",
+            "ecosystem": "rust",
+        }]),
+    );
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+
+    let one = sandbox.root.join("one");
+    let log = npm_log(&sandbox);
+    assert_eq!(log.len(), 1);
+    assert!(log[0].starts_with(&format!("{} ", one.display())));
+
+    let fetched = std::fs::read_to_string(sandbox.root.join("fetch.log")).unwrap();
+    assert_eq!(fetched, format!("{}\n", one.display()));
+}
+
+#[test]
+fn workspace_members_install_with_the_root() {
+    let (sandbox, bin) = npm_sandbox();
+    commit(
+        &sandbox.repo,
+        "package.json",
+        "{\"workspaces\": [\"packages/*\"]}\n",
+    );
+    add_package(&sandbox, "packages/ui");
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 1);
+
+    // Choosing a member installs the workspace for just that member
+    sandbox
+        .dev()
+        .args(["install", "one", "--package", "packages/ui", "--force"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installing dependencies for packages/ui using npm",
+        ));
+
+    let log = npm_log(&sandbox);
+    assert!(
+        log[1].ends_with("install --workspace=packages/ui"),
+        "{}",
+        log[1]
+    );
+}
+
+#[test]
+fn cargo_workspace_members_are_not_installed_on_their_own() {
+    let (sandbox, bin) = npm_sandbox();
+    commit(
+        &sandbox.repo,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    );
+    std::fs::create_dir_all(sandbox.repo.join("crates/core")).unwrap();
+    commit(
+        &sandbox.repo,
+        "crates/core/Cargo.toml",
+        "[package]\nname = \"core\"\n",
+    );
+    // Stands in for cargo so nothing is fetched
+    sandbox.set_config(
+        "package_managers",
+        json!([{
+            "name": "fetch",
+            "marker": "Cargo.toml",
+            "install": format!("pwd >> {}", sandbox.root.join("fetch.log").display()),
+            "ecosystem": "rust",
+        }]),
+    );
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+
+    let log = std::fs::read_to_string(sandbox.root.join("fetch.log")).unwrap();
+    assert_eq!(log.lines().count(), 1);
+}
+
+#[test]
+fn selected_packages_are_remembered_for_the_worktree() {
+    let (sandbox, bin) = npm_sandbox();
+    add_package(&sandbox, "web");
+
+    sandbox
+        .dev()
+        .args(["--create", "one"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 2);
+
+    let one = sandbox.root.join("one");
+    commit(&one, "package-lock.json", "{\"v\": 2}\n");
+    commit(&one, "web/package-lock.json", "{\"v\": 2}\n");
+
+    sandbox
+        .dev()
+        .args(["install", "one", "-p", "web"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 3);
+    assert!(npm_log(&sandbox)[2].starts_with(&one.join("web").display().to_string()));
+
+    // The root's stale dependencies no longer count
+    let output = sandbox
+        .dev()
+        .args(["--list", "--format", "json"])
+        .env("PATH", path_with(&bin))
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["data"]["worktrees"][1]["deps"], "up_to_date");
+
+    sandbox
+        .dev()
+        .args(["install", "one", "--all-packages"])
+        .env("PATH", path_with(&bin))
+        .assert()
+        .success();
+    assert_eq!(npm_runs(&sandbox), 4);
+}
+
+#[test]
+fn dependencies_stay_when_the_worktree_cannot_be_removed() {
+    let sandbox = Sandbox::new();
+    commit(&sandbox.repo, "package.json", "{}\n");
+    commit(&sandbox.repo, ".gitignore", "node_modules/\n");
+    let feature = sandbox.add_worktree("feature");
+    std::fs::create_dir_all(feature.join("node_modules/left-pad")).unwrap();
+    common::git(
+        &sandbox.repo,
+        &["worktree", "lock", feature.to_str().unwrap()],
+    );
+
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--keep-branch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("locked"));
+    assert!(feature.join("node_modules/left-pad").exists());
+
+    common::git(
+        &sandbox.repo,
+        &["worktree", "unlock", feature.to_str().unwrap()],
+    );
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--keep-branch"])
+        .assert()
+        .success();
+    assert!(!feature.exists());
+}
+
+#[test]
+fn list_shows_unknown_deps_instead_of_failing() {
+    let sandbox = Sandbox::new();
+    commit(&sandbox.repo, "package.json", "{}\n");
+    sandbox.register(json!({ "packages": ["["] }));
+    sandbox.add_worktree("feature");
+
+    let output = sandbox
+        .dev()
+        .args(["--list", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let worktrees = json["data"]["worktrees"].as_array().unwrap();
+    assert_eq!(worktrees.len(), 2);
+    assert!(worktrees.iter().all(|wt| wt["deps"].is_null()));
+    assert_eq!(worktrees[1]["status"]["state"], "clean");
+}
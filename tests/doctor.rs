@@ -0,0 +1,219 @@
+//! `dev doctor`, with stand-in tools on PATH
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use common::{commit, Sandbox};
+
+/// Put a script in `bin` that prints `version` when asked for it
+fn fake_tool(bin: &Path, name: &str, version: &str) -> PathBuf {
+    let path = bin.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\necho '{}'\n", version)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn path_with(bin: &Path) -> String {
+    format!("{}:{}", bin.display(), std::env::var("PATH").unwrap())
+}
+
+#[test]
+fn doctor_reports_paths_and_versions() {
+    let sandbox = Sandbox::new();
+    commit(&sandbox.repo, "package.json", "{}\n");
+    sandbox.set_config("editor", json!("nvim"));
+
+    let bin = sandbox.root.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let nvim = fake_tool(&bin, "nvim", "NVIM v0.10.1");
+    let npm = fake_tool(&bin, "npm", "10.8.2");
+    // Found on PATH, but not runnable, so not installed
+    std::fs::write(bin.join("devbox"), "").unwrap();
+
+    let output = sandbox
+        .dev()
+        .args(["doctor", "--format", "json"])
+        .env("PATH", path_with(&bin))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let doc: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(doc["kind"], "doctor");
+
+    let tools = doc["data"]["tools"].as_array().unwrap();
+    let tool = |kind: &str| tools.iter().find(|t| t["kind"] == kind).unwrap();
+
+    assert_eq!(tool("git")["required"], true);
+    assert!(tool("git")["version"].is_string());
+
+    assert_eq!(tool("editor")["name"], "nvim");
+    assert_eq!(tool("editor")["path"], nvim.display().to_string());
+    assert_eq!(tool("editor")["version"], "0.10.1");
+
+    assert_eq!(tool("devbox")["path"], Value::Null);
+    assert_eq!(tool("devbox")["required"], false);
+
+    assert_eq!(tool("package_manager")["name"], "npm");
+    assert_eq!(tool("package_manager")["path"], npm.display().to_string());
+    assert_eq!(tool("package_manager")["version"], "10.8.2");
+}
+
+#[test]
+fn editor_configured_as_a_path_needs_no_path_lookup() {
+    let sandbox = Sandbox::new();
+    let bin = sandbox.root.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let editor = fake_tool(&bin, "my-editor", "1.0");
+    sandbox.set_config("editor", json!(editor));
+
+    sandbox
+        .dev()
+        .args(["doctor", "--format", "plain"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(format!(
+            "editor\t{}\t{}\t\n",
+            editor.display(),
+            editor.display()
+        )));
+}
+
+#[test]
+fn doctor_finds_registry_problems_and_fixes_them() {
+    let sandbox = Sandbox::new();
+    sandbox.register(json!({}));
+
+    let projects = sandbox.config_dir.join("projects");
+    let project = |name: &str, path: &Path| {
+        json!({
+            "name": name,
+            "path": path,
+            "created_at": "2023-01-01T00:00:00Z",
+            "last_accessed": "2023-01-01T00:00:00Z",
+        })
+        .to_string()
+    };
+    std::fs::write(
+        projects.join("gone.json"),
+        project("gone", &sandbox.root.join("nope")),
+    )
+    .unwrap();
+    std::fs::write(projects.join("copy.json"), project("copy", &sandbox.repo)).unwrap();
+    let other = sandbox.root.join("other");
+    std::fs::create_dir(&other).unwrap();
+    std::fs::write(projects.join("renamed.json"), project("old-name", &other)).unwrap();
+    std::fs::write(projects.join("broken.json"), "{").unwrap();
+
+    let problems = |output: &[u8]| -> Vec<String> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .filter(|line| line.starts_with("problem\t"))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let output = sandbox
+        .dev()
+        .args(["doctor", "--format", "plain"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let found = problems(&output.stdout);
+    assert_eq!(found.len(), 4, "{:?}", found);
+    assert!(found[0].starts_with("problem\tconfig\tunfixable\t"));
+    assert!(found[0].contains("broken.json is invalid"));
+    assert_eq!(
+        found[1],
+        format!(
+            "problem\tregistry\tfixable\tProject 'copy' has the same path as 'demo': {}",
+            sandbox.repo.display()
+        )
+    );
+    assert_eq!(
+        found[2],
+        format!(
+            "problem\tregistry\tfixable\tProject 'gone' points to {}, which doesn't exist",
+            sandbox.root.join("nope").display()
+        )
+    );
+    assert_eq!(
+        found[3],
+        "problem\tregistry\tfixable\tProject 'renamed' is named 'old-name' inside its file"
+    );
+
+    let output = sandbox
+        .dev()
+        .args(["doctor", "--fix", "--format", "plain"])
+        .output()
+        .unwrap();
+    // The broken file is left for a person to look at
+    assert!(!output.status.success());
+    assert_eq!(
+        problems(&output.stdout)
+            .iter()
+            .filter(|p| p.contains("\tfixed\t"))
+            .count(),
+        3
+    );
+
+    assert!(projects.join("demo.json").exists());
+    assert!(!projects.join("copy.json").exists());
+    assert!(!projects.join("gone.json").exists());
+    assert!(projects.join("broken.json").exists());
+    let renamed: Value =
+        serde_json::from_str(&std::fs::read_to_string(projects.join("renamed.json")).unwrap())
+            .unwrap();
+    assert_eq!(renamed["name"], "renamed");
+}
+
+#[test]
+fn doctor_prunes_worktrees_whose_directory_is_gone() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+    std::fs::remove_dir_all(&feature).unwrap();
+
+    sandbox
+        .dev()
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(format!(
+            "Worktree for 'feature' is gone from {}",
+            feature.display()
+        )))
+        .stdout(predicates::str::contains(
+            "Run 'dev doctor --fix' to repair 1 of them",
+        ));
+
+    sandbox
+        .dev()
+        .args(["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("(fixed: prune it)"));
+
+    let worktrees = common::git(&sandbox.repo, &["worktree", "list"]);
+    assert!(!worktrees.contains("feature"));
+}
+
+#[test]
+fn doctor_reports_an_invalid_global_config() {
+    let sandbox = Sandbox::new();
+    std::fs::write(sandbox.config_dir.join("config.json"), "{\"editor\": 1}").unwrap();
+
+    sandbox
+        .dev()
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains("config.json is invalid"))
+        // Listed with the default settings rather than not at all
+        .stdout(predicates::str::contains("🩺 Tools:"));
+}
@@ -0,0 +1,127 @@
+//! Opening worktrees and files with `dev open` and editor command templates
+
+mod common;
+
+use predicates::prelude::*;
+use serde_json::json;
+
+use common::Sandbox;
+
+/// Use an editor command line that records its arguments, prefixed by `args`
+fn logging_editor(sandbox: &Sandbox, args: &str) {
+    let log = sandbox.root.join("editor.log");
+    sandbox.set_config(
+        "editor",
+        json!(format!(
+            "sh -c 'echo \"$@\" > {}' sh {}",
+            log.display(),
+            args
+        )),
+    );
+}
+
+fn editor_log(sandbox: &Sandbox) -> String {
+    std::fs::read_to_string(sandbox.root.join("editor.log"))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+#[test]
+fn open_file_fills_in_placeholders() {
+    let sandbox = Sandbox::new();
+    logging_editor(&sandbox, "{path} --goto {file}:{line}");
+
+    sandbox
+        .dev()
+        .args(["open", "--file", "README:3", "--wait"])
+        .assert()
+        .success();
+
+    let repo = sandbox.repo.display();
+    assert_eq!(
+        editor_log(&sandbox),
+        format!("{} --goto {}/README:3", repo, repo)
+    );
+}
+
+#[test]
+fn file_arguments_are_dropped_when_opening_a_worktree() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+    logging_editor(&sandbox, "--goto={file}:{line} {workspace_file}");
+    sandbox.set_config("editor_wait", json!(true));
+
+    sandbox.dev().args(["open", "feature"]).assert().success();
+
+    assert_eq!(editor_log(&sandbox), feature.display().to_string());
+}
+
+#[test]
+fn template_without_paths_gets_the_worktree_appended() {
+    let sandbox = Sandbox::new();
+    logging_editor(&sandbox, "--new-window");
+
+    sandbox.dev().args(["open", "--wait"]).assert().success();
+
+    assert_eq!(
+        editor_log(&sandbox),
+        format!("--new-window {}", sandbox.repo.display())
+    );
+}
+
+#[test]
+fn open_missing_file_fails() {
+    let sandbox = Sandbox::new();
+    logging_editor(&sandbox, "{path}");
+
+    sandbox
+        .dev()
+        .args(["open", "--file", "nope.rs:10"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No such file in the worktree: nope.rs:10",
+        ));
+
+    assert!(!sandbox.root.join("editor.log").exists());
+}
+
+#[test]
+fn flag_for_the_file_is_dropped_with_it() {
+    let sandbox = Sandbox::new();
+    logging_editor(&sandbox, "--new-window --goto {file}:{line}");
+
+    sandbox.dev().args(["open", "--wait"]).assert().success();
+
+    // Nothing left names a path, so the worktree is appended
+    assert_eq!(
+        editor_log(&sandbox),
+        format!("--new-window {}", sandbox.repo.display())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn editor_path_with_spaces_is_not_a_template() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    let dir = sandbox.root.join("My Editor");
+    std::fs::create_dir(&dir).unwrap();
+    let editor = dir.join("ed");
+    std::fs::write(
+        &editor,
+        format!(
+            "#!/bin/sh\necho \"$@\" > '{}'\n",
+            sandbox.root.join("editor.log").display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    sandbox.set_config("editor", json!(editor));
+
+    sandbox.dev().args(["open", "--wait"]).assert().success();
+
+    assert_eq!(editor_log(&sandbox), sandbox.repo.display().to_string());
+}
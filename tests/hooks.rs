@@ -0,0 +1,173 @@
+//! Lifecycle hooks from global config and a committed `.dev.json`
+
+mod common;
+
+use std::path::Path;
+
+use dev_cli::config::{RepoConfig, TrustedHooks};
+use predicates::prelude::*;
+use serde_json::json;
+
+use common::{commit, git, Sandbox};
+
+/// Approve the hooks in `worktree`'s `.dev.json`, as answering the prompt would
+fn approve_hooks(sandbox: &Sandbox, worktree: &Path) {
+    let (_, hash) = RepoConfig::load_hashed(worktree).unwrap().unwrap();
+    let mut trusted = TrustedHooks::load(&sandbox.config_dir).unwrap();
+    trusted.trust(&hash, &RepoConfig::config_path(worktree));
+    trusted.save(&sandbox.config_dir).unwrap();
+}
+
+#[test]
+fn post_create_runs_committed_hooks_in_new_worktree() {
+    let sandbox = Sandbox::new();
+    let hooks = json!({
+        "hooks": {
+            "post_create": ["echo \"$DEV_HOOK $DEV_BRANCH\" > created"]
+        }
+    });
+    commit(&sandbox.repo, ".dev.json", &hooks.to_string());
+    approve_hooks(&sandbox, &sandbox.repo);
+
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Running post_create hook"));
+
+    let created = std::fs::read_to_string(sandbox.root.join("feature/created")).unwrap();
+    assert_eq!(created, "post_create feature\n");
+}
+
+#[test]
+fn global_hooks_run_before_committed_ones() {
+    let sandbox = Sandbox::new();
+    sandbox.set_config("hooks", json!({ "post_switch": ["echo global >> ran"] }));
+    let feature = sandbox.add_worktree("feature");
+    let hooks = json!({ "hooks": { "post_switch": ["echo repo >> ran"] } });
+    commit(&feature, ".dev.json", &hooks.to_string());
+    approve_hooks(&sandbox, &feature);
+
+    sandbox.dev().arg("feature").assert().success();
+
+    let ran = std::fs::read_to_string(feature.join("ran")).unwrap();
+    assert_eq!(ran, "global\nrepo\n");
+}
+
+#[test]
+fn hooks_in_a_freshly_cloned_repo_do_not_run() {
+    let sandbox = Sandbox::new();
+    let pwned = sandbox.root.join("pwned");
+    let hooks = json!({
+        "hooks": {
+            "post_init": [format!("touch {}", pwned.display())],
+            "pre_open": [format!("touch {}", pwned.display())],
+        }
+    });
+    commit(&sandbox.repo, ".dev.json", &hooks.to_string());
+
+    // Neither --yes nor the lack of a terminal approves them
+    sandbox
+        .dev_in(&sandbox.root)
+        .args(["init", "demo", "--clone"])
+        .arg(&sandbox.repo)
+        .args(["--no-devbox", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "has post_init hooks that haven't been approved",
+        ))
+        .stdout(predicate::str::contains("Skipping post_init hooks"))
+        .stdout(predicate::str::contains("Skipping pre_open hooks"));
+
+    assert!(sandbox.root.join("dev/demo").exists());
+    assert!(!pwned.exists());
+    assert!(!sandbox.config_dir.join(TrustedHooks::FILE_NAME).exists());
+}
+
+#[test]
+fn changed_hooks_need_approval_again() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+    commit(
+        &feature,
+        ".dev.json",
+        &json!({ "hooks": { "post_switch": ["echo one >> ran"] } }).to_string(),
+    );
+    approve_hooks(&sandbox, &feature);
+    commit(
+        &feature,
+        ".dev.json",
+        &json!({ "hooks": { "post_switch": ["echo two >> ran"] } }).to_string(),
+    );
+
+    sandbox
+        .dev()
+        .arg("feature")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipping post_switch hooks"));
+
+    assert!(!feature.join("ran").exists());
+}
+
+#[test]
+fn failing_pre_remove_hook_keeps_worktree() {
+    let sandbox = Sandbox::new();
+    sandbox.set_config("hooks", json!({ "pre_remove": ["exit 3"] }));
+    let feature = sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--delete-branch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "pre_remove hook 'exit 3' failed: exit code 3",
+        ));
+    assert!(feature.exists());
+
+    // --no-hooks is the way out
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--delete-branch", "--no-hooks"])
+        .assert()
+        .success();
+    assert!(!feature.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "feature"]), "");
+}
+
+#[test]
+fn hook_set_to_warn_does_not_stop_command() {
+    let sandbox = Sandbox::new();
+    sandbox.set_config(
+        "hooks",
+        json!({ "post_create": [{ "run": "false", "on_failure": "warn" }] }),
+    );
+
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("post_create hook 'false' failed"))
+        .stdout(predicate::str::contains("Ready to work on 'feature'"));
+}
+
+#[test]
+fn hook_is_killed_after_timeout() {
+    let sandbox = Sandbox::new();
+    sandbox.set_config(
+        "hooks",
+        json!({ "post_create": [{ "run": "sleep 10", "timeout": 1 }] }),
+    );
+
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .timeout(std::time::Duration::from_secs(8))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("timed out after 1s"));
+}
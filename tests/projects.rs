@@ -0,0 +1,114 @@
+//! Project registry and configuration end to end
+
+mod common;
+
+use predicates::prelude::*;
+use serde_json::Value;
+
+use common::Sandbox;
+
+#[test]
+fn config_set_then_get() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .args(["config", "--set", "editor=nvim"])
+        .assert()
+        .success();
+
+    sandbox
+        .dev()
+        .args(["config", "--get", "editor"])
+        .assert()
+        .success()
+        .stdout("nvim\n");
+
+    // Written to the config dir from DEV_CONFIG_DIR, not the real one
+    let config = std::fs::read_to_string(sandbox.config_dir.join("config.json")).unwrap();
+    assert!(config.contains("nvim"));
+}
+
+#[test]
+fn config_rejects_unknown_key() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .args(["config", "--get", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key: nope"));
+}
+
+#[test]
+fn config_editor_window_takes_new_or_reuse() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .args(["config", "--get", "editor_window"])
+        .assert()
+        .success()
+        .stdout("new\n");
+
+    sandbox
+        .dev()
+        .args(["config", "--set", "editor_window=reuse"])
+        .assert()
+        .success();
+
+    sandbox
+        .dev()
+        .args(["config", "--get", "editor_window"])
+        .assert()
+        .success()
+        .stdout("reuse\n");
+
+    sandbox
+        .dev()
+        .args(["config", "--set", "editor_window=tab"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown editor window: tab"));
+}
+
+#[test]
+fn init_existing_registers_project() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev_in(&sandbox.root)
+        .args(["init", "demo", "--existing", "repo", "--no-devbox"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Project 'demo' registered"));
+
+    sandbox
+        .dev()
+        .args(["projects", "--format", "plain"])
+        .assert()
+        .success()
+        .stdout(format!("demo\t{}\n", sandbox.repo.display()));
+
+    let output = sandbox
+        .dev()
+        .args(["projects", "--format", "json"])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["kind"], "projects");
+    assert_eq!(json["data"][0]["name"], "demo");
+}
+
+#[test]
+fn open_unknown_project_fails() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev_in(&sandbox.home)
+        .arg("nope")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Project 'nope' not found"));
+}
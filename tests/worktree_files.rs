@@ -0,0 +1,142 @@
+//! Untracked files brought into worktrees by `--create` and `sync-files`
+
+mod common;
+
+use predicates::prelude::*;
+use serde_json::json;
+
+use common::{commit, git, Sandbox};
+
+/// A registered repository with gitignored files in its main worktree
+fn sandbox_with_files() -> Sandbox {
+    let sandbox = Sandbox::new();
+    commit(&sandbox.repo, ".gitignore", ".env\n.env.local\ncerts/\n");
+    commit(&sandbox.repo, ".env.example", "A=\n");
+
+    std::fs::write(sandbox.repo.join(".env"), "A=1\n").unwrap();
+    std::fs::write(sandbox.repo.join(".env.local"), "DB=app_{{DEV_BRANCH}}\n").unwrap();
+    std::fs::create_dir(sandbox.repo.join("certs")).unwrap();
+    std::fs::write(sandbox.repo.join("certs/dev.pem"), "cert\n").unwrap();
+
+    sandbox.register(json!({
+        "worktree_files": [
+            { "pattern": ".env.local", "mode": "template" },
+            ".env*",
+            { "pattern": "certs", "mode": "symlink" },
+        ]
+    }));
+    sandbox
+}
+
+#[test]
+fn create_brings_untracked_files() {
+    let sandbox = sandbox_with_files();
+
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Copied .env"))
+        .stdout(predicate::str::contains(
+            "Skipped .env.example (tracked by git)",
+        ));
+
+    let feature = sandbox.root.join("feature");
+    assert_eq!(
+        std::fs::read_to_string(feature.join(".env")).unwrap(),
+        "A=1\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(feature.join(".env.local")).unwrap(),
+        "DB=app_feature\n"
+    );
+    assert_eq!(
+        std::fs::read_link(feature.join("certs")).unwrap(),
+        sandbox.repo.join("certs")
+    );
+    assert_eq!(
+        std::fs::read_to_string(feature.join(".env.example")).unwrap(),
+        "A=\n"
+    );
+}
+
+#[test]
+fn sync_files_propagates_changes() {
+    let sandbox = sandbox_with_files();
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success();
+    let env = sandbox.root.join("feature/.env");
+
+    std::fs::write(sandbox.repo.join(".env"), "A=2\n").unwrap();
+
+    sandbox
+        .dev()
+        .args(["sync-files", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("would update .env"))
+        .stdout(predicate::str::contains("1 file(s) would be updated"));
+    assert_eq!(std::fs::read_to_string(&env).unwrap(), "A=1\n");
+
+    sandbox
+        .dev()
+        .args(["sync-files", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s) updated"));
+    assert_eq!(std::fs::read_to_string(&env).unwrap(), "A=2\n");
+
+    sandbox
+        .dev()
+        .arg("sync-files")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All worktree files are up to date",
+        ));
+}
+
+#[test]
+fn sync_files_skips_files_the_target_branch_tracks() {
+    let sandbox = sandbox_with_files();
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success();
+    let feature = sandbox.root.join("feature");
+
+    // Committed on the branch, though only local in the main worktree
+    std::fs::write(feature.join(".env"), "A=feature\n").unwrap();
+    git(&feature, &["add", "-f", ".env"]);
+    git(&feature, &["commit", "-q", "-m", "env"]);
+    std::fs::write(sandbox.repo.join(".env"), "A=2\n").unwrap();
+
+    sandbox
+        .dev()
+        .args(["sync-files", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped .env (tracked by git)"));
+
+    assert_eq!(
+        std::fs::read_to_string(feature.join(".env")).unwrap(),
+        "A=feature\n"
+    );
+}
+
+#[test]
+fn sync_files_without_project_does_nothing() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .arg("sync-files")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("isn't registered"));
+}
@@ -0,0 +1,401 @@
+//! Worktree commands end to end against throwaway repositories
+
+mod common;
+
+use predicates::prelude::*;
+use serde_json::{json, Value};
+
+use common::{commit, git, Sandbox};
+
+#[test]
+fn list_plain_shows_each_worktree() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["--list", "--format", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "main\t{}",
+            sandbox.repo.display()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "feature\t{}",
+            feature.display()
+        )));
+}
+
+#[test]
+fn list_json_is_versioned() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    let output = sandbox
+        .dev()
+        .args(["--list", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    assert_eq!(json["kind"], "worktrees");
+
+    let worktrees = json["data"]["worktrees"].as_array().unwrap();
+    assert_eq!(worktrees.len(), 2);
+    assert_eq!(worktrees[0]["branch"], "main");
+    assert_eq!(worktrees[0]["is_current"], true);
+    assert_eq!(worktrees[1]["branch"], "feature");
+    assert_eq!(worktrees[1]["status"]["state"], "clean");
+}
+
+#[test]
+fn create_then_switch() {
+    let sandbox = Sandbox::new();
+    let cd_file = sandbox.root.join("cd");
+
+    sandbox
+        .dev()
+        .args(["--create", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ready to work on 'feature'"));
+
+    let feature = sandbox.root.join("feature");
+    assert_eq!(git(&feature, &["branch", "--show-current"]), "feature");
+
+    // The shell wrapper is told where to cd
+    sandbox
+        .dev()
+        .arg("feature")
+        .env("DEV_CD_FILE", &cd_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to 'feature'"));
+
+    let contents = std::fs::read_to_string(&cd_file).unwrap();
+    assert_eq!(contents.lines().next(), feature.to_str());
+}
+
+#[test]
+fn create_existing_branch_fails() {
+    let sandbox = Sandbox::new();
+    git(&sandbox.repo, &["branch", "topic"]);
+
+    sandbox
+        .dev()
+        .args(["--create", "topic"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("topic"));
+}
+
+#[test]
+fn create_without_terminal_asks_for_flag() {
+    let sandbox = Sandbox::new().with_clone();
+
+    sandbox
+        .dev()
+        .args(["--create", "remote-only"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Input required: pass --track-remote or --diverge",
+        ));
+
+    sandbox
+        .dev()
+        .args(["--create", "remote-only", "--track-remote"])
+        .assert()
+        .success();
+
+    let path = sandbox.root.join("remote-only");
+    assert_eq!(
+        git(&path, &["rev-parse", "--abbrev-ref", "@{upstream}"]),
+        "origin/remote-only"
+    );
+}
+
+#[test]
+fn create_flags_require_create() {
+    let sandbox = Sandbox::new();
+
+    sandbox.dev().arg("--track-remote").assert().failure();
+}
+
+#[test]
+fn switch_to_unknown_branch_fails() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .arg("missing")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No worktree found for branch 'missing'",
+        ));
+}
+
+#[test]
+fn remove_without_terminal_changes_nothing() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["remove", "feature"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "pass --delete-branch or --keep-branch",
+        ));
+
+    assert!(feature.exists());
+}
+
+#[test]
+fn remove_deletes_worktree_and_branch() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--delete-branch"])
+        .assert()
+        .success();
+
+    assert!(!feature.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "feature"]), "");
+}
+
+#[test]
+fn remove_with_yes_keeps_branches() {
+    let sandbox = Sandbox::new().with_clone();
+    let feature = sandbox.add_worktree("feature");
+    git(&feature, &["push", "-q", "-u", "origin", "feature"]);
+
+    sandbox
+        .dev()
+        .args(["remove", "feature", "--yes"])
+        .assert()
+        .success();
+
+    // Deleting a branch is never the default; it takes its own flag
+    assert!(!feature.exists());
+    assert_eq!(
+        git(&sandbox.repo, &["branch", "--list", "feature"]),
+        "feature"
+    );
+    let origin = sandbox.root.join("repo");
+    assert_eq!(git(&origin, &["branch", "--list", "feature"]), "feature");
+}
+
+#[test]
+fn cleanup_with_yes_removes_merged_worktrees() {
+    let sandbox = Sandbox::new();
+    let merged = |branch: &str| {
+        let path = sandbox.add_worktree(branch);
+        commit(&path, &format!("{}.txt", branch), "merged\n");
+        git(&sandbox.repo, &["merge", "-q", "--ff-only", branch]);
+        path
+    };
+    let done = merged("done");
+
+    sandbox
+        .dev()
+        .arg("--cleanup")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Input required: pass --yes"));
+    assert!(done.exists());
+
+    sandbox
+        .dev()
+        .args(["--cleanup", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleanup complete: 1 removed"));
+
+    // Deleting branches takes its own flag
+    assert!(!done.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "done"]), "done");
+
+    let also_done = merged("also-done");
+    sandbox
+        .dev()
+        .args(["--cleanup", "--yes", "--delete-branches"])
+        .assert()
+        .success();
+
+    assert!(!also_done.exists());
+    assert_eq!(git(&sandbox.repo, &["branch", "--list", "also-done"]), "");
+}
+
+#[test]
+fn cleanup_with_nothing_to_do() {
+    let sandbox = Sandbox::new();
+
+    sandbox
+        .dev()
+        .arg("--cleanup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All worktrees are in good condition",
+        ));
+}
+
+#[test]
+fn migrate_worktrees_moves_the_current_one_last() {
+    let sandbox = Sandbox::new();
+    let first = sandbox.add_worktree("first");
+    let second = sandbox.add_worktree("second");
+    std::fs::create_dir(second.join("src")).unwrap();
+    sandbox.set_config("worktree_path_template", json!("worktrees"));
+    let cd_file = sandbox.root.join("cd");
+
+    sandbox
+        .dev_in(&second.join("src"))
+        .args(["migrate-worktrees", "--yes"])
+        .env("DEV_CD_FILE", &cd_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Migration complete: 2 moved"));
+
+    let worktrees = git(&sandbox.repo, &["worktree", "list", "--porcelain"]);
+    assert!(!first.exists() && !second.exists());
+    assert!(!worktrees.contains(first.to_str().unwrap()));
+    assert!(!worktrees.contains(second.to_str().unwrap()));
+
+    // The shell follows the worktree it was in
+    let moved = worktrees
+        .lines()
+        .filter_map(|line| line.strip_prefix("worktree "))
+        .find(|path| path.ends_with("second"))
+        .map(std::path::PathBuf::from)
+        .unwrap();
+    let contents = std::fs::read_to_string(&cd_file).unwrap();
+    assert_eq!(contents.lines().next(), moved.join("src").to_str());
+}
+
+#[test]
+fn env_prints_worktree_variables() {
+    let sandbox = Sandbox::new();
+    let feature = sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["env", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("export DEV_BRANCH='feature'"))
+        .stdout(predicate::str::contains(format!(
+            "export DEV_WORKTREE='{}'",
+            feature.display()
+        )));
+}
+
+#[cfg(unix)]
+#[test]
+fn devbox_shell_gets_worktree_environment() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    sandbox.register(json!({ "uses_devbox": true, "env": { "API_URL": "http://localhost" } }));
+    let feature = sandbox.add_worktree("feature");
+    std::fs::write(feature.join("devbox.json"), "{}\n").unwrap();
+
+    // A devbox that records the environment it was started with
+    let bin = sandbox.root.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let log = sandbox.root.join("devbox.env");
+    let devbox = bin.join("devbox");
+    std::fs::write(
+        &devbox,
+        format!(
+            "#!/bin/sh\necho \"$(pwd) $*\" > {0}\nenv >> {0}\n",
+            log.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&devbox, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    sandbox
+        .dev_in(&feature)
+        .arg("__devbox-shell")
+        .env(
+            "PATH",
+            format!("{}:{}", bin.display(), std::env::var("PATH").unwrap()),
+        )
+        .env_remove("DEVBOX_SHELL_ENABLED")
+        .assert()
+        .success();
+
+    let recorded = std::fs::read_to_string(&log).unwrap();
+    let mut lines = recorded.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("{} shell", feature.display()).as_str())
+    );
+    assert!(recorded.contains("API_URL=http://localhost\n"));
+    assert!(recorded.contains("DEV_BRANCH=feature\n"));
+    assert!(recorded.contains(&format!("DEV_WORKTREE={}\n", feature.display())));
+}
+
+#[test]
+fn complete_offers_worktree_branches() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["__complete", "fea"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feature"));
+}
+
+#[test]
+fn complete_offers_branches_for_install() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["__complete", "install", "fea"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feature"));
+}
+
+#[test]
+fn complete_offers_branches_for_sync_files() {
+    let sandbox = Sandbox::new();
+    sandbox.add_worktree("feature");
+
+    sandbox
+        .dev()
+        .args(["__complete", "sync-files", "fea"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feature"));
+}
+
+#[test]
+fn outside_a_repository_reports_error_code() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox
+        .dev_in(&sandbox.home)
+        .args(["--list", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    assert_eq!(json["error"]["code"], "not_git_repository");
+}